@@ -0,0 +1,426 @@
+//! Summaries of the records over a range of dates, broken down by day, week or month.
+//!
+//! Records are placed on days in the same way as everywhere else: either in the timezone of the
+//! range, or in the timezone that each record was written in. See `timezone::TimezoneDisplay`.
+
+use chrono::{Datelike, TimeZone};
+use dimensioned::si::{Kilogram, Meter, MeterPerSecond, Second, KG, M, S};
+use emseries::Recordable;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::activity::ActivityId;
+use crate::timezone::TimezoneDisplay;
+use crate::TraxRecord;
+
+/// How finely to break down a range of dates.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Period {
+    Day,
+    /// Weeks start on Monday.
+    Week,
+    Month,
+    /// The whole range as a single period.
+    Range,
+}
+
+/// Totals for one time/distance activity.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ActivityTotals {
+    pub workouts: usize,
+    pub distance: Meter<f64>,
+    pub duration: Second<f64>,
+    /// The distance of only those workouts that recorded a duration as well.
+    pub paced_distance: Meter<f64>,
+    /// The duration of only those workouts that recorded a distance as well.
+    pub paced_duration: Second<f64>,
+}
+
+impl Default for ActivityTotals {
+    fn default() -> ActivityTotals {
+        ActivityTotals {
+            workouts: 0,
+            distance: 0. * M,
+            duration: 0. * S,
+            paced_distance: 0. * M,
+            paced_duration: 0. * S,
+        }
+    }
+}
+
+impl ActivityTotals {
+    /// The average speed over the workouts that recorded both a distance and a duration.
+    pub fn speed(&self) -> Option<MeterPerSecond<f64>> {
+        if self.paced_distance > 0. * M && self.paced_duration > 0. * S {
+            Some(self.paced_distance / self.paced_duration)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct WeightSummary {
+    pub count: usize,
+    pub min: Kilogram<f64>,
+    pub max: Kilogram<f64>,
+    pub mean: Kilogram<f64>,
+    /// The earliest and latest weights in the period, to show the change over the period.
+    pub first: Kilogram<f64>,
+    pub last: Kilogram<f64>,
+}
+
+impl WeightSummary {
+    fn from_weights(
+        mut weights: Vec<(chrono::DateTime<chrono_tz::Tz>, Kilogram<f64>)>,
+    ) -> Option<WeightSummary> {
+        weights.sort_by_key(|(time, _)| *time);
+        let first = weights.first()?.1;
+        let last = weights.last()?.1;
+        let (min, max, sum) =
+            weights
+                .iter()
+                .fold((first, first, 0. * KG), |(min, max, sum), &(_, weight)| {
+                    (
+                        if weight < min { weight } else { min },
+                        if weight > max { weight } else { max },
+                        sum + weight,
+                    )
+                });
+        Some(WeightSummary {
+            count: weights.len(),
+            min,
+            max,
+            mean: sum / weights.len() as f64,
+            first,
+            last,
+        })
+    }
+}
+
+/// The aggregates for one period. `start` and `end` are both included, and are cut off at the
+/// ends of the range being summarized.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Summary {
+    pub start: chrono::Date<chrono_tz::Tz>,
+    pub end: chrono::Date<chrono_tz::Tz>,
+    pub time_distance: BTreeMap<ActivityId, ActivityTotals>,
+    /// Total repetitions for each set/rep activity.
+    pub reps: BTreeMap<ActivityId, u32>,
+    pub steps: u32,
+    /// The number of days which have a step count.
+    pub step_days: usize,
+    pub weight: Option<WeightSummary>,
+}
+
+impl Summary {
+    fn new(start: chrono::Date<chrono_tz::Tz>) -> Summary {
+        Summary {
+            start,
+            end: start,
+            time_distance: BTreeMap::new(),
+            reps: BTreeMap::new(),
+            steps: 0,
+            step_days: 0,
+            weight: None,
+        }
+    }
+
+    /// The number of days in the period.
+    pub fn days(&self) -> i64 {
+        (self.end - self.start).num_days() + 1
+    }
+
+    /// The average step count over the days that have a step count.
+    pub fn mean_steps(&self) -> Option<f64> {
+        if self.step_days > 0 {
+            Some(f64::from(self.steps) / self.step_days as f64)
+        } else {
+            None
+        }
+    }
+}
+
+pub(crate) fn period_start(
+    period: Period,
+    date: chrono::Date<chrono_tz::Tz>,
+) -> chrono::Date<chrono_tz::Tz> {
+    match period {
+        Period::Day | Period::Range => date,
+        Period::Week => {
+            date - chrono::Duration::days(i64::from(date.weekday().num_days_from_monday()))
+        }
+        Period::Month => date.timezone().ymd(date.year(), date.month(), 1),
+    }
+}
+
+/// Summarize the records which fall between `start` and `end`, inclusive. The timezone of `start`
+/// is the home timezone for `display`. Every period in the range gets a summary, even when it has
+/// no records, so that the result can be charted directly. Records outside of the range are
+/// ignored.
+pub fn summarize<'a, I>(
+    records: I,
+    start: chrono::Date<chrono_tz::Tz>,
+    end: chrono::Date<chrono_tz::Tz>,
+    period: Period,
+    display: TimezoneDisplay,
+) -> Vec<Summary>
+where
+    I: IntoIterator<Item = &'a TraxRecord>,
+{
+    let timezone = start.timezone();
+    let mut summaries: Vec<Summary> = Vec::new();
+    let mut index: HashMap<chrono::NaiveDate, usize> = HashMap::new();
+
+    let mut current = start;
+    while current <= end {
+        match summaries.last_mut() {
+            Some(summary)
+                if period == Period::Range
+                    || period_start(period, summary.start) == period_start(period, current) =>
+            {
+                summary.end = current
+            }
+            _ => summaries.push(Summary::new(current)),
+        }
+        index.insert(current.naive_local(), summaries.len() - 1);
+        current = current.succ();
+    }
+
+    let mut weights = vec![Vec::new(); summaries.len()];
+    let mut step_days: Vec<HashSet<chrono::NaiveDate>> = vec![HashSet::new(); summaries.len()];
+    for record in records {
+        let time = record.timestamp().0.with_timezone(&timezone);
+        let date = display.date(&record.timestamp(), &timezone);
+        let idx = match index.get(&date) {
+            Some(idx) => *idx,
+            None => continue,
+        };
+        let summary = &mut summaries[idx];
+        match record {
+            TraxRecord::TimeDistance(ref rec) => {
+                let totals = summary
+                    .time_distance
+                    .entry(rec.activity.clone())
+                    .or_default();
+                totals.workouts += 1;
+                if let Some(distance) = rec.distance {
+                    totals.distance += distance;
+                }
+                if let Some(duration) = rec.duration {
+                    totals.duration += duration;
+                }
+                if let (Some(distance), Some(duration)) = (rec.distance, rec.duration) {
+                    totals.paced_distance += distance;
+                    totals.paced_duration += duration;
+                }
+            }
+            TraxRecord::SetRep(ref rec) => {
+                *summary.reps.entry(rec.activity.clone()).or_insert(0) +=
+                    rec.sets.iter().sum::<u32>();
+            }
+            TraxRecord::Steps(ref rec) => {
+                summary.steps += rec.steps;
+                step_days[idx].insert(date);
+            }
+            TraxRecord::Weight(ref rec) => weights[idx].push((time, rec.weight)),
+            _ => (),
+        }
+    }
+
+    for ((summary, weights), days) in summaries.iter_mut().zip(weights).zip(step_days) {
+        summary.weight = WeightSummary::from_weights(weights);
+        summary.step_days = days.len();
+    }
+
+    summaries
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::setrep::SetRepRecord;
+    use crate::timedistance::{ActivityType, TimeDistanceRecord};
+    use chrono_tz::America::New_York;
+    use chrono_tz::Asia::Tokyo;
+    use chrono_tz::Etc::UTC;
+    use emseries::DateTimeTz;
+
+    fn records() -> Vec<TraxRecord> {
+        vec![
+            TraxRecord::weight(
+                DateTimeTz(New_York.ymd(2020, 3, 2).and_hms(7, 0, 0)),
+                85. * KG,
+            ),
+            TraxRecord::weight(
+                DateTimeTz(New_York.ymd(2020, 3, 4).and_hms(7, 0, 0)),
+                84. * KG,
+            ),
+            TraxRecord::weight(
+                DateTimeTz(New_York.ymd(2020, 3, 9).and_hms(7, 0, 0)),
+                83.5 * KG,
+            ),
+            TraxRecord::steps(DateTimeTz(New_York.ymd(2020, 3, 2).and_hms(0, 0, 0)), 6000),
+            TraxRecord::steps(DateTimeTz(New_York.ymd(2020, 3, 3).and_hms(0, 0, 0)), 9000),
+            TraxRecord::TimeDistance(TimeDistanceRecord::new(
+                DateTimeTz(New_York.ymd(2020, 3, 3).and_hms(18, 0, 0)),
+                ActivityType::Running.into(),
+                Some(5000. * M),
+                Some(1500. * S),
+                None,
+            )),
+            // Late in the evening of March 8th in New York, which is already the 9th in UTC.
+            TraxRecord::TimeDistance(TimeDistanceRecord::new(
+                DateTimeTz(UTC.ymd(2020, 3, 9).and_hms(3, 0, 0)),
+                ActivityType::Running.into(),
+                Some(3000. * M),
+                None,
+                None,
+            )),
+            TraxRecord::SetRep(
+                SetRepRecord::new(
+                    DateTimeTz(New_York.ymd(2020, 3, 4).and_hms(12, 0, 0)),
+                    crate::setrep::ActivityType::Pushups.into(),
+                    vec![20, 15, 10],
+                    None,
+                )
+                .unwrap(),
+            ),
+        ]
+    }
+
+    #[test]
+    fn it_summarizes_by_day() {
+        let records = records();
+        let days = summarize(
+            records.iter(),
+            New_York.ymd(2020, 3, 1),
+            New_York.ymd(2020, 3, 9),
+            Period::Day,
+            TimezoneDisplay::Home,
+        );
+        assert_eq!(days.len(), 9);
+        assert_eq!(days[0].start, New_York.ymd(2020, 3, 1));
+        assert_eq!(days[0], Summary::new(New_York.ymd(2020, 3, 1)));
+        assert_eq!(days[2].steps, 9000);
+        assert_eq!(
+            days[2].time_distance[&ActivityType::Running.into()].speed(),
+            Some(5000. * M / (1500. * S))
+        );
+        assert_eq!(
+            days[3].reps[&crate::setrep::ActivityType::Pushups.into()],
+            45
+        );
+        assert_eq!(
+            days[7].time_distance[&ActivityType::Running.into()].distance,
+            3000. * M
+        );
+        assert_eq!(
+            days[7].time_distance[&ActivityType::Running.into()].speed(),
+            None
+        );
+    }
+
+    #[test]
+    fn it_summarizes_by_week_and_month() {
+        let records = records();
+        let weeks = summarize(
+            records.iter(),
+            New_York.ymd(2020, 2, 29),
+            New_York.ymd(2020, 3, 10),
+            Period::Week,
+            TimezoneDisplay::Home,
+        );
+        assert_eq!(
+            weeks
+                .iter()
+                .map(|w| (w.start, w.end))
+                .collect::<Vec<(chrono::Date<chrono_tz::Tz>, chrono::Date<chrono_tz::Tz>)>>(),
+            vec![
+                (New_York.ymd(2020, 2, 29), New_York.ymd(2020, 3, 1)),
+                (New_York.ymd(2020, 3, 2), New_York.ymd(2020, 3, 8)),
+                (New_York.ymd(2020, 3, 9), New_York.ymd(2020, 3, 10)),
+            ]
+        );
+
+        let running = &weeks[1].time_distance[&ActivityType::Running.into()];
+        assert_eq!(running.workouts, 2);
+        assert_eq!(running.distance, 8000. * M);
+        assert_eq!(running.duration, 1500. * S);
+        assert_eq!(weeks[1].steps, 15000);
+        assert_eq!(weeks[1].mean_steps(), Some(7500.));
+        assert_eq!(
+            weeks[1].weight,
+            Some(WeightSummary {
+                count: 2,
+                min: 84. * KG,
+                max: 85. * KG,
+                mean: 84.5 * KG,
+                first: 85. * KG,
+                last: 84. * KG,
+            })
+        );
+
+        let months = summarize(
+            records.iter(),
+            New_York.ymd(2020, 2, 29),
+            New_York.ymd(2020, 3, 10),
+            Period::Month,
+            TimezoneDisplay::Home,
+        );
+        assert_eq!(months.len(), 2);
+        assert_eq!(months[0].days(), 1);
+        assert_eq!(months[1].days(), 10);
+        assert_eq!(months[1].weight.as_ref().map(|w| w.count), Some(3));
+
+        let whole = summarize(
+            records.iter(),
+            New_York.ymd(2020, 2, 29),
+            New_York.ymd(2020, 3, 10),
+            Period::Range,
+            TimezoneDisplay::Home,
+        );
+        assert_eq!(whole.len(), 1);
+        assert_eq!(whole[0].days(), 11);
+        assert_eq!(whole[0].weight.as_ref().map(|w| w.last), Some(83.5 * KG));
+    }
+
+    #[test]
+    fn it_summarizes_in_the_timezone_that_records_were_written_in() {
+        // 07:00 on the 10th in Tokyo is still the 9th in New York.
+        let records = [TraxRecord::TimeDistance(TimeDistanceRecord::new(
+            DateTimeTz(Tokyo.ymd(2020, 3, 10).and_hms(7, 0, 0)),
+            ActivityType::Running.into(),
+            Some(5000. * M),
+            None,
+            None,
+        ))];
+        let running = |days: &[Summary]| -> Vec<usize> {
+            days.iter()
+                .map(|day| {
+                    day.time_distance
+                        .get(&ActivityType::Running.into())
+                        .map(|totals| totals.workouts)
+                        .unwrap_or(0)
+                })
+                .collect()
+        };
+
+        let home = summarize(
+            records.iter(),
+            New_York.ymd(2020, 3, 9),
+            New_York.ymd(2020, 3, 10),
+            Period::Day,
+            TimezoneDisplay::Home,
+        );
+        assert_eq!(running(&home), vec![1, 0]);
+
+        let recorded = summarize(
+            records.iter(),
+            New_York.ymd(2020, 3, 9),
+            New_York.ymd(2020, 3, 10),
+            Period::Day,
+            TimezoneDisplay::Recorded,
+        );
+        assert_eq!(running(&recorded), vec![0, 1]);
+    }
+}
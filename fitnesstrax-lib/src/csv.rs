@@ -0,0 +1,704 @@
+//! CSV export and import, with one table per record type.
+//!
+//! Every table starts with a `date` column, which holds an RFC 3339 timestamp with its UTC offset,
+//! and a `timezone` column, which holds the name of the timezone the record was made in. Values
+//! are in SI units, as noted in the column names, and activities are written by name. Records with
+//! several sets get one row per set, numbered from 1; on import, a row whose set number carries on
+//! from the row before, with the same date and activity, gets joined back into the same record.
+
+use chrono::DateTime;
+use dimensioned::si::{KG, M, S};
+use emseries::{DateTimeTz, Recordable};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::Path;
+
+use crate::activity::{ActivityKind, ActivityRegistry};
+use crate::error::{Error, Result};
+use crate::import::{import_records, ImportReport, Parsed, Unmapped};
+use crate::types::{comments, repduration, setrep, steps, timedistance, weight, weightedset};
+use crate::{Trax, TraxRecord};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Table {
+    Comments,
+    RepDuration,
+    SetRep,
+    Steps,
+    TimeDistance,
+    Weight,
+    WeightedSet,
+}
+
+impl Table {
+    pub fn all() -> Vec<Table> {
+        vec![
+            Table::Comments,
+            Table::RepDuration,
+            Table::SetRep,
+            Table::Steps,
+            Table::TimeDistance,
+            Table::Weight,
+            Table::WeightedSet,
+        ]
+    }
+
+    pub fn file_name(self) -> &'static str {
+        match self {
+            Table::Comments => "comments.csv",
+            Table::RepDuration => "rep_duration.csv",
+            Table::SetRep => "set_rep.csv",
+            Table::Steps => "steps.csv",
+            Table::TimeDistance => "time_distance.csv",
+            Table::Weight => "weight.csv",
+            Table::WeightedSet => "weighted_set.csv",
+        }
+    }
+
+    fn columns(self) -> &'static [&'static str] {
+        match self {
+            Table::Comments => &["date", "timezone", "comments"],
+            Table::RepDuration => &[
+                "date",
+                "timezone",
+                "activity",
+                "set",
+                "duration_s",
+                "comments",
+            ],
+            Table::SetRep => &["date", "timezone", "activity", "set", "reps", "comments"],
+            Table::Steps => &["date", "timezone", "steps"],
+            Table::TimeDistance => &[
+                "date",
+                "timezone",
+                "activity",
+                "distance_m",
+                "duration_s",
+                "comments",
+            ],
+            Table::Weight => &["date", "timezone", "weight_kg"],
+            Table::WeightedSet => &[
+                "date", "timezone", "activity", "set", "reps", "load_kg", "rpe", "comments",
+            ],
+        }
+    }
+
+    fn of(record: &TraxRecord) -> Table {
+        match record {
+            TraxRecord::Comments(_) => Table::Comments,
+            TraxRecord::RepDuration(_) => Table::RepDuration,
+            TraxRecord::SetRep(_) => Table::SetRep,
+            TraxRecord::Steps(_) => Table::Steps,
+            TraxRecord::TimeDistance(_) => Table::TimeDistance,
+            TraxRecord::Weight(_) => Table::Weight,
+            TraxRecord::WeightedSet(_) => Table::WeightedSet,
+        }
+    }
+}
+
+fn timestamp_columns(timestamp: &DateTimeTz) -> Vec<String> {
+    vec![
+        timestamp.0.to_rfc3339(),
+        String::from(timestamp.0.timezone().name()),
+    ]
+}
+
+fn activity_column(id: &crate::activity::ActivityId, activities: &ActivityRegistry) -> String {
+    activities
+        .get(id)
+        .map(|activity| activity.name.clone())
+        .unwrap_or_else(|| id.to_string())
+}
+
+fn optional<A: ToString>(val: Option<A>) -> String {
+    val.map(|v| v.to_string()).unwrap_or_default()
+}
+
+fn set_rows(
+    timestamp: &DateTimeTz,
+    activity: String,
+    sets: Vec<Vec<String>>,
+    empty_set: Vec<String>,
+    comments: &Option<String>,
+) -> Vec<Vec<String>> {
+    let row = |set: String, fields: Vec<String>| {
+        let mut row = timestamp_columns(timestamp);
+        row.push(activity.clone());
+        row.push(set);
+        row.extend(fields);
+        row.push(optional(comments.as_ref()));
+        row
+    };
+    if sets.is_empty() {
+        vec![row(String::new(), empty_set)]
+    } else {
+        sets.into_iter()
+            .enumerate()
+            .map(|(idx, fields)| row((idx + 1).to_string(), fields))
+            .collect()
+    }
+}
+
+fn rows(record: &TraxRecord, activities: &ActivityRegistry) -> Vec<Vec<String>> {
+    let mut row = timestamp_columns(&record.timestamp());
+    match record {
+        TraxRecord::Comments(rec) => {
+            row.push(String::from(rec.text()));
+            vec![row]
+        }
+        TraxRecord::RepDuration(rec) => set_rows(
+            &rec.timestamp,
+            activity_column(&rec.activity, activities),
+            rec.sets
+                .iter()
+                .map(|duration| vec![duration.value_unsafe.to_string()])
+                .collect(),
+            vec![String::new()],
+            &rec.comments,
+        ),
+        TraxRecord::SetRep(rec) => set_rows(
+            &rec.timestamp,
+            activity_column(&rec.activity, activities),
+            rec.sets.iter().map(|reps| vec![reps.to_string()]).collect(),
+            vec![String::new()],
+            &rec.comments,
+        ),
+        TraxRecord::Steps(rec) => {
+            row.push(rec.steps.to_string());
+            vec![row]
+        }
+        TraxRecord::TimeDistance(rec) => {
+            row.push(activity_column(&rec.activity, activities));
+            row.push(optional(rec.distance.map(|d| d.value_unsafe)));
+            row.push(optional(rec.duration.map(|d| d.value_unsafe)));
+            row.push(optional(rec.comments.as_ref()));
+            vec![row]
+        }
+        TraxRecord::Weight(rec) => {
+            row.push(rec.weight.value_unsafe.to_string());
+            vec![row]
+        }
+        TraxRecord::WeightedSet(rec) => set_rows(
+            &rec.timestamp,
+            activity_column(&rec.activity, activities),
+            rec.sets
+                .iter()
+                .map(|set| {
+                    vec![
+                        set.reps.to_string(),
+                        set.load.value_unsafe.to_string(),
+                        optional(set.rpe),
+                    ]
+                })
+                .collect(),
+            vec![String::new(), String::new(), String::new()],
+            &rec.comments,
+        ),
+    }
+}
+
+/// Write one table. Records which belong to other tables are skipped.
+pub fn export_table<'a, W: Write, I: IntoIterator<Item = &'a TraxRecord>>(
+    table: Table,
+    records: I,
+    activities: &ActivityRegistry,
+    writer: W,
+) -> Result<()> {
+    let mut writer = csv::Writer::from_writer(writer);
+    writer
+        .write_record(table.columns())
+        .map_err(Error::CSVError)?;
+
+    let mut records: Vec<&TraxRecord> = records
+        .into_iter()
+        .filter(|record| Table::of(record) == table)
+        .collect();
+    records.sort_by_key(|record| record.timestamp());
+
+    for record in records {
+        for row in rows(record, activities) {
+            writer.write_record(&row).map_err(Error::CSVError)?;
+        }
+    }
+    writer.flush().map_err(Error::IOError)
+}
+
+/// Export every record in the series, writing one file per table into `dir`.
+pub fn export(trax: &Trax, dir: &Path) -> Result<()> {
+    let records = trax.all_records()?;
+    for table in Table::all() {
+        let file = fs::File::create(dir.join(table.file_name())).map_err(Error::IOError)?;
+        export_table(
+            table,
+            records.iter().map(|(_, record)| record),
+            trax.activities(),
+            file,
+        )?;
+    }
+    Ok(())
+}
+
+struct Row<'a> {
+    columns: &'a HashMap<String, usize>,
+    record: &'a csv::StringRecord,
+}
+
+impl<'a> Row<'a> {
+    fn get(&self, column: &str) -> &str {
+        self.columns
+            .get(column)
+            .and_then(|idx| self.record.get(*idx))
+            .map(|val| val.trim())
+            .unwrap_or("")
+    }
+
+    fn required(&self, column: &str) -> std::result::Result<&str, String> {
+        match self.get(column) {
+            "" => Err(format!("{} is required", column)),
+            val => Ok(val),
+        }
+    }
+
+    fn parse<A: std::str::FromStr>(&self, column: &str) -> std::result::Result<A, String> {
+        let val = self.required(column)?;
+        val.parse::<A>()
+            .map_err(|_| format!("invalid {}: {}", column, val))
+    }
+
+    fn parse_optional<A: std::str::FromStr>(
+        &self,
+        column: &str,
+    ) -> std::result::Result<Option<A>, String> {
+        match self.get(column) {
+            "" => Ok(None),
+            _ => self.parse(column).map(Some),
+        }
+    }
+
+    fn timestamp(&self) -> std::result::Result<DateTimeTz, String> {
+        let date = self.required("date")?;
+        let timezone = self.parse::<chrono_tz::Tz>("timezone")?;
+        DateTime::parse_from_rfc3339(date)
+            .map(|dt| DateTimeTz(dt.with_timezone(&timezone)))
+            .map_err(|_| format!("invalid date: {}", date))
+    }
+
+    fn activity(
+        &self,
+        kind: ActivityKind,
+        activities: &ActivityRegistry,
+    ) -> std::result::Result<crate::activity::ActivityId, String> {
+        let name = self.required("activity")?;
+        activities
+            .find(kind, name)
+            .map(|activity| activity.id.clone())
+            .ok_or_else(|| format!("unknown activity: {}", name))
+    }
+
+    fn comments(&self) -> Option<String> {
+        match self.get("comments") {
+            "" => None,
+            val => Some(String::from(val)),
+        }
+    }
+
+    /// Rows of the set tables leave the set column empty for records that have no sets.
+    fn has_set(&self) -> bool {
+        !self.get("set").is_empty()
+    }
+}
+
+fn parse_row(
+    table: Table,
+    row: &Row,
+    activities: &ActivityRegistry,
+) -> std::result::Result<TraxRecord, String> {
+    let timestamp = row.timestamp()?;
+    match table {
+        Table::Comments => Ok(TraxRecord::Comments(comments::Comments::new(
+            timestamp,
+            row.required("comments")?,
+        ))),
+        Table::RepDuration => {
+            let mut sets = Vec::new();
+            if row.has_set() {
+                sets.push(row.parse::<f64>("duration_s")? * S);
+            }
+            Ok(TraxRecord::from(repduration::RepDurationRecord::new(
+                timestamp,
+                row.activity(ActivityKind::RepDuration, activities)?,
+                sets,
+                row.comments(),
+            )))
+        }
+        Table::SetRep => {
+            let mut sets = Vec::new();
+            if row.has_set() {
+                sets.push(row.parse::<u32>("reps")?);
+            }
+            setrep::SetRepRecord::new(
+                timestamp,
+                row.activity(ActivityKind::SetRep, activities)?,
+                sets,
+                row.comments(),
+            )
+            .map(TraxRecord::from)
+            .map_err(|_| String::from("reps must be greater than zero"))
+        }
+        Table::Steps => Ok(TraxRecord::from(steps::StepRecord::new(
+            timestamp,
+            row.parse("steps")?,
+        ))),
+        Table::TimeDistance => Ok(TraxRecord::from(timedistance::TimeDistanceRecord::new(
+            timestamp,
+            row.activity(ActivityKind::TimeDistance, activities)?,
+            row.parse_optional::<f64>("distance_m")?.map(|v| v * M),
+            row.parse_optional::<f64>("duration_s")?.map(|v| v * S),
+            row.comments(),
+        ))),
+        Table::Weight => Ok(TraxRecord::from(weight::WeightRecord::new(
+            timestamp,
+            row.parse::<f64>("weight_kg")? * KG,
+        ))),
+        Table::WeightedSet => {
+            let mut sets = Vec::new();
+            if row.has_set() {
+                sets.push(
+                    weightedset::WeightedSet::new(
+                        row.parse("reps")?,
+                        row.parse::<f64>("load_kg")? * KG,
+                        row.parse_optional("rpe")?,
+                    )
+                    .map_err(|_| {
+                        String::from("invalid set: reps must be positive, load must not be negative and rpe must be between 1 and 10")
+                    })?,
+                );
+            }
+            Ok(TraxRecord::from(weightedset::WeightedSetRecord::new(
+                timestamp,
+                row.activity(ActivityKind::WeightedSet, activities)?,
+                sets,
+                row.comments(),
+            )))
+        }
+    }
+}
+
+/// Add the sets of `from` to `into`, if both are the same exercise at the same time. Returns
+/// `from` back if the two records cannot be joined.
+fn join_sets(into: &mut TraxRecord, from: TraxRecord) -> Option<TraxRecord> {
+    match (into, from) {
+        (TraxRecord::RepDuration(a), TraxRecord::RepDuration(b))
+            if a.timestamp == b.timestamp && a.activity == b.activity =>
+        {
+            a.sets.extend(b.sets);
+            None
+        }
+        (TraxRecord::SetRep(a), TraxRecord::SetRep(b))
+            if a.timestamp == b.timestamp && a.activity == b.activity =>
+        {
+            a.sets.extend(b.sets);
+            None
+        }
+        (TraxRecord::WeightedSet(a), TraxRecord::WeightedSet(b))
+            if a.timestamp == b.timestamp && a.activity == b.activity =>
+        {
+            a.sets.extend(b.sets);
+            None
+        }
+        (_, from) => Some(from),
+    }
+}
+
+/// Read one table without touching the series. Rows which fail validation are reported along with
+/// their line number in the file.
+pub fn read_table<R: Read>(
+    table: Table,
+    activities: &ActivityRegistry,
+    reader: R,
+) -> Result<Parsed> {
+    let mut reader = csv::ReaderBuilder::new().flexible(true).from_reader(reader);
+    let mut parsed = Parsed::default();
+
+    let columns: HashMap<String, usize> = reader
+        .headers()
+        .map_err(Error::CSVError)?
+        .iter()
+        .enumerate()
+        .map(|(idx, name)| (String::from(name.trim()), idx))
+        .collect();
+    let missing: Vec<&str> = table
+        .columns()
+        .iter()
+        .filter(|column| **column != "comments" && !columns.contains_key(**column))
+        .cloned()
+        .collect();
+    if !missing.is_empty() {
+        parsed.unmapped.push(Unmapped {
+            line: 1,
+            reason: format!("missing columns: {}", missing.join(", ")),
+        });
+        return Ok(parsed);
+    }
+
+    let mut current: Option<(usize, TraxRecord)> = None;
+    for result in reader.records() {
+        let record = result.map_err(Error::CSVError)?;
+        let line = record
+            .position()
+            .map(|pos| pos.line() as usize)
+            .unwrap_or_default();
+        let row = Row {
+            columns: &columns,
+            record: &record,
+        };
+
+        let parsed_row = parse_row(table, &row, activities)
+            .and_then(|trax_record| Ok((row.parse_optional::<usize>("set")?, trax_record)));
+        match parsed_row {
+            Ok((set, trax_record)) => {
+                /* Two records can share a date and activity, so only a set after the first
+                 * continues the record before it. */
+                let remainder = match (&mut current, set) {
+                    (Some((_, into)), Some(set)) if set > 1 => join_sets(into, trax_record),
+                    _ => Some(trax_record),
+                };
+                if let Some(trax_record) = remainder {
+                    if let Some(done) = current.take() {
+                        parsed.records.push(done);
+                    }
+                    current = Some((line, trax_record));
+                }
+            }
+            Err(reason) => parsed.unmapped.push(Unmapped { line, reason }),
+        }
+    }
+    if let Some(done) = current.take() {
+        parsed.records.push(done);
+    }
+
+    Ok(parsed)
+}
+
+/// Import the tables found in `dir`. Tables which are missing from the directory are skipped.
+/// Records that the series already contains are not added again.
+pub fn import(trax: &mut Trax, dir: &Path) -> Result<Vec<(Table, ImportReport)>> {
+    read(dir, trax.activities())?
+        .into_iter()
+        .map(|(table, parsed)| import_records(trax, parsed).map(|report| (table, report)))
+        .collect()
+}
+
+/// Read every table in a directory without touching the series. Tables which are missing from
+/// the directory are left out.
+pub fn read(dir: &Path, activities: &ActivityRegistry) -> Result<Vec<(Table, Parsed)>> {
+    let mut tables = Vec::new();
+    for table in Table::all() {
+        let path = dir.join(table.file_name());
+        if !path.exists() {
+            continue;
+        }
+        let file = fs::File::open(path).map_err(Error::IOError)?;
+        tables.push((table, read_table(table, activities, file)?));
+    }
+    Ok(tables)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::CleanupFile;
+    use crate::Params;
+    use chrono::TimeZone;
+    use chrono_tz::America::New_York;
+    use std::path::PathBuf;
+
+    fn records() -> Vec<TraxRecord> {
+        let date = DateTimeTz(New_York.ymd(2020, 3, 1).and_hms(18, 0, 0));
+        vec![
+            TraxRecord::weight(date.clone(), 85.2 * KG),
+            TraxRecord::steps(date.clone(), 6500),
+            TraxRecord::timedistance(
+                date.clone(),
+                timedistance::ActivityType::Running.into(),
+                Some(5000. * M),
+                None,
+                Some(String::from("hills, \"lots\" of them")),
+            ),
+            TraxRecord::weightedset(
+                date.clone(),
+                weightedset::ActivityType::Deadlift.into(),
+                vec![
+                    weightedset::WeightedSet::new(5, 100. * KG, Some(8.)).unwrap(),
+                    weightedset::WeightedSet::new(3, 110. * KG, None).unwrap(),
+                ],
+                None,
+            ),
+            TraxRecord::setrep(
+                date.clone(),
+                setrep::ActivityType::Pushups.into(),
+                vec![],
+                None,
+            )
+            .unwrap(),
+            TraxRecord::Comments(comments::Comments::new(date, "A good day")),
+        ]
+    }
+
+    fn round_trip(table: Table, records: &[TraxRecord]) -> Parsed {
+        let activities = ActivityRegistry::default();
+        let mut buf = Vec::new();
+        export_table(table, records, &activities, &mut buf).expect("export should succeed");
+        read_table(table, &activities, buf.as_slice()).expect("read should succeed")
+    }
+
+    #[test]
+    fn it_round_trips_every_table() {
+        let records = records();
+        for table in Table::all() {
+            let parsed = round_trip(table, &records);
+            assert_eq!(parsed.unmapped, vec![]);
+            let expected: Vec<TraxRecord> = records
+                .iter()
+                .filter(|record| Table::of(record) == table)
+                .cloned()
+                .collect();
+            let actual: Vec<TraxRecord> = parsed.records.into_iter().map(|(_, r)| r).collect();
+            assert_eq!(actual, expected);
+        }
+    }
+
+    #[test]
+    fn it_writes_iso_timestamps_and_activity_names() {
+        let activities = ActivityRegistry::default();
+        let mut buf = Vec::new();
+        export_table(Table::WeightedSet, &records(), &activities, &mut buf)
+            .expect("export should succeed");
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            "date,timezone,activity,set,reps,load_kg,rpe,comments
+2020-03-01T18:00:00-05:00,America/New_York,Deadlift,1,5,100,8,
+2020-03-01T18:00:00-05:00,America/New_York,Deadlift,2,3,110,,
+"
+        );
+    }
+
+    #[test]
+    fn it_keeps_records_at_the_same_time_apart() {
+        let date = DateTimeTz(New_York.ymd(2020, 3, 1).and_hms(18, 0, 0));
+        let record = |reps| {
+            TraxRecord::weightedset(
+                date.clone(),
+                weightedset::ActivityType::Deadlift.into(),
+                vec![
+                    weightedset::WeightedSet::new(reps, 100. * KG, None).unwrap(),
+                    weightedset::WeightedSet::new(reps, 110. * KG, None).unwrap(),
+                ],
+                None,
+            )
+        };
+        let records = vec![record(5), record(3)];
+
+        let parsed = round_trip(Table::WeightedSet, &records);
+        assert_eq!(parsed.unmapped, vec![]);
+        assert_eq!(
+            parsed
+                .records
+                .into_iter()
+                .map(|(_, record)| record)
+                .collect::<Vec<TraxRecord>>(),
+            records
+        );
+    }
+
+    #[test]
+    fn it_reports_invalid_rows_by_line() {
+        let activities = ActivityRegistry::default();
+        let table = "date,timezone,activity,distance_m,duration_s,comments
+2020-03-01T18:00:00-05:00,America/New_York,Running,5000,1800,
+2020-03-01T18:00:00-05:00,America/New_York,Unicycling,5000,1800,
+not a date,America/New_York,Running,5000,1800,
+2020-03-01T18:00:00-05:00,Mars/Olympus_Mons,Running,5000,1800,
+2020-03-01T18:00:00-05:00,America/New_York,Cycling,far,1800,
+";
+        let parsed = read_table(Table::TimeDistance, &activities, table.as_bytes())
+            .expect("read should succeed");
+        assert_eq!(parsed.records.len(), 1);
+        assert_eq!(parsed.records[0].0, 2);
+        assert_eq!(
+            parsed.unmapped,
+            vec![
+                Unmapped {
+                    line: 3,
+                    reason: String::from("unknown activity: Unicycling"),
+                },
+                Unmapped {
+                    line: 4,
+                    reason: String::from("invalid date: not a date"),
+                },
+                Unmapped {
+                    line: 5,
+                    reason: String::from("invalid timezone: Mars/Olympus_Mons"),
+                },
+                Unmapped {
+                    line: 6,
+                    reason: String::from("invalid distance_m: far"),
+                },
+            ]
+        );
+
+        let parsed = read_table(Table::Weight, &activities, "date,weight\n".as_bytes())
+            .expect("read should succeed");
+        assert_eq!(
+            parsed.unmapped[0].reason,
+            "missing columns: timezone, weight_kg"
+        );
+    }
+
+    #[test]
+    fn it_exports_and_imports_a_series() {
+        let dir = PathBuf::from("var/it_exports_and_imports_a_series");
+        fs::create_dir_all(&dir).unwrap();
+        let _cleanups: Vec<CleanupFile> = Table::all()
+            .into_iter()
+            .map(|table| CleanupFile(dir.join(table.file_name())))
+            .collect();
+        let _source_cleanup = CleanupFile(PathBuf::from("var/it_exports_a_series.series"));
+        let _dest_cleanup = CleanupFile(PathBuf::from("var/it_imports_a_series.series"));
+
+        let mut source = Trax::create(Params {
+            series_path: PathBuf::from("var/it_exports_a_series.series"),
+        })
+        .unwrap();
+        for record in records() {
+            source.add_record(record).unwrap();
+        }
+        export(&source, &dir).expect("export should succeed");
+
+        let mut dest = Trax::create(Params {
+            series_path: PathBuf::from("var/it_imports_a_series.series"),
+        })
+        .unwrap();
+        let reports = import(&mut dest, &dir).expect("import should succeed");
+        assert_eq!(
+            reports
+                .iter()
+                .map(|(_, report)| report.imported)
+                .sum::<usize>(),
+            6
+        );
+
+        let reports = import(&mut dest, &dir).expect("import should succeed");
+        assert_eq!(
+            reports
+                .iter()
+                .map(|(_, report)| report.duplicates)
+                .sum::<usize>(),
+            6
+        );
+
+        drop(_cleanups);
+        fs::remove_dir(&dir).expect("the export directory should be empty");
+    }
+}
@@ -1,12 +1,32 @@
 use std::error;
 use std::fmt;
+use std::io;
+use std::path::PathBuf;
 use std::result;
 
+use crate::types::activity::ActivityId;
+
 #[derive(Debug)]
 pub enum Error {
+    /// Built-in activities cannot be replaced.
+    BuiltinActivity(ActivityId),
+    CSVError(csv::Error),
+    FITError(String),
     InvalidParameter,
+    IOError(io::Error),
+    JSONError(serde_json::Error),
     NoSeries,
+    /// The series file could not be parsed.
+    SeriesCorrupt(PathBuf, emseries::Error),
     SeriesError(emseries::Error),
+    /// There is already a file where a new series was to be created.
+    SeriesExists(PathBuf),
+    SeriesMissing(PathBuf),
+    SeriesUnreadable(PathBuf, io::Error),
+    UnknownActivity(ActivityId),
+    UnknownTimezoneDisplay(String),
+    UnsupportedFormat(PathBuf),
+    XMLError(quick_xml::Error),
 }
 
 impl From<emseries::Error> for Error {
@@ -18,9 +38,28 @@ impl From<emseries::Error> for Error {
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
+            Error::BuiltinActivity(id) => write!(f, "Activity is built in: {}", id),
+            Error::CSVError(err) => write!(f, "CSV Error: {}", err),
+            Error::FITError(err) => write!(f, "FIT Error: {}", err),
             Error::InvalidParameter => write!(f, "Invalid parameter"),
+            Error::IOError(err) => write!(f, "IO Error: {}", err),
+            Error::JSONError(err) => write!(f, "JSON Error: {}", err),
             Error::NoSeries => write!(f, "Series is not open"),
+            Error::SeriesCorrupt(path, err) => {
+                write!(f, "Series is corrupt: {}: {}", path.display(), err)
+            }
             Error::SeriesError(err) => write!(f, "Series Error: {}", err),
+            Error::SeriesExists(path) => write!(f, "Series already exists: {}", path.display()),
+            Error::SeriesMissing(path) => write!(f, "Series does not exist: {}", path.display()),
+            Error::SeriesUnreadable(path, err) => {
+                write!(f, "Series cannot be read: {}: {}", path.display(), err)
+            }
+            Error::UnknownActivity(id) => write!(f, "Unknown activity: {}", id),
+            Error::UnknownTimezoneDisplay(s) => write!(f, "Unknown timezone display: {}", s),
+            Error::UnsupportedFormat(path) => {
+                write!(f, "Unsupported file format: {}", path.display())
+            }
+            Error::XMLError(err) => write!(f, "XML Error: {}", err),
         }
     }
 }
@@ -28,17 +67,43 @@ impl fmt::Display for Error {
 impl error::Error for Error {
     fn description(&self) -> &str {
         match self {
+            Error::BuiltinActivity(_) => "Activity is built in",
+            Error::CSVError(_) => "CSV Error",
+            Error::FITError(_) => "FIT Error",
             Error::InvalidParameter => "Invalid parameter",
+            Error::IOError(_) => "IO Error",
+            Error::JSONError(_) => "JSON Error",
             Error::NoSeries => "Series is not open",
-            Error::SeriesError(err) => err.description(),
+            Error::SeriesCorrupt(_, _) => "Series is corrupt",
+            Error::SeriesError(_) => "Series Error",
+            Error::SeriesExists(_) => "Series already exists",
+            Error::SeriesMissing(_) => "Series does not exist",
+            Error::SeriesUnreadable(_, _) => "Series cannot be read",
+            Error::UnknownActivity(_) => "Unknown activity",
+            Error::UnknownTimezoneDisplay(_) => "Unknown timezone display",
+            Error::UnsupportedFormat(_) => "Unsupported file format",
+            Error::XMLError(_) => "XML Error",
         }
     }
 
     fn cause(&self) -> Option<&(dyn error::Error + 'static)> {
         match self {
+            Error::BuiltinActivity(_) => None,
+            Error::CSVError(ref err) => Some(err),
+            Error::FITError(_) => None,
             Error::InvalidParameter => None,
+            Error::IOError(ref err) => Some(err),
+            Error::JSONError(ref err) => Some(err),
             Error::NoSeries => None,
+            Error::SeriesCorrupt(_, ref err) => Some(err),
             Error::SeriesError(ref err) => Some(err),
+            Error::SeriesExists(_) => None,
+            Error::SeriesMissing(_) => None,
+            Error::SeriesUnreadable(_, ref err) => Some(err),
+            Error::UnknownActivity(_) => None,
+            Error::UnknownTimezoneDisplay(_) => None,
+            Error::UnsupportedFormat(_) => None,
+            Error::XMLError(ref err) => Some(err),
         }
     }
 }
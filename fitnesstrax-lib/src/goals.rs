@@ -0,0 +1,399 @@
+//! Goals, such as running 20 km every week or reaching a weight by a date, and the progress
+//! towards them.
+//!
+//! Goals are stored as a JSON list in a file alongside the series, in the same way as the
+//! user-defined activities.
+
+use chrono::{Datelike, TimeZone};
+use dimensioned::si::{Kilogram, Meter, M};
+use emseries::Recordable;
+use std::fs;
+use std::path;
+
+use crate::activity::ActivityId;
+use crate::analytics::{period_start, summarize, Period};
+use crate::error::{Error, Result};
+use crate::timezone::TimezoneDisplay;
+use crate::TraxRecord;
+
+/// How many days of weights to fit the weight trend to.
+const TREND_DAYS: i64 = 28;
+
+/// Projections further out than this are not worth showing.
+const MAX_PROJECTION_DAYS: f64 = 3650.;
+
+/// How often a periodic goal starts over.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Interval {
+    Day,
+    /// Weeks start on Monday.
+    Week,
+    Month,
+}
+
+impl From<Interval> for Period {
+    fn from(interval: Interval) -> Period {
+        match interval {
+            Interval::Day => Period::Day,
+            Interval::Week => Period::Week,
+            Interval::Month => Period::Month,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub enum Goal {
+    /// Cover a distance in an activity in every interval.
+    Distance {
+        activity: ActivityId,
+        distance: Meter<f64>,
+        interval: Interval,
+    },
+    /// Take a number of steps in every interval.
+    Steps { steps: u32, interval: Interval },
+    /// Go from the weight at the time that the goal was set to the target weight by a date.
+    Weight {
+        start: Kilogram<f64>,
+        target: Kilogram<f64>,
+        by: chrono::NaiveDate,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Amount {
+    Distance(Meter<f64>),
+    Steps(u32),
+    Weight(Kilogram<f64>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Progress {
+    /// The days that the progress covers: the current interval for periodic goals, or today until
+    /// the target date for weight goals.
+    pub start: chrono::Date<chrono_tz::Tz>,
+    pub end: chrono::Date<chrono_tz::Tz>,
+    /// Where things stand today. Weight goals have no current amount until a weight is recorded.
+    pub current: Option<Amount>,
+    /// How much of the goal is complete, from 0 to 1.
+    pub complete: f64,
+    /// For weight goals, the day that the target weight gets reached if the current trend
+    /// continues. There is no projection when the trend heads away from the target.
+    pub projected: Option<chrono::Date<chrono_tz::Tz>>,
+}
+
+impl Goal {
+    /// Evaluate the progress towards this goal as of `today`. Records are placed on days in the
+    /// timezone of `today`.
+    pub fn progress<'a, I>(
+        &self,
+        records: I,
+        today: chrono::Date<chrono_tz::Tz>,
+        display: TimezoneDisplay,
+    ) -> Progress
+    where
+        I: IntoIterator<Item = &'a TraxRecord>,
+    {
+        match self {
+            Goal::Distance {
+                activity,
+                distance,
+                interval,
+            } => {
+                let (start, end) = interval_bounds(*interval, today);
+                let done = summarize(records, start, end, Period::Range, display)
+                    .first()
+                    .and_then(|summary| summary.time_distance.get(activity))
+                    .map(|totals| totals.distance)
+                    .unwrap_or(0. * M);
+                Progress {
+                    start,
+                    end,
+                    current: Some(Amount::Distance(done)),
+                    complete: fraction(done.value_unsafe, distance.value_unsafe),
+                    projected: None,
+                }
+            }
+            Goal::Steps { steps, interval } => {
+                let (start, end) = interval_bounds(*interval, today);
+                let done = summarize(records, start, end, Period::Range, display)
+                    .first()
+                    .map(|summary| summary.steps)
+                    .unwrap_or(0);
+                Progress {
+                    start,
+                    end,
+                    current: Some(Amount::Steps(done)),
+                    complete: fraction(f64::from(done), f64::from(*steps)),
+                    projected: None,
+                }
+            }
+            Goal::Weight { start, target, by } => {
+                weight_progress(*start, *target, *by, records, today, display)
+            }
+        }
+    }
+}
+
+fn fraction(done: f64, target: f64) -> f64 {
+    if target <= 0. {
+        1.
+    } else {
+        (done / target).clamp(0., 1.)
+    }
+}
+
+/// The first and last days of the interval that contains `today`.
+fn interval_bounds(
+    interval: Interval,
+    today: chrono::Date<chrono_tz::Tz>,
+) -> (chrono::Date<chrono_tz::Tz>, chrono::Date<chrono_tz::Tz>) {
+    let start = period_start(interval.into(), today);
+    let end = match interval {
+        Interval::Day => start,
+        Interval::Week => start + chrono::Duration::days(6),
+        Interval::Month => {
+            let (year, month) = if start.month() == 12 {
+                (start.year() + 1, 1)
+            } else {
+                (start.year(), start.month() + 1)
+            };
+            start.timezone().ymd(year, month, 1).pred()
+        }
+    };
+    (start, end)
+}
+
+fn weight_progress<'a, I>(
+    start: Kilogram<f64>,
+    target: Kilogram<f64>,
+    by: chrono::NaiveDate,
+    records: I,
+    today: chrono::Date<chrono_tz::Tz>,
+    display: TimezoneDisplay,
+) -> Progress
+where
+    I: IntoIterator<Item = &'a TraxRecord>,
+{
+    let timezone = today.timezone();
+    let end = timezone.ymd(by.year(), by.month(), by.day());
+    let day = today.naive_local();
+    let mut weights: Vec<(chrono::NaiveDate, Kilogram<f64>)> = records
+        .into_iter()
+        .filter_map(|record| match record {
+            TraxRecord::Weight(ref rec) => {
+                Some((display.date(&rec.timestamp(), &timezone), rec.weight))
+            }
+            _ => None,
+        })
+        .filter(|(date, _)| *date <= day)
+        .collect();
+    weights.sort_by_key(|(date, _)| *date);
+
+    let current = match weights.last() {
+        Some((_, weight)) => *weight,
+        None => {
+            return Progress {
+                start: today,
+                end,
+                current: None,
+                complete: 0.,
+                projected: None,
+            }
+        }
+    };
+
+    let remaining = (target - current).value_unsafe;
+    let total = (target - start).value_unsafe;
+    let reached = remaining == 0. || remaining.signum() != total.signum();
+    let complete = if reached || total == 0. {
+        1.
+    } else {
+        fraction((current - start).value_unsafe / total, 1.)
+    };
+
+    let recent: Vec<(f64, f64)> = weights
+        .iter()
+        .filter(|(date, _)| (day - *date).num_days() < TREND_DAYS)
+        .map(|(date, weight)| (-(day - *date).num_days() as f64, weight.value_unsafe))
+        .collect();
+    let projected = if reached {
+        None
+    } else {
+        trend(&recent).and_then(|(level, slope)| {
+            let days = (target.value_unsafe - level) / slope;
+            if (0. ..=MAX_PROJECTION_DAYS).contains(&days) {
+                Some(today + chrono::Duration::days(days.round() as i64))
+            } else {
+                None
+            }
+        })
+    };
+
+    Progress {
+        start: today,
+        end,
+        current: Some(Amount::Weight(current)),
+        complete,
+        projected,
+    }
+}
+
+/// Fit a line to the weights by least squares, with days counted relative to today. Returns the
+/// weight that the line gives for today, and the change in weight per day.
+fn trend(points: &[(f64, f64)]) -> Option<(f64, f64)> {
+    if points.len() < 2 {
+        return None;
+    }
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+    let covariance: f64 = points
+        .iter()
+        .map(|(x, y)| (x - mean_x) * (y - mean_y))
+        .sum();
+    let variance: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+    if variance == 0. {
+        return None;
+    }
+    let slope = covariance / variance;
+    Some((mean_y - slope * mean_x, slope))
+}
+
+/// The list of goals, stored in a file alongside the series.
+#[derive(Clone, Debug, Default)]
+pub struct Goals {
+    path: Option<path::PathBuf>,
+    goals: Vec<Goal>,
+}
+
+impl Goals {
+    /// Open the goals stored at `path`. A missing file means that there are no goals yet.
+    pub fn open(path: &path::Path) -> Result<Goals> {
+        let goals = if path.exists() {
+            let file = fs::File::open(path).map_err(Error::IOError)?;
+            serde_json::from_reader(file).map_err(Error::JSONError)?
+        } else {
+            Vec::new()
+        };
+        Ok(Goals {
+            path: Some(path.to_path_buf()),
+            goals,
+        })
+    }
+
+    pub fn goals(&self) -> &[Goal] {
+        &self.goals
+    }
+
+    pub fn add(&mut self, goal: Goal) -> Result<()> {
+        self.goals.push(goal);
+        self.save()
+    }
+
+    /// Remove the goal at `idx` in the list.
+    pub fn remove(&mut self, idx: usize) -> Result<()> {
+        if idx >= self.goals.len() {
+            return Err(Error::InvalidParameter);
+        }
+        self.goals.remove(idx);
+        self.save()
+    }
+
+    fn save(&self) -> Result<()> {
+        if let Some(ref path) = self.path {
+            let file = fs::File::create(path).map_err(Error::IOError)?;
+            serde_json::to_writer(file, &self.goals).map_err(Error::JSONError)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::timedistance::{ActivityType, TimeDistanceRecord};
+    use crate::utils::CleanupFile;
+    use chrono_tz::America::New_York;
+    use dimensioned::si::{KG, S};
+    use emseries::DateTimeTz;
+
+    #[test]
+    fn it_measures_distance_over_the_current_week() {
+        let run = |day: u32, distance: f64| {
+            TraxRecord::TimeDistance(TimeDistanceRecord::new(
+                DateTimeTz(New_York.ymd(2020, 3, day).and_hms(18, 0, 0)),
+                ActivityType::Running.into(),
+                Some(distance * M),
+                Some(1800. * S),
+                None,
+            ))
+        };
+        // March 2nd, 2020 was a Monday, so the run on the 1st belongs to the week before.
+        let records = [run(1, 10000.), run(2, 5000.), run(4, 7000.)];
+        let goal = Goal::Distance {
+            activity: ActivityType::Running.into(),
+            distance: 20000. * M,
+            interval: Interval::Week,
+        };
+        let progress = goal.progress(
+            records.iter(),
+            New_York.ymd(2020, 3, 5),
+            TimezoneDisplay::Home,
+        );
+        assert_eq!(progress.start, New_York.ymd(2020, 3, 2));
+        assert_eq!(progress.end, New_York.ymd(2020, 3, 8));
+        assert_eq!(progress.current, Some(Amount::Distance(12000. * M)));
+        assert!((progress.complete - 0.6).abs() < 1e-9);
+    }
+
+    #[test]
+    fn it_projects_weight_from_the_trend() {
+        let weigh = |day: u32, weight: f64| {
+            TraxRecord::weight(
+                DateTimeTz(New_York.ymd(2020, 3, day).and_hms(7, 0, 0)),
+                weight * KG,
+            )
+        };
+        // Losing 0.1 kg every day.
+        let records = [weigh(1, 85.), weigh(11, 84.), weigh(21, 83.)];
+        let goal = Goal::Weight {
+            start: 85. * KG,
+            target: 80. * KG,
+            by: chrono::NaiveDate::from_ymd(2020, 6, 1),
+        };
+        let progress = goal.progress(
+            records.iter(),
+            New_York.ymd(2020, 3, 21),
+            TimezoneDisplay::Home,
+        );
+        assert_eq!(progress.current, Some(Amount::Weight(83. * KG)));
+        assert!((progress.complete - 0.4).abs() < 1e-9);
+        assert_eq!(progress.end, New_York.ymd(2020, 6, 1));
+        assert_eq!(progress.projected, Some(New_York.ymd(2020, 4, 20)));
+
+        let gaining = [weigh(1, 83.), weigh(11, 84.)];
+        let progress = goal.progress(
+            gaining.iter(),
+            New_York.ymd(2020, 3, 11),
+            TimezoneDisplay::Home,
+        );
+        assert!((progress.complete - 0.2).abs() < 1e-9);
+        assert_eq!(progress.projected, None);
+    }
+
+    #[test]
+    fn it_persists_goals() {
+        let path = path::PathBuf::from("var/it_persists_goals.goals");
+        let _cleanup = CleanupFile(path.clone());
+        let steps = Goal::Steps {
+            steps: 10000,
+            interval: Interval::Day,
+        };
+        {
+            let mut goals = Goals::open(&path).expect("goals should open");
+            goals.add(steps.clone()).expect("add should succeed");
+        }
+        let goals = Goals::open(&path).expect("goals should open again");
+        assert_eq!(goals.goals(), &[steps]);
+    }
+}
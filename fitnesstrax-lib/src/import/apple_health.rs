@@ -0,0 +1,530 @@
+//! Importer for the `export.xml` file inside an Apple Health export.
+//!
+//! Exports easily run to hundreds of megabytes, so the file is read one element at a time and
+//! only the records of interest are kept:
+//!
+//! * `HKQuantityTypeIdentifierStepCount` samples become one `StepRecord` per day. The phone and
+//!   the watch both count the same steps, so each day takes the total of whichever source counted
+//!   the most rather than adding the sources together.
+//! * `HKQuantityTypeIdentifierBodyMass` samples become `WeightRecord`s.
+//! * `Workout` elements become `TimeDistanceRecord`s. Workouts that do not match a built-in
+//!   activity, such as yoga or strength training, are reported as unmapped.
+//!
+//! Every other record type is ignored. Unmapped entries are numbered by their position among the
+//! `Record` and `Workout` elements of the file.
+
+use chrono::{DateTime, Datelike, FixedOffset, NaiveDate, TimeZone};
+use dimensioned::si::{KG, M, S};
+use emseries::DateTimeTz;
+use quick_xml::events::{BytesStart, Event};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use crate::activity::ActivityId;
+use crate::error::{Error, Result};
+use crate::import::{attribute, import_records, ImportReport, Parsed, Unmapped};
+use crate::timezone::day_start;
+use crate::types::steps::StepRecord;
+use crate::types::timedistance::{ActivityType, TimeDistanceRecord};
+use crate::types::weight::WeightRecord;
+use crate::{Trax, TraxRecord};
+
+const STEP_COUNT: &str = "HKQuantityTypeIdentifierStepCount";
+const BODY_MASS: &str = "HKQuantityTypeIdentifierBodyMass";
+
+const DATE_FORMAT: &str = "%Y-%m-%d %H:%M:%S %z";
+
+fn step_count(value: f64, unit: &str) -> Option<f64> {
+    match unit {
+        "count" => Some(value),
+        _ => None,
+    }
+}
+
+fn mass_in_kg(value: f64, unit: &str) -> Option<f64> {
+    match unit {
+        "kg" => Some(value),
+        "g" => Some(value / 1000.),
+        "lb" => Some(value * 0.453_592_37),
+        "st" => Some(value * 6.350_293_18),
+        _ => None,
+    }
+}
+
+fn distance_in_m(value: f64, unit: &str) -> Option<f64> {
+    match unit {
+        "m" => Some(value),
+        "cm" => Some(value / 100.),
+        "km" => Some(value * 1000.),
+        "ft" => Some(value * 0.3048),
+        "yd" => Some(value * 0.9144),
+        "mi" => Some(value * 1609.344),
+        _ => None,
+    }
+}
+
+fn duration_in_s(value: f64, unit: &str) -> Option<f64> {
+    match unit {
+        "s" => Some(value),
+        "min" => Some(value * 60.),
+        "hr" => Some(value * 3600.),
+        _ => None,
+    }
+}
+
+fn activity_for(workout_type: &str) -> Option<ActivityId> {
+    match workout_type.trim_start_matches("HKWorkoutActivityType") {
+        "Running" => Some(ActivityType::Running.into()),
+        "Cycling" | "HandCycling" => Some(ActivityType::Cycling.into()),
+        "Walking" | "Hiking" => Some(ActivityType::Walking.into()),
+        "Swimming" => Some(ActivityType::Swimming.into()),
+        "Rowing" | "PaddleSports" => Some(ActivityType::Rowing.into()),
+        _ => None,
+    }
+}
+
+type Conversion<A> = std::result::Result<A, String>;
+
+fn required(e: &BytesStart, name: &str) -> Result<Conversion<String>> {
+    Ok(attribute(e, name)?.ok_or_else(|| format!("missing attribute: {}", name)))
+}
+
+fn parse_date(val: &str) -> Conversion<DateTime<FixedOffset>> {
+    DateTime::parse_from_str(val, DATE_FORMAT).map_err(|_| format!("invalid date: {}", val))
+}
+
+fn parse_number(val: &str) -> Conversion<f64> {
+    val.parse::<f64>()
+        .map_err(|_| format!("invalid value: {}", val))
+}
+
+/// Read a quantity along with the unit it was recorded in.
+fn quantity(
+    e: &BytesStart,
+    value: &str,
+    unit: &str,
+    convert: fn(f64, &str) -> Option<f64>,
+) -> Result<Conversion<Option<f64>>> {
+    let value = match attribute(e, value)? {
+        Some(value) => value,
+        None => return Ok(Ok(None)),
+    };
+    let unit = attribute(e, unit)?.unwrap_or_default();
+    Ok(parse_number(&value).and_then(|value| {
+        convert(value, &unit)
+            .map(Some)
+            .ok_or_else(|| format!("unknown unit: {}", unit))
+    }))
+}
+
+struct Workout {
+    entry: usize,
+    start: DateTime<FixedOffset>,
+    end: Option<DateTime<FixedOffset>>,
+    activity: Conversion<ActivityId>,
+    duration: Option<f64>,
+    distance: Option<f64>,
+}
+
+#[derive(Default)]
+struct State {
+    entry: usize,
+    parsed: Parsed,
+    /// For each day, and for each source, the entry of the first sample and the total steps.
+    steps: BTreeMap<NaiveDate, BTreeMap<String, (usize, f64)>>,
+    workout: Option<Workout>,
+}
+
+impl State {
+    fn unmapped(&mut self, entry: usize, reason: String) {
+        self.parsed.unmapped.push(Unmapped {
+            line: entry,
+            reason,
+        });
+    }
+
+    fn record(&mut self, e: &BytesStart, timezone: chrono_tz::Tz) -> Result<()> {
+        self.entry += 1;
+        let entry = self.entry;
+        let type_ = attribute(e, "type")?.unwrap_or_default();
+        if type_ != STEP_COUNT && type_ != BODY_MASS {
+            return Ok(());
+        }
+
+        let start = required(e, "startDate")?.and_then(|val| parse_date(&val));
+        let convert = if type_ == STEP_COUNT {
+            step_count
+        } else {
+            mass_in_kg
+        };
+        let value = quantity(e, "value", "unit", convert)?;
+        let (start, value) = match (start, value) {
+            (Ok(start), Ok(Some(value))) => (start, value),
+            (Ok(_), Ok(None)) => {
+                self.unmapped(entry, String::from("missing attribute: value"));
+                return Ok(());
+            }
+            (Err(reason), _) | (_, Err(reason)) => {
+                self.unmapped(entry, reason);
+                return Ok(());
+            }
+        };
+
+        if type_ == STEP_COUNT {
+            let source = attribute(e, "sourceName")?.unwrap_or_default();
+            let total = self
+                .steps
+                .entry(start.naive_local().date())
+                .or_default()
+                .entry(source)
+                .or_insert((entry, 0.));
+            total.1 += value;
+        } else {
+            self.parsed.records.push((
+                entry,
+                TraxRecord::from(WeightRecord::new(
+                    DateTimeTz(start.with_timezone(&timezone)),
+                    value * KG,
+                )),
+            ));
+        }
+        Ok(())
+    }
+
+    fn start_workout(&mut self, e: &BytesStart) -> Result<()> {
+        self.entry += 1;
+        let entry = self.entry;
+
+        let start = required(e, "startDate")?.and_then(|val| parse_date(&val));
+        let end = attribute(e, "endDate")?.map(|val| parse_date(&val));
+        let duration = quantity(e, "duration", "durationUnit", duration_in_s)?;
+        let distance = quantity(e, "totalDistance", "totalDistanceUnit", distance_in_m)?;
+        let activity = required(e, "workoutActivityType")?.and_then(|val| {
+            activity_for(&val).ok_or_else(|| format!("unknown workout type: {}", val))
+        });
+
+        match (start, end.transpose(), duration, distance) {
+            (Ok(start), Ok(end), Ok(duration), Ok(distance)) => {
+                self.workout = Some(Workout {
+                    entry,
+                    start,
+                    end,
+                    activity,
+                    duration,
+                    distance,
+                })
+            }
+            (Err(reason), _, _, _)
+            | (_, Err(reason), _, _)
+            | (_, _, Err(reason), _)
+            | (_, _, _, Err(reason)) => self.unmapped(entry, reason),
+        }
+        Ok(())
+    }
+
+    /// Newer exports leave the distance off of the workout and list it among the statistics.
+    fn workout_statistics(&mut self, e: &BytesStart) -> Result<()> {
+        let type_ = attribute(e, "type")?.unwrap_or_default();
+        if !type_.starts_with("HKQuantityTypeIdentifierDistance") {
+            return Ok(());
+        }
+        let distance = quantity(e, "sum", "unit", distance_in_m)?;
+        if let Some(ref mut workout) = self.workout {
+            match distance {
+                Ok(Some(distance)) if workout.distance.is_none() => {
+                    workout.distance = Some(distance)
+                }
+                Ok(_) => (),
+                Err(reason) => {
+                    let entry = workout.entry;
+                    self.unmapped(entry, reason);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn end_workout(&mut self, timezone: chrono_tz::Tz) {
+        if let Some(Workout {
+            entry,
+            start,
+            end,
+            activity,
+            duration,
+            distance,
+        }) = self.workout.take()
+        {
+            let activity = match activity {
+                Ok(activity) => activity,
+                Err(reason) => {
+                    self.unmapped(entry, reason);
+                    return;
+                }
+            };
+            let duration =
+                duration.or_else(|| end.map(|end| (end - start).num_milliseconds() as f64 / 1000.));
+            self.parsed.records.push((
+                entry,
+                TraxRecord::from(TimeDistanceRecord::new(
+                    DateTimeTz(start.with_timezone(&timezone)),
+                    activity,
+                    distance.map(|d| d * M),
+                    duration.map(|d| d * S),
+                    None,
+                )),
+            ));
+        }
+    }
+
+    fn finish(mut self, timezone: chrono_tz::Tz) -> Parsed {
+        for (date, sources) in self.steps {
+            let best = sources.values().fold(
+                None,
+                |best: Option<(usize, f64)>, &(entry, steps)| match best {
+                    Some((_, most)) if most >= steps => best,
+                    _ => Some((entry, steps)),
+                },
+            );
+            if let Some((entry, steps)) = best {
+                let start = day_start(&timezone.ymd(date.year(), date.month(), date.day()));
+                self.parsed.records.push((
+                    entry,
+                    TraxRecord::from(StepRecord::new(DateTimeTz(start), steps.round() as u32)),
+                ));
+            }
+        }
+        self.parsed.records.sort_by_key(|(entry, _)| *entry);
+        self.parsed.unmapped.sort_by_key(|unmapped| unmapped.line);
+        self.parsed
+    }
+}
+
+/// Read an Apple Health export without touching the series. Records are placed in `timezone`.
+pub fn read_export<R: BufRead>(reader: R, timezone: chrono_tz::Tz) -> Result<Parsed> {
+    let mut reader = quick_xml::Reader::from_reader(reader);
+    reader.config_mut().trim_text(true);
+
+    let mut state = State::default();
+    let mut buf = Vec::new();
+    loop {
+        match reader.read_event_into(&mut buf).map_err(Error::XMLError)? {
+            Event::Start(e) => match e.local_name().as_ref() {
+                b"Record" => state.record(&e, timezone)?,
+                b"Workout" => state.start_workout(&e)?,
+                b"WorkoutStatistics" => state.workout_statistics(&e)?,
+                _ => (),
+            },
+            Event::Empty(e) => match e.local_name().as_ref() {
+                b"Record" => state.record(&e, timezone)?,
+                b"Workout" => {
+                    state.start_workout(&e)?;
+                    state.end_workout(timezone);
+                }
+                b"WorkoutStatistics" => state.workout_statistics(&e)?,
+                _ => (),
+            },
+            Event::End(e) if e.local_name().as_ref() == b"Workout" => state.end_workout(timezone),
+            Event::Eof => break,
+            _ => (),
+        }
+        buf.clear();
+    }
+
+    Ok(state.finish(timezone))
+}
+
+/// Read an Apple Health `export.xml` file from disk.
+pub fn read_file(path: &Path, timezone: chrono_tz::Tz) -> Result<Parsed> {
+    let file = fs::File::open(path).map_err(Error::IOError)?;
+    read_export(BufReader::new(file), timezone)
+}
+
+/// Import an Apple Health export into the series. Importing the same export more than once does
+/// not create duplicate records.
+pub fn import<R: BufRead>(
+    trax: &mut Trax,
+    reader: R,
+    timezone: chrono_tz::Tz,
+) -> Result<ImportReport> {
+    let parsed = read_export(reader, timezone)?;
+    import_records(trax, parsed)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::CleanupFile;
+    use crate::Params;
+    use chrono_tz::America::{New_York, Sao_Paulo};
+    use emseries::Recordable;
+    use std::path;
+
+    const EXPORT: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+<!DOCTYPE HealthData [
+<!ELEMENT HealthData (ExportDate,Me,(Record|Workout)*)>
+]>
+<HealthData locale=\"en_US\">
+ <ExportDate value=\"2020-03-02 10:00:00 -0500\"/>
+ <Me HKCharacteristicTypeIdentifierDateOfBirth=\"\"/>
+ <Record type=\"HKQuantityTypeIdentifierStepCount\" sourceName=\"Phone\" unit=\"count\" startDate=\"2020-03-01 08:00:00 -0500\" endDate=\"2020-03-01 08:10:00 -0500\" value=\"1000\"/>
+ <Record type=\"HKQuantityTypeIdentifierStepCount\" sourceName=\"Phone\" unit=\"count\" startDate=\"2020-03-01 18:00:00 -0500\" endDate=\"2020-03-01 18:10:00 -0500\" value=\"2500\"/>
+ <Record type=\"HKQuantityTypeIdentifierStepCount\" sourceName=\"Watch\" unit=\"count\" startDate=\"2020-03-01 08:00:00 -0500\" endDate=\"2020-03-01 08:10:00 -0500\" value=\"3200\"/>
+ <Record type=\"HKQuantityTypeIdentifierHeartRate\" sourceName=\"Watch\" unit=\"count/min\" startDate=\"2020-03-01 08:00:00 -0500\" endDate=\"2020-03-01 08:00:00 -0500\" value=\"72\"/>
+ <Record type=\"HKQuantityTypeIdentifierBodyMass\" sourceName=\"Scale\" unit=\"lb\" startDate=\"2020-03-01 07:00:00 -0500\" endDate=\"2020-03-01 07:00:00 -0500\" value=\"180\">
+  <MetadataEntry key=\"HKWasUserEntered\" value=\"1\"/>
+ </Record>
+ <Record type=\"HKQuantityTypeIdentifierBodyMass\" sourceName=\"Scale\" unit=\"oz\" startDate=\"2020-03-02 07:00:00 -0500\" endDate=\"2020-03-02 07:00:00 -0500\" value=\"2880\"/>
+ <Record type=\"HKQuantityTypeIdentifierStepCount\" sourceName=\"Phone\" unit=\"count\" startDate=\"2020-03-02 09:00:00 -0500\" endDate=\"2020-03-02 09:10:00 -0500\" value=\"400\"/>
+ <Workout workoutActivityType=\"HKWorkoutActivityTypeRunning\" duration=\"30.5\" durationUnit=\"min\" totalDistance=\"3.1\" totalDistanceUnit=\"mi\" startDate=\"2020-03-01 12:00:00 -0500\" endDate=\"2020-03-01 12:31:00 -0500\"/>
+ <Workout workoutActivityType=\"HKWorkoutActivityTypeYoga\" startDate=\"2020-03-02 12:00:00 -0500\" endDate=\"2020-03-02 12:45:00 -0500\">
+  <WorkoutStatistics type=\"HKQuantityTypeIdentifierActiveEnergyBurned\" sum=\"120\" unit=\"Cal\"/>
+ </Workout>
+ <Workout workoutActivityType=\"HKWorkoutActivityTypeCycling\" duration=\"1\" durationUnit=\"hr\" startDate=\"2020-03-02 17:00:00 -0500\" endDate=\"2020-03-02 18:00:00 -0500\">
+  <WorkoutStatistics type=\"HKQuantityTypeIdentifierDistanceCycling\" sum=\"25.5\" unit=\"km\"/>
+ </Workout>
+</HealthData>
+";
+
+    #[test]
+    fn it_reads_an_apple_health_export() {
+        let Parsed { records, unmapped } =
+            read_export(EXPORT.as_bytes(), New_York).expect("the export should be read");
+
+        assert_eq!(
+            unmapped,
+            vec![
+                Unmapped {
+                    line: 6,
+                    reason: String::from("unknown unit: oz")
+                },
+                Unmapped {
+                    line: 9,
+                    reason: String::from("unknown workout type: HKWorkoutActivityTypeYoga")
+                },
+            ]
+        );
+
+        let steps: Vec<(DateTimeTz, u32)> = records
+            .iter()
+            .filter_map(|(_, record)| match record {
+                TraxRecord::Steps(ref rec) => Some((rec.timestamp(), rec.steps)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            steps,
+            vec![
+                (DateTimeTz(New_York.ymd(2020, 3, 1).and_hms(0, 0, 0)), 3500),
+                (DateTimeTz(New_York.ymd(2020, 3, 2).and_hms(0, 0, 0)), 400),
+            ]
+        );
+
+        let weights: Vec<&WeightRecord> = records
+            .iter()
+            .filter_map(|(_, record)| match record {
+                TraxRecord::Weight(ref rec) => Some(rec),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(weights.len(), 1);
+        assert!((weights[0].weight - 81.646_626_6 * KG).value_unsafe.abs() < 1e-6);
+
+        let workouts: Vec<&TimeDistanceRecord> = records
+            .iter()
+            .filter_map(|(_, record)| match record {
+                TraxRecord::TimeDistance(ref rec) => Some(rec),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(workouts.len(), 2);
+        assert_eq!(workouts[0].activity, ActivityType::Running.into());
+        assert_eq!(workouts[0].duration, Some(1830. * S));
+        assert!(
+            (workouts[0].distance.unwrap() - 4_988.966_4 * M)
+                .value_unsafe
+                .abs()
+                < 1e-6
+        );
+        assert_eq!(workouts[1].activity, ActivityType::Cycling.into());
+        assert_eq!(workouts[1].duration, Some(3600. * S));
+        assert_eq!(workouts[1].distance, Some(25500. * M));
+    }
+
+    #[test]
+    fn it_counts_steps_on_days_without_a_midnight() {
+        // Clocks in São Paulo went from 00:00 straight to 01:00 on the 16th.
+        let export = "<HealthData locale=\"pt_BR\">
+ <Record type=\"HKQuantityTypeIdentifierStepCount\" sourceName=\"Phone\" unit=\"count\" startDate=\"2016-10-16 08:00:00 -0200\" endDate=\"2016-10-16 08:10:00 -0200\" value=\"1200\"/>
+</HealthData>
+";
+        let Parsed { records, .. } =
+            read_export(export.as_bytes(), Sao_Paulo).expect("the export should be read");
+
+        assert_eq!(records.len(), 1);
+        assert_eq!(
+            records[0].1.timestamp(),
+            DateTimeTz(Sao_Paulo.ymd(2016, 10, 16).and_hms(1, 0, 0))
+        );
+    }
+
+    #[test]
+    fn it_imports_an_apple_health_export_only_once() {
+        let series_path =
+            path::PathBuf::from("var/it_imports_an_apple_health_export_only_once.series");
+        let _cleanup = CleanupFile(series_path.clone());
+        let mut trax = Trax::create(Params { series_path }).expect("the app to be created");
+
+        let report = import(&mut trax, EXPORT.as_bytes(), New_York).expect("import should succeed");
+        assert_eq!(report.imported, 5);
+        assert_eq!(report.duplicates, 0);
+        assert_eq!(report.unmapped.len(), 2);
+
+        let report = import(&mut trax, EXPORT.as_bytes(), New_York).expect("import should succeed");
+        assert_eq!(report.imported, 0);
+        assert_eq!(report.duplicates, 5);
+    }
+
+    #[test]
+    fn it_replaces_step_totals_that_grew_since_the_last_import() {
+        let series_path = path::PathBuf::from(
+            "var/it_replaces_step_totals_that_grew_since_the_last_import.series",
+        );
+        let _cleanup = CleanupFile(series_path.clone());
+        let mut trax = Trax::create(Params { series_path }).expect("the app to be created");
+        let export = |value| {
+            format!("<HealthData locale=\"en_US\">
+ <Record type=\"HKQuantityTypeIdentifierStepCount\" sourceName=\"Phone\" unit=\"count\" startDate=\"2020-03-01 08:00:00 -0500\" endDate=\"2020-03-01 08:10:00 -0500\" value=\"{}\"/>
+</HealthData>
+", value)
+        };
+        let steps = |trax: &Trax| -> Vec<u32> {
+            trax.all_records()
+                .unwrap()
+                .into_iter()
+                .filter_map(|(_, record)| match record {
+                    TraxRecord::Steps(rec) => Some(rec.steps),
+                    _ => None,
+                })
+                .collect()
+        };
+
+        let report =
+            import(&mut trax, export(1000).as_bytes(), New_York).expect("import should succeed");
+        assert_eq!(report.imported, 1);
+
+        // The day went on after the first export.
+        let report =
+            import(&mut trax, export(4000).as_bytes(), New_York).expect("import should succeed");
+        assert_eq!(report.imported, 1);
+        assert_eq!(steps(&trax), vec![4000]);
+
+        // An older export does not take any steps away.
+        let report =
+            import(&mut trax, export(1000).as_bytes(), New_York).expect("import should succeed");
+        assert_eq!(report.imported, 0);
+        assert_eq!(report.duplicates, 1);
+        assert_eq!(steps(&trax), vec![4000]);
+    }
+}
@@ -0,0 +1,426 @@
+//! Importer for the binary FIT files that Garmin and many other devices produce.
+//!
+//! A FIT file is a stream of definition messages, which describe the layout of the messages that
+//! follow, and data messages. Only the session messages are of interest here: each session
+//! becomes one `TimeDistanceRecord`, using the session's start time, total distance and timer
+//! time. Every other message is skipped, so files from newer devices still import.
+
+use chrono::{DateTime, TimeZone, Utc};
+use dimensioned::si::{M, S};
+use emseries::DateTimeTz;
+use std::collections::HashMap;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+use crate::activity::ActivityId;
+use crate::error::{Error, Result};
+use crate::import::{Parsed, Unmapped};
+use crate::types::timedistance::{ActivityType, TimeDistanceRecord};
+use crate::TraxRecord;
+
+/// FIT timestamps count seconds from 1989-12-31T00:00:00Z rather than from the Unix epoch.
+const FIT_EPOCH: i64 = 631_065_600;
+
+const SESSION_MESSAGE: u16 = 18;
+
+const FIELD_TIMESTAMP: u8 = 253;
+const FIELD_START_TIME: u8 = 2;
+const FIELD_SPORT: u8 = 5;
+const FIELD_TOTAL_ELAPSED_TIME: u8 = 7;
+const FIELD_TOTAL_TIMER_TIME: u8 = 8;
+const FIELD_TOTAL_DISTANCE: u8 = 9;
+const FIELD_AVG_HEART_RATE: u8 = 16;
+
+const CRC_TABLE: [u16; 16] = [
+    0x0000, 0xCC01, 0xD801, 0x1400, 0xF001, 0x3C00, 0x2800, 0xE401, 0xA001, 0x6C00, 0x7800, 0xB401,
+    0x5000, 0x9C01, 0x8801, 0x4400,
+];
+
+fn crc(data: &[u8]) -> u16 {
+    data.iter().fold(0, |crc, byte| {
+        let tmp = CRC_TABLE[(crc & 0xF) as usize];
+        let crc = ((crc >> 4) & 0x0FFF) ^ tmp ^ CRC_TABLE[(byte & 0xF) as usize];
+        let tmp = CRC_TABLE[(crc & 0xF) as usize];
+        ((crc >> 4) & 0x0FFF) ^ tmp ^ CRC_TABLE[((byte >> 4) & 0xF) as usize]
+    })
+}
+
+#[derive(Clone, Debug)]
+struct Field {
+    number: u8,
+    size: usize,
+}
+
+#[derive(Clone, Debug)]
+struct Definition {
+    global: u16,
+    big_endian: bool,
+    fields: Vec<Field>,
+    developer_size: usize,
+}
+
+impl Definition {
+    fn size(&self) -> usize {
+        self.fields.iter().map(|f| f.size).sum::<usize>() + self.developer_size
+    }
+}
+
+/// The fields of a session message, already converted to SI units.
+#[derive(Clone, Debug, Default)]
+struct Session {
+    timestamp: Option<DateTime<Utc>>,
+    start_time: Option<DateTime<Utc>>,
+    sport: Option<u8>,
+    elapsed_time: Option<f64>,
+    timer_time: Option<f64>,
+    distance: Option<f64>,
+    avg_heart_rate: Option<u8>,
+}
+
+struct Cursor<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.pos + len > self.data.len() {
+            return Err(Error::FITError(String::from("the file is truncated")));
+        }
+        let bytes = &self.data[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn byte(&mut self) -> Result<u8> {
+        self.take(1).map(|b| b[0])
+    }
+}
+
+/// Read an unsigned integer field, treating the all-ones value that FIT uses for "no value" as
+/// missing.
+fn unsigned(bytes: &[u8], big_endian: bool) -> Option<u64> {
+    if bytes.is_empty() || bytes.len() > 8 || bytes.iter().all(|b| *b == 0xFF) {
+        return None;
+    }
+    let fold = |acc: u64, b: &u8| (acc << 8) | u64::from(*b);
+    Some(if big_endian {
+        bytes.iter().fold(0, fold)
+    } else {
+        bytes.iter().rev().fold(0, fold)
+    })
+}
+
+fn date_time(value: u64) -> Option<DateTime<Utc>> {
+    Utc.timestamp_opt(FIT_EPOCH + value as i64, 0).single()
+}
+
+fn read_session(definition: &Definition, data: &[u8]) -> Session {
+    let mut session = Session::default();
+    let mut offset = 0;
+    for field in definition.fields.iter() {
+        let value = unsigned(&data[offset..offset + field.size], definition.big_endian);
+        offset += field.size;
+        match field.number {
+            FIELD_TIMESTAMP => session.timestamp = value.and_then(date_time),
+            FIELD_START_TIME => session.start_time = value.and_then(date_time),
+            FIELD_SPORT => session.sport = value.map(|v| v as u8),
+            FIELD_TOTAL_ELAPSED_TIME => session.elapsed_time = value.map(|v| v as f64 / 1000.),
+            FIELD_TOTAL_TIMER_TIME => session.timer_time = value.map(|v| v as f64 / 1000.),
+            FIELD_TOTAL_DISTANCE => session.distance = value.map(|v| v as f64 / 100.),
+            FIELD_AVG_HEART_RATE => session.avg_heart_rate = value.map(|v| v as u8),
+            _ => (),
+        }
+    }
+    session
+}
+
+fn read_definition(cursor: &mut Cursor, has_developer_fields: bool) -> Result<Definition> {
+    let _reserved = cursor.byte()?;
+    let big_endian = cursor.byte()? == 1;
+    let global = cursor.take(2)?;
+    let global = if big_endian {
+        u16::from_be_bytes([global[0], global[1]])
+    } else {
+        u16::from_le_bytes([global[0], global[1]])
+    };
+
+    let field_count = cursor.byte()?;
+    let mut fields = Vec::new();
+    for _ in 0..field_count {
+        let field = cursor.take(3)?;
+        fields.push(Field {
+            number: field[0],
+            size: field[1] as usize,
+        });
+    }
+
+    let mut developer_size = 0;
+    if has_developer_fields {
+        let developer_count = cursor.byte()?;
+        for _ in 0..developer_count {
+            developer_size += cursor.take(3)?[1] as usize;
+        }
+    }
+
+    Ok(Definition {
+        global,
+        big_endian,
+        fields,
+        developer_size,
+    })
+}
+
+/// Read the records of one FIT file, which may be followed by further chained files.
+fn read_sessions(data: &[u8]) -> Result<Vec<Session>> {
+    let mut sessions = Vec::new();
+    let mut cursor = Cursor { data, pos: 0 };
+
+    while cursor.pos < data.len() {
+        let start = cursor.pos;
+        let header_size = cursor.byte()? as usize;
+        if header_size < 12 {
+            return Err(Error::FITError(String::from("the header is too short")));
+        }
+        let header = cursor.take(header_size - 1)?;
+        if &header[7..11] != b".FIT" {
+            return Err(Error::FITError(String::from("this is not a FIT file")));
+        }
+        let data_size = u32::from_le_bytes([header[3], header[4], header[5], header[6]]) as usize;
+        let end = cursor.pos + data_size;
+
+        let body = cursor.take(data_size)?;
+        let checksum = cursor.take(2)?;
+        if crc(&data[start..end]) != u16::from_le_bytes([checksum[0], checksum[1]]) {
+            return Err(Error::FITError(String::from("the checksum does not match")));
+        }
+
+        let mut definitions: HashMap<u8, Definition> = HashMap::new();
+        let mut records = Cursor { data: body, pos: 0 };
+        while records.pos < body.len() {
+            let record_header = records.byte()?;
+            let (is_definition, has_developer_fields, local) = if record_header & 0x80 != 0 {
+                // A compressed timestamp header, which always introduces a data message.
+                (false, false, (record_header >> 5) & 0x03)
+            } else {
+                (
+                    record_header & 0x40 != 0,
+                    record_header & 0x20 != 0,
+                    record_header & 0x0F,
+                )
+            };
+
+            if is_definition {
+                let definition = read_definition(&mut records, has_developer_fields)?;
+                definitions.insert(local, definition);
+            } else {
+                let definition = definitions.get(&local).ok_or_else(|| {
+                    Error::FITError(format!("no definition for local message {}", local))
+                })?;
+                let content = records.take(definition.size())?;
+                if definition.global == SESSION_MESSAGE {
+                    sessions.push(read_session(definition, content));
+                }
+            }
+        }
+    }
+
+    Ok(sessions)
+}
+
+/// Map the FIT sport enumeration onto the built-in activities.
+fn activity_for(sport: u8) -> Option<ActivityId> {
+    match sport {
+        1 => Some(ActivityType::Running.into()),
+        2 => Some(ActivityType::Cycling.into()),
+        5 => Some(ActivityType::Swimming.into()),
+        11 | 17 => Some(ActivityType::Walking.into()),
+        15 => Some(ActivityType::Rowing.into()),
+        _ => None,
+    }
+}
+
+fn to_record(
+    session: &Session,
+    timezone: chrono_tz::Tz,
+    fallback: &ActivityId,
+) -> std::result::Result<TimeDistanceRecord, String> {
+    let start = match (session.start_time, session.timestamp, session.elapsed_time) {
+        (Some(start), _, _) => start,
+        (None, Some(end), Some(elapsed)) => {
+            end - chrono::Duration::milliseconds((elapsed * 1000.) as i64)
+        }
+        _ => return Err(String::from("the session has no start time")),
+    };
+
+    Ok(TimeDistanceRecord::new(
+        DateTimeTz(start.with_timezone(&timezone)),
+        session
+            .sport
+            .and_then(activity_for)
+            .unwrap_or_else(|| fallback.clone()),
+        session.distance.map(|d| d * M),
+        session.timer_time.or(session.elapsed_time).map(|d| d * S),
+        session
+            .avg_heart_rate
+            .map(|hr| format!("Average heart rate: {} bpm", hr)),
+    ))
+}
+
+/// Read every session in a FIT file. Sessions for sports that have no built-in activity get the
+/// `fallback` activity. Unmapped entries are numbered by session.
+pub fn read_fit<R: Read>(
+    mut reader: R,
+    timezone: chrono_tz::Tz,
+    fallback: &ActivityId,
+) -> Result<Parsed> {
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data).map_err(Error::IOError)?;
+
+    let mut parsed = Parsed::default();
+    for (idx, session) in read_sessions(&data)?.iter().enumerate() {
+        match to_record(session, timezone, fallback) {
+            Ok(record) => parsed.records.push((idx + 1, TraxRecord::from(record))),
+            Err(reason) => parsed.unmapped.push(Unmapped {
+                line: idx + 1,
+                reason,
+            }),
+        }
+    }
+    Ok(parsed)
+}
+
+/// Read a FIT file from disk.
+pub fn read_file(path: &Path, timezone: chrono_tz::Tz, fallback: &ActivityId) -> Result<Parsed> {
+    let file = fs::File::open(path).map_err(Error::IOError)?;
+    read_fit(file, timezone, fallback)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono_tz::America::New_York;
+
+    /// Assemble a FIT file from its records, adding the header and the checksum.
+    fn fit_file(records: &[u8]) -> Vec<u8> {
+        let mut file = vec![14, 0x20, 0x08, 0x08];
+        file.extend_from_slice(&(records.len() as u32).to_le_bytes());
+        file.extend_from_slice(b".FIT");
+        file.extend_from_slice(&[0, 0]);
+        file.extend_from_slice(records);
+        let checksum = crc(&file);
+        file.extend_from_slice(&checksum.to_le_bytes());
+        file
+    }
+
+    fn session_definition(local: u8) -> Vec<u8> {
+        let mut record = vec![0x40 | local, 0, 0];
+        record.extend_from_slice(&SESSION_MESSAGE.to_le_bytes());
+        record.push(7);
+        for (number, size, base_type) in &[
+            (FIELD_TIMESTAMP, 4, 0x86),
+            (FIELD_START_TIME, 4, 0x86),
+            (FIELD_SPORT, 1, 0x00),
+            (FIELD_TOTAL_ELAPSED_TIME, 4, 0x86),
+            (FIELD_TOTAL_TIMER_TIME, 4, 0x86),
+            (FIELD_TOTAL_DISTANCE, 4, 0x86),
+            (FIELD_AVG_HEART_RATE, 1, 0x02),
+        ] {
+            record.extend_from_slice(&[*number, *size, *base_type]);
+        }
+        record
+    }
+
+    fn session(
+        local: u8,
+        start: u32,
+        sport: u8,
+        elapsed: u32,
+        timer: u32,
+        distance: u32,
+    ) -> Vec<u8> {
+        let mut record = vec![local];
+        record.extend_from_slice(&(start + elapsed / 1000).to_le_bytes());
+        record.extend_from_slice(&start.to_le_bytes());
+        record.push(sport);
+        record.extend_from_slice(&elapsed.to_le_bytes());
+        record.extend_from_slice(&timer.to_le_bytes());
+        record.extend_from_slice(&distance.to_le_bytes());
+        record.push(142);
+        record
+    }
+
+    /// 2020-03-01T12:00:00Z in FIT time.
+    const START: u32 = 1_583_064_000 - FIT_EPOCH as u32;
+
+    fn time_distance(parsed: &Parsed, idx: usize) -> &TimeDistanceRecord {
+        match parsed.records[idx].1 {
+            TraxRecord::TimeDistance(ref rec) => rec,
+            ref rec => panic!("expected a time/distance record: {:?}", rec),
+        }
+    }
+
+    #[test]
+    fn it_reads_sessions_and_skips_unknown_messages() {
+        let mut records = Vec::new();
+        // A message type that this decoder knows nothing about, with a developer field, read
+        // back once with a normal header and once with a compressed timestamp header.
+        records.extend_from_slice(&[0x61, 0, 0, 0x0F, 0x27, 1, 3, 2, 0x84, 1, 0, 2, 0]);
+        records.extend_from_slice(&[0x01, 0x34, 0x12, 0xAA, 0xBB]);
+        records.extend_from_slice(&[0x80 | (1 << 5) | 4, 0x34, 0x12, 0xAA, 0xBB]);
+        records.extend(session_definition(0));
+        records.extend(session(0, START, 1, 1_800_000, 1_750_500, 500_000));
+        records.extend(session(0, START + 3600, 4, 600_000, 600_000, 0xFFFF_FFFF));
+
+        let parsed = read_fit(
+            &fit_file(&records)[..],
+            New_York,
+            &ActivityType::Cycling.into(),
+        )
+        .expect("the file should be read");
+
+        assert_eq!(parsed.records.len(), 2);
+        assert!(parsed.unmapped.is_empty());
+
+        let run = time_distance(&parsed, 0);
+        assert_eq!(
+            run.timestamp,
+            DateTimeTz(New_York.ymd(2020, 3, 1).and_hms(7, 0, 0))
+        );
+        assert_eq!(run.activity, ActivityType::Running.into());
+        assert_eq!(run.distance, Some(5000. * M));
+        assert_eq!(run.duration, Some(1750.5 * S));
+        assert_eq!(
+            run.comments,
+            Some(String::from("Average heart rate: 142 bpm"))
+        );
+
+        let other = time_distance(&parsed, 1);
+        assert_eq!(other.activity, ActivityType::Cycling.into());
+        assert_eq!(other.distance, None);
+        assert_eq!(other.duration, Some(600. * S));
+    }
+
+    #[test]
+    fn it_rejects_corrupt_files() {
+        let mut records = session_definition(0);
+        records.extend(session(0, START, 1, 1_800_000, 1_750_500, 500_000));
+        let mut file = fit_file(&records);
+        let idx = file.len() - 5;
+        file[idx] ^= 0xFF;
+
+        match read_fit(&file[..], New_York, &ActivityType::Running.into()) {
+            Err(Error::FITError(_)) => (),
+            other => panic!("expected a FIT error: {:?}", other),
+        }
+        match read_fit(
+            &b"not a fit file"[..],
+            New_York,
+            &ActivityType::Running.into(),
+        ) {
+            Err(Error::FITError(_)) => (),
+            other => panic!("expected a FIT error: {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,499 @@
+//! Importer for the GPX and TCX files that GPS watches and cycling computers produce.
+//!
+//! Every track (GPX) or activity (TCX) becomes one `TimeDistanceRecord`. The record starts at the
+//! first timestamped trackpoint, the distance is the haversine distance over the trackpoints of
+//! each track segment, and the duration only counts the time spent moving, so pauses at traffic
+//! lights do not count against the workout.
+
+use chrono::{DateTime, Utc};
+use dimensioned::si::{M, S};
+use emseries::DateTimeTz;
+use quick_xml::events::{BytesStart, Event};
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use crate::activity::ActivityId;
+use crate::error::{Error, Result};
+use crate::import::{attribute, Parsed, Unmapped};
+use crate::types::timedistance::{ActivityType, TimeDistanceRecord};
+use crate::TraxRecord;
+
+/// The mean radius of the Earth, in meters.
+const EARTH_RADIUS: f64 = 6_371_008.8;
+
+/// Anything slower than this, in meters per second, counts as standing still.
+const MOVING_SPEED: f64 = 0.5;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Format {
+    Gpx,
+    Tcx,
+}
+
+#[derive(Clone, Debug, Default)]
+struct Point {
+    lat: Option<f64>,
+    lon: Option<f64>,
+    time: Option<DateTime<Utc>>,
+    /// The cumulative distance that the device recorded, which TCX files carry even for indoor
+    /// workouts that have no position.
+    distance: Option<f64>,
+    /// The track segment (GPX) or lap track (TCX) that the point belongs to. Devices start a new
+    /// one after losing the signal or being paused, so the gap between two of them was not
+    /// travelled along a straight line.
+    segment: usize,
+}
+
+impl Point {
+    fn position(&self) -> Option<(f64, f64)> {
+        match (self.lat, self.lon) {
+            (Some(lat), Some(lon)) => Some((lat, lon)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+struct Track {
+    sport: Option<String>,
+    points: Vec<Point>,
+    segments: usize,
+    lap_distance: f64,
+    lap_duration: f64,
+}
+
+/// The great-circle distance, in meters, between two (latitude, longitude) pairs in degrees.
+pub fn haversine(a: (f64, f64), b: (f64, f64)) -> f64 {
+    let (lat1, lon1) = (a.0.to_radians(), a.1.to_radians());
+    let (lat2, lon2) = (b.0.to_radians(), b.1.to_radians());
+    let h = ((lat2 - lat1) / 2.).sin().powi(2)
+        + lat1.cos() * lat2.cos() * ((lon2 - lon1) / 2.).sin().powi(2);
+    2. * EARTH_RADIUS * h.sqrt().asin()
+}
+
+/// Map the sport names that devices write into the built-in activities.
+fn activity_for(sport: &str) -> Option<ActivityId> {
+    match sport.trim().to_lowercase().as_str() {
+        "running" | "run" | "trail_running" | "treadmill_running" => {
+            Some(ActivityType::Running.into())
+        }
+        "biking" | "cycling" | "bike" | "road_biking" | "mountain_biking" | "indoor_cycling" => {
+            Some(ActivityType::Cycling.into())
+        }
+        "walking" | "walk" | "hiking" => Some(ActivityType::Walking.into()),
+        "swimming" | "swim" | "open_water_swimming" | "lap_swimming" => {
+            Some(ActivityType::Swimming.into())
+        }
+        "rowing" | "indoor_rowing" => Some(ActivityType::Rowing.into()),
+        _ => None,
+    }
+}
+
+fn start_element(format: Format, e: &BytesStart, tracks: &mut Vec<Track>) -> Result<()> {
+    match (format, e.local_name().as_ref()) {
+        (Format::Gpx, b"trk") => tracks.push(Track::default()),
+        (Format::Gpx, b"trkseg") | (Format::Tcx, b"Track") => {
+            if let Some(track) = tracks.last_mut() {
+                track.segments += 1;
+            }
+        }
+        (Format::Gpx, b"trkpt") => {
+            if tracks.is_empty() {
+                tracks.push(Track::default());
+            }
+            if let Some(track) = tracks.last_mut() {
+                track.points.push(Point {
+                    lat: attribute(e, "lat")?.and_then(|v| v.parse().ok()),
+                    lon: attribute(e, "lon")?.and_then(|v| v.parse().ok()),
+                    segment: track.segments,
+                    ..Point::default()
+                });
+            }
+        }
+        (Format::Tcx, b"Activity") => tracks.push(Track {
+            sport: attribute(e, "Sport")?,
+            ..Track::default()
+        }),
+        (Format::Tcx, b"Trackpoint") => {
+            if let Some(track) = tracks.last_mut() {
+                track.points.push(Point {
+                    segment: track.segments,
+                    ..Point::default()
+                });
+            }
+        }
+        _ => (),
+    }
+    Ok(())
+}
+
+fn text(format: Format, path: &[String], val: &str, tracks: &mut [Track]) {
+    let track = match tracks.last_mut() {
+        Some(track) => track,
+        None => return,
+    };
+    let parent = |depth: usize| {
+        path.len()
+            .checked_sub(depth + 1)
+            .and_then(|idx| path.get(idx))
+            .map(|s| s.as_str())
+    };
+
+    match (format, parent(0), parent(1)) {
+        (Format::Gpx, Some("type"), Some("trk")) => track.sport = Some(String::from(val)),
+        (Format::Gpx, Some("time"), Some("trkpt"))
+        | (Format::Tcx, Some("Time"), Some("Trackpoint")) => {
+            if let Some(point) = track.points.last_mut() {
+                point.time = DateTime::parse_from_rfc3339(val)
+                    .ok()
+                    .map(|time| time.with_timezone(&Utc));
+            }
+        }
+        (Format::Tcx, Some("LatitudeDegrees"), Some("Position")) => {
+            if let Some(point) = track.points.last_mut() {
+                point.lat = val.parse().ok();
+            }
+        }
+        (Format::Tcx, Some("LongitudeDegrees"), Some("Position")) => {
+            if let Some(point) = track.points.last_mut() {
+                point.lon = val.parse().ok();
+            }
+        }
+        (Format::Tcx, Some("DistanceMeters"), Some("Trackpoint")) => {
+            if let Some(point) = track.points.last_mut() {
+                point.distance = val.parse().ok();
+            }
+        }
+        (Format::Tcx, Some("DistanceMeters"), Some("Lap")) => {
+            track.lap_distance += val.parse::<f64>().unwrap_or(0.)
+        }
+        (Format::Tcx, Some("TotalTimeSeconds"), Some("Lap")) => {
+            track.lap_duration += val.parse::<f64>().unwrap_or(0.)
+        }
+        _ => (),
+    }
+}
+
+fn read_tracks<R: BufRead>(format: Format, reader: R) -> Result<Vec<Track>> {
+    let mut reader = quick_xml::Reader::from_reader(reader);
+    reader.config_mut().trim_text(true);
+
+    let mut tracks = Vec::new();
+    let mut path: Vec<String> = Vec::new();
+    let mut buf = Vec::new();
+    loop {
+        match reader.read_event_into(&mut buf).map_err(Error::XMLError)? {
+            Event::Start(e) => {
+                start_element(format, &e, &mut tracks)?;
+                path.push(String::from_utf8_lossy(e.local_name().as_ref()).into_owned());
+            }
+            Event::Empty(e) => start_element(format, &e, &mut tracks)?,
+            Event::Text(e) => {
+                let val = e.unescape().map_err(Error::XMLError)?;
+                text(format, &path, &val, &mut tracks);
+            }
+            Event::End(_) => {
+                path.pop();
+            }
+            Event::Eof => break,
+            _ => (),
+        }
+        buf.clear();
+    }
+    Ok(tracks)
+}
+
+fn to_record(
+    track: &Track,
+    timezone: chrono_tz::Tz,
+    fallback: &ActivityId,
+) -> std::result::Result<TimeDistanceRecord, String> {
+    let timed: Vec<&Point> = track.points.iter().filter(|p| p.time.is_some()).collect();
+    let start = timed
+        .first()
+        .and_then(|p| p.time)
+        .ok_or_else(|| String::from("the track has no timestamps"))?;
+
+    let positioned: Vec<&Point> = track
+        .points
+        .iter()
+        .filter(|p| p.position().is_some())
+        .collect();
+    let distance = if positioned.len() > 1 {
+        positioned
+            .windows(2)
+            .filter(|pair| pair[0].segment == pair[1].segment)
+            .filter_map(|pair| Some(haversine(pair[0].position()?, pair[1].position()?)))
+            .sum()
+    } else {
+        track
+            .points
+            .iter()
+            .filter_map(|p| p.distance)
+            .fold(track.lap_distance, f64::max)
+    };
+
+    /* Pairs of points where either lacks both a position and a recorded distance are assumed to be
+     * moving, as there is no way to tell otherwise. The time between two segments is a pause. */
+    let mut moving = 0.;
+    for pair in timed.windows(2) {
+        if pair[0].segment != pair[1].segment {
+            continue;
+        }
+        let elapsed =
+            (pair[1].time.unwrap() - pair[0].time.unwrap()).num_milliseconds() as f64 / 1000.;
+        if elapsed <= 0. {
+            continue;
+        }
+        let covered = match (pair[0].position(), pair[1].position()) {
+            (Some(a), Some(b)) => Some(haversine(a, b)),
+            _ => match (pair[0].distance, pair[1].distance) {
+                (Some(a), Some(b)) => Some(b - a),
+                _ => None,
+            },
+        };
+        match covered {
+            Some(covered) if covered / elapsed < MOVING_SPEED => (),
+            _ => moving += elapsed,
+        }
+    }
+    if moving == 0. {
+        moving = track.lap_duration;
+    }
+
+    let activity = track
+        .sport
+        .as_ref()
+        .and_then(|sport| activity_for(sport))
+        .unwrap_or_else(|| fallback.clone());
+
+    Ok(TimeDistanceRecord::new(
+        DateTimeTz(start.with_timezone(&timezone)),
+        activity,
+        if distance > 0. {
+            Some(distance * M)
+        } else {
+            None
+        },
+        if moving > 0. { Some(moving * S) } else { None },
+        None,
+    ))
+}
+
+fn read<R: BufRead>(
+    format: Format,
+    reader: R,
+    timezone: chrono_tz::Tz,
+    fallback: &ActivityId,
+) -> Result<Parsed> {
+    let mut parsed = Parsed::default();
+    for (idx, track) in read_tracks(format, reader)?.iter().enumerate() {
+        match to_record(track, timezone, fallback) {
+            Ok(record) => parsed.records.push((idx + 1, TraxRecord::from(record))),
+            Err(reason) => parsed.unmapped.push(Unmapped {
+                line: idx + 1,
+                reason,
+            }),
+        }
+    }
+    Ok(parsed)
+}
+
+/// Read every track in a GPX file. Tracks that do not name a known activity type get the
+/// `fallback` activity. Unmapped entries are numbered by track.
+pub fn read_gpx<R: BufRead>(
+    reader: R,
+    timezone: chrono_tz::Tz,
+    fallback: &ActivityId,
+) -> Result<Parsed> {
+    read(Format::Gpx, reader, timezone, fallback)
+}
+
+/// Read every activity in a TCX file. Activities that do not name a known sport get the
+/// `fallback` activity. Unmapped entries are numbered by activity.
+pub fn read_tcx<R: BufRead>(
+    reader: R,
+    timezone: chrono_tz::Tz,
+    fallback: &ActivityId,
+) -> Result<Parsed> {
+    read(Format::Tcx, reader, timezone, fallback)
+}
+
+/// Read a GPX or TCX file, deciding which by the file extension.
+pub fn read_file(path: &Path, timezone: chrono_tz::Tz, fallback: &ActivityId) -> Result<Parsed> {
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase());
+    let format = match extension.as_deref() {
+        Some("gpx") => Format::Gpx,
+        Some("tcx") => Format::Tcx,
+        _ => return Err(Error::UnsupportedFormat(path.to_path_buf())),
+    };
+    let file = fs::File::open(path).map_err(Error::IOError)?;
+    read(format, BufReader::new(file), timezone, fallback)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::timedistance::TimeDistanceRecord;
+    use chrono::TimeZone;
+    use chrono_tz::America::New_York;
+
+    const GPX: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+<gpx version=\"1.1\" creator=\"test\" xmlns=\"http://www.topografix.com/GPX/1/1\">
+  <trk>
+    <name>Morning Run</name>
+    <type>running</type>
+    <trkseg>
+      <trkpt lat=\"0.0\" lon=\"0.0\"><ele>10</ele><time>2020-03-01T12:00:00Z</time></trkpt>
+      <trkpt lat=\"0.001\" lon=\"0.0\"><time>2020-03-01T12:01:00Z</time></trkpt>
+      <trkpt lat=\"0.001\" lon=\"0.0\"><time>2020-03-01T12:02:00Z</time></trkpt>
+      <trkpt lat=\"0.002\" lon=\"0.0\"><time>2020-03-01T12:03:00Z</time></trkpt>
+    </trkseg>
+  </trk>
+  <trk>
+    <type>kayaking</type>
+    <trkseg>
+      <trkpt lat=\"0.0\" lon=\"0.0\"/>
+      <trkpt lat=\"0.0\" lon=\"0.001\"/>
+    </trkseg>
+  </trk>
+</gpx>";
+
+    const TCX: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+<TrainingCenterDatabase xmlns=\"http://www.garmin.com/xmlschemas/TrainingCenterDatabase/v2\">
+  <Activities>
+    <Activity Sport=\"Biking\">
+      <Id>2020-03-01T12:00:00Z</Id>
+      <Lap StartTime=\"2020-03-01T12:00:00Z\">
+        <TotalTimeSeconds>120</TotalTimeSeconds>
+        <DistanceMeters>500</DistanceMeters>
+        <Track>
+          <Trackpoint>
+            <Time>2020-03-01T12:00:00Z</Time>
+            <DistanceMeters>0</DistanceMeters>
+          </Trackpoint>
+          <Trackpoint>
+            <Time>2020-03-01T12:01:00Z</Time>
+            <DistanceMeters>250</DistanceMeters>
+          </Trackpoint>
+          <Trackpoint>
+            <Time>2020-03-01T12:02:00Z</Time>
+            <DistanceMeters>500</DistanceMeters>
+          </Trackpoint>
+        </Track>
+      </Lap>
+    </Activity>
+    <Activity Sport=\"Other\">
+      <Lap StartTime=\"2020-03-02T12:00:00Z\">
+        <Track>
+          <Trackpoint>
+            <Time>2020-03-02T12:00:00Z</Time>
+            <Position><LatitudeDegrees>0.0</LatitudeDegrees><LongitudeDegrees>0.0</LongitudeDegrees></Position>
+          </Trackpoint>
+          <Trackpoint>
+            <Time>2020-03-02T12:10:00Z</Time>
+            <Position><LatitudeDegrees>0.01</LatitudeDegrees><LongitudeDegrees>0.0</LongitudeDegrees></Position>
+          </Trackpoint>
+        </Track>
+      </Lap>
+    </Activity>
+  </Activities>
+</TrainingCenterDatabase>";
+
+    fn time_distance(parsed: &Parsed, idx: usize) -> &TimeDistanceRecord {
+        match parsed.records[idx].1 {
+            TraxRecord::TimeDistance(ref rec) => rec,
+            ref rec => panic!("expected a time/distance record: {:?}", rec),
+        }
+    }
+
+    #[test]
+    fn it_computes_haversine_distances() {
+        let one_degree = haversine((0., 0.), (1., 0.));
+        assert!((one_degree - 111_195.08).abs() < 0.01);
+        assert!(haversine((45., 7.), (45., 7.)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn it_reads_gpx_tracks() {
+        let fallback = ActivityId::from(ActivityType::Walking);
+        let parsed = read_gpx(GPX.as_bytes(), New_York, &fallback).expect("gpx should parse");
+
+        assert_eq!(parsed.records.len(), 1);
+        let record = time_distance(&parsed, 0);
+        assert_eq!(
+            record.timestamp,
+            DateTimeTz(New_York.ymd(2020, 3, 1).and_hms(7, 0, 0))
+        );
+        assert_eq!(record.activity, ActivityId::from(ActivityType::Running));
+        let distance = record
+            .distance
+            .expect("the track has a distance")
+            .value_unsafe;
+        assert!((distance - 222.39).abs() < 0.01);
+        assert_eq!(record.duration, Some(120. * S));
+
+        assert_eq!(
+            parsed.unmapped,
+            vec![Unmapped {
+                line: 2,
+                reason: String::from("the track has no timestamps"),
+            }]
+        );
+    }
+
+    #[test]
+    fn it_does_not_count_the_distance_between_track_segments() {
+        // The watch lost the signal on the bus between the two segments.
+        let gpx = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+<gpx version=\"1.1\" creator=\"test\" xmlns=\"http://www.topografix.com/GPX/1/1\">
+  <trk>
+    <type>walking</type>
+    <trkseg>
+      <trkpt lat=\"0.0\" lon=\"0.0\"><time>2020-03-01T12:00:00Z</time></trkpt>
+      <trkpt lat=\"0.001\" lon=\"0.0\"><time>2020-03-01T12:01:00Z</time></trkpt>
+    </trkseg>
+    <trkseg>
+      <trkpt lat=\"1.0\" lon=\"0.0\"><time>2020-03-01T13:00:00Z</time></trkpt>
+      <trkpt lat=\"1.001\" lon=\"0.0\"><time>2020-03-01T13:01:00Z</time></trkpt>
+    </trkseg>
+  </trk>
+</gpx>";
+        let fallback = ActivityId::from(ActivityType::Walking);
+        let parsed = read_gpx(gpx.as_bytes(), New_York, &fallback).expect("gpx should parse");
+
+        assert_eq!(parsed.records.len(), 1);
+        let record = time_distance(&parsed, 0);
+        let distance = record
+            .distance
+            .expect("the track has a distance")
+            .value_unsafe;
+        assert!((distance - 222.39).abs() < 0.01);
+        assert_eq!(record.duration, Some(120. * S));
+    }
+
+    #[test]
+    fn it_reads_tcx_activities() {
+        let fallback = ActivityId::from(ActivityType::Walking);
+        let parsed = read_tcx(TCX.as_bytes(), New_York, &fallback).expect("tcx should parse");
+
+        assert_eq!(parsed.records.len(), 2);
+        let indoor = time_distance(&parsed, 0);
+        assert_eq!(indoor.activity, ActivityId::from(ActivityType::Cycling));
+        assert_eq!(indoor.distance, Some(500. * M));
+        assert_eq!(indoor.duration, Some(120. * S));
+
+        let other = time_distance(&parsed, 1);
+        assert_eq!(other.activity, fallback);
+        let distance = other
+            .distance
+            .expect("the track has a distance")
+            .value_unsafe;
+        assert!((distance - 1111.95).abs() < 0.01);
+        assert_eq!(other.duration, Some(600. * S));
+    }
+}
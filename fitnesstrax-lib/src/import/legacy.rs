@@ -0,0 +1,187 @@
+//! Importer for the data of the older health application which this one replaces.
+//!
+//! That application kept its data in an emseries series file, which is also what it exports.
+//! Every line is a JSON object with the id of a record and the record body. A later line with
+//! the same id replaces the record, or deletes it when the body is `null`:
+//!
+//! ```text
+//! {"id":"88921380-3ad5-462e-948e-89b46b459752","data":{"date":"2017-10-28T08:00:00Z","weight":85.2}}
+//! {"id":"04a63393-4183-49a3-8fe1-0793dc26bdba","data":{"date":"2017-10-28T00:00:00Z","steps":6500}}
+//! {"id":"391ca31a-3986-43c4-8202-1cb5cc424c5c","data":{"distance":12200,"date":"2017-10-28T19:27:00Z","activity":"Cycling","comments":null,"duration":3120}}
+//! {"id":"9fbc83e7-0195-4929-93fd-7fd7927ff158","data":{"date":"2017-10-28T00:00:00Z","val":"A good day"}}
+//! {"id":"04a63393-4183-49a3-8fe1-0793dc26bdba","data":null}
+//! ```
+//!
+//! The bodies have no type tag. They have the same shape as the weight, step, time/distance and
+//! comment records of this crate, which were carried over from that application, so each body is
+//! recognized by its fields. `fixtures/legacy-export.series` holds a complete sample. Blank lines
+//! are ignored, and any body which is not one of those records gets reported as unmapped.
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read};
+
+use crate::error::{Error, Result};
+use crate::import::{import_records, ImportReport, Parsed, Unmapped};
+use crate::types::{comments, steps, timedistance, weight};
+use crate::{Trax, TraxRecord};
+
+#[derive(Deserialize)]
+struct Line {
+    id: String,
+    data: Option<serde_json::Value>,
+}
+
+/// The record bodies, in the order in which they are tried. Time/distance records come first
+/// since theirs is the only body with an activity.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Body {
+    TimeDistance(timedistance::TimeDistanceRecord),
+    Weight(weight::WeightRecord),
+    Steps(steps::StepRecord),
+    Comments(comments::Comments),
+}
+
+impl From<Body> for TraxRecord {
+    fn from(body: Body) -> TraxRecord {
+        match body {
+            Body::TimeDistance(rec) => TraxRecord::TimeDistance(rec),
+            Body::Weight(rec) => TraxRecord::Weight(rec),
+            Body::Steps(rec) => TraxRecord::Steps(rec),
+            Body::Comments(rec) => TraxRecord::Comments(rec),
+        }
+    }
+}
+
+/// Read a legacy export without touching the series. Each record comes with the line of its
+/// latest version.
+pub fn read_export<R: Read>(reader: R) -> Result<Parsed> {
+    let mut parsed = Parsed::default();
+    /* Records in the order in which their ids first appear, so that the result is stable. */
+    let mut records: Vec<Option<(usize, TraxRecord)>> = Vec::new();
+    let mut positions: HashMap<String, usize> = HashMap::new();
+
+    for (idx, line) in BufReader::new(reader).lines().enumerate() {
+        let line_no = idx + 1;
+        let line = line.map_err(Error::IOError)?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let entry = match serde_json::from_str::<Line>(&line) {
+            Ok(entry) => entry,
+            Err(err) => {
+                parsed.unmapped.push(Unmapped {
+                    line: line_no,
+                    reason: format!("invalid entry: {}", err),
+                });
+                continue;
+            }
+        };
+        let record = match entry.data {
+            Some(data) => match serde_json::from_value::<Body>(data) {
+                Ok(body) => Some((line_no, TraxRecord::from(body))),
+                Err(_) => {
+                    parsed.unmapped.push(Unmapped {
+                        line: line_no,
+                        reason: String::from("unrecognized record"),
+                    });
+                    continue;
+                }
+            },
+            None => None,
+        };
+
+        match positions.get(&entry.id) {
+            Some(&pos) => records[pos] = record,
+            None => {
+                positions.insert(entry.id, records.len());
+                records.push(record);
+            }
+        }
+    }
+
+    parsed.records = records.into_iter().flatten().collect();
+    Ok(parsed)
+}
+
+/// Import a legacy export into the series. Importing the same export more than once does not
+/// create duplicate records.
+pub fn import<R: Read>(trax: &mut Trax, reader: R) -> Result<ImportReport> {
+    let parsed = read_export(reader)?;
+    import_records(trax, parsed)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::CleanupFile;
+    use crate::Params;
+    use dimensioned::si::{KG, M, S};
+    use std::path;
+
+    const EXPORT: &str = include_str!("../../fixtures/legacy-export.series");
+
+    #[test]
+    fn it_reads_a_legacy_export() {
+        let Parsed { records, unmapped } =
+            read_export(EXPORT.as_bytes()).expect("export should be read");
+
+        assert_eq!(
+            records
+                .iter()
+                .map(|(line, _)| *line)
+                .collect::<Vec<usize>>(),
+            vec![8, 2, 3, 4, 5]
+        );
+        match records[0].1 {
+            TraxRecord::Weight(ref rec) => assert_eq!(rec.weight, 85.0 * KG),
+            ref rec => panic!("expected a weight record: {:?}", rec),
+        }
+        match records[1].1 {
+            TraxRecord::Steps(ref rec) => assert_eq!(rec.steps, 6500),
+            ref rec => panic!("expected a step record: {:?}", rec),
+        }
+        match records[2].1 {
+            TraxRecord::TimeDistance(ref rec) => {
+                assert_eq!(rec.distance, Some(12200. * M));
+                assert_eq!(rec.duration, Some(3120. * S));
+            }
+            ref rec => panic!("expected a time/distance record: {:?}", rec),
+        }
+        match records[4].1 {
+            TraxRecord::Comments(ref rec) => assert_eq!(rec.text(), "A good day"),
+            ref rec => panic!("expected comments: {:?}", rec),
+        }
+
+        assert_eq!(
+            unmapped.iter().map(|u| u.line).collect::<Vec<usize>>(),
+            vec![6, 7]
+        );
+        assert_eq!(unmapped[0].reason, "unrecognized record");
+    }
+
+    #[test]
+    fn it_imports_an_export_only_once() {
+        let series_path = path::PathBuf::from("var/it_imports_an_export_only_once.series");
+        let _cleanup = CleanupFile(series_path.clone());
+        let mut trax = Trax::create(Params { series_path }).expect("the app to be created");
+
+        let report = import(&mut trax, EXPORT.as_bytes()).expect("import should succeed");
+        assert_eq!(report.imported, 4);
+        assert_eq!(report.duplicates, 0);
+        assert_eq!(
+            report
+                .unmapped
+                .iter()
+                .map(|u| u.line)
+                .collect::<Vec<usize>>(),
+            vec![4, 6, 7]
+        );
+        assert_eq!(report.unmapped[0].reason, "unknown activity: Unicycling");
+
+        let report = import(&mut trax, EXPORT.as_bytes()).expect("import should succeed");
+        assert_eq!(report.imported, 0);
+        assert_eq!(report.duplicates, 4);
+    }
+}
@@ -0,0 +1,292 @@
+use chrono::NaiveDate;
+use emseries::{DateTimeTz, Recordable, UniqueId};
+use quick_xml::events::BytesStart;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+use crate::activity::ActivityId;
+use crate::comments::Comments;
+use crate::error::{Error, Result};
+use crate::steps::StepRecord;
+use crate::{Trax, TraxRecord};
+
+pub mod apple_health;
+pub mod fit;
+pub mod gps;
+pub mod legacy;
+
+/// An entry from an import source which could not be turned into a record.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Unmapped {
+    /// The line (or entry) number in the source, starting at 1.
+    pub line: usize,
+    pub reason: String,
+}
+
+impl fmt::Display for Unmapped {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.reason)
+    }
+}
+
+/// The records read from an import source, each paired with the line that it came from, along
+/// with the entries that could not be converted.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Parsed {
+    pub records: Vec<(usize, TraxRecord)>,
+    pub unmapped: Vec<Unmapped>,
+}
+
+/// The outcome of an import. Records which were already present in the series are counted as
+/// duplicates and are not added again, so importing the same source twice is harmless. Notes for
+/// days that already have a different note are left out and reported as conflicts, so that an
+/// import never overwrites the journal. Each day has one step total, which an import only replaces
+/// with a larger one, as a later export of the same day has counted more steps.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ImportReport {
+    pub imported: usize,
+    pub duplicates: usize,
+    pub conflicts: Vec<Unmapped>,
+    pub unmapped: Vec<Unmapped>,
+}
+
+/// Read and unescape an XML attribute.
+fn attribute(e: &BytesStart, name: &str) -> Result<Option<String>> {
+    match e
+        .try_get_attribute(name)
+        .map_err(|err| Error::XMLError(err.into()))?
+    {
+        Some(attr) => attr
+            .unescape_value()
+            .map(|v| Some(v.into_owned()))
+            .map_err(Error::XMLError),
+        None => Ok(None),
+    }
+}
+
+/// Read a workout file from a GPS device, deciding between FIT, GPX and TCX by the file
+/// extension. Workouts that do not name a known activity get the `fallback` activity.
+pub fn read_workout_file(
+    path: &Path,
+    timezone: chrono_tz::Tz,
+    fallback: &ActivityId,
+) -> Result<Parsed> {
+    let is_fit = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.eq_ignore_ascii_case("fit"))
+        .unwrap_or(false);
+    if is_fit {
+        fit::read_file(path, timezone, fallback)
+    } else {
+        gps::read_file(path, timezone, fallback)
+    }
+}
+
+/// The records which the series already holds around the time span of an import, so that every
+/// imported record can be checked without searching the whole series again.
+struct Existing {
+    /// Records by the second of their timestamp, which identical records always share.
+    records: HashMap<i64, Vec<TraxRecord>>,
+    /// The note of each day, by the date in the timezone of the note.
+    notes: HashMap<NaiveDate, String>,
+    /// The step total of each day, by the date in the timezone of the total.
+    steps: HashMap<NaiveDate, (UniqueId, u32)>,
+}
+
+impl Existing {
+    fn load(trax: &Trax, records: &[(usize, TraxRecord)]) -> Result<Existing> {
+        let mut existing = Existing {
+            records: HashMap::new(),
+            notes: HashMap::new(),
+            steps: HashMap::new(),
+        };
+        let timestamps = records.iter().map(|(_, record)| record.timestamp());
+        let (start, end) = match (timestamps.clone().min(), timestamps.max()) {
+            (Some(start), Some(end)) => (start, end),
+            _ => return Ok(existing),
+        };
+
+        /* A day on either side covers the notes and step totals of the days that the span touches. */
+        let mut history = trax.get_history(
+            DateTimeTz(start.0 - chrono::Duration::days(1)),
+            DateTimeTz(end.0 + chrono::Duration::days(1)),
+        )?;
+        history.sort_by_key(|(_, record)| record.timestamp());
+        for (uid, record) in history {
+            existing.add(uid.clone(), record.clone());
+        }
+        Ok(existing)
+    }
+
+    fn add(&mut self, uid: UniqueId, record: TraxRecord) {
+        match record {
+            TraxRecord::Comments(ref comments) => {
+                self.notes
+                    .entry(comments.timestamp().0.naive_local().date())
+                    .or_insert_with(|| comments.text().to_owned());
+            }
+            TraxRecord::Steps(ref rec) => {
+                let date = rec.timestamp().0.naive_local().date();
+                match self.steps.get(&date) {
+                    Some((_, total)) if *total >= rec.steps => (),
+                    _ => {
+                        self.steps.insert(date, (uid, rec.steps));
+                    }
+                }
+                return;
+            }
+            _ => (),
+        }
+        self.records
+            .entry(record.timestamp().0.timestamp())
+            .or_default()
+            .push(record);
+    }
+
+    fn contains(&self, record: &TraxRecord) -> bool {
+        self.records
+            .get(&record.timestamp().0.timestamp())
+            .map(|records| records.contains(record))
+            .unwrap_or(false)
+    }
+
+    fn note(&self, comments: &Comments) -> Option<&String> {
+        self.notes.get(&comments.timestamp().0.naive_local().date())
+    }
+
+    fn steps(&self, steps: &StepRecord) -> Option<&(UniqueId, u32)> {
+        self.steps.get(&steps.timestamp().0.naive_local().date())
+    }
+}
+
+/// Add records to the series, skipping any which the series already contains. Records which the
+/// series rejects, such as those that refer to an unknown activity, are reported as unmapped
+/// rather than aborting the import.
+pub fn import_records(trax: &mut Trax, parsed: Parsed) -> Result<ImportReport> {
+    let mut report = ImportReport {
+        unmapped: parsed.unmapped,
+        ..ImportReport::default()
+    };
+    let mut existing = Existing::load(trax, &parsed.records)?;
+
+    for (line, record) in parsed.records {
+        if existing.contains(&record) {
+            report.duplicates += 1;
+            continue;
+        }
+        if let TraxRecord::Comments(ref comments) = record {
+            match existing.note(comments) {
+                Some(note) if note == comments.text() => {
+                    report.duplicates += 1;
+                    continue;
+                }
+                Some(_) => {
+                    report.conflicts.push(Unmapped {
+                        line,
+                        reason: String::from("the day already has a different note"),
+                    });
+                    continue;
+                }
+                None => (),
+            }
+        }
+        if let TraxRecord::Steps(ref steps) = record {
+            match existing.steps(steps) {
+                Some((_, total)) if *total >= steps.steps => {
+                    report.duplicates += 1;
+                    continue;
+                }
+                Some((uid, _)) => {
+                    let uid = trax.replace_record(uid.clone(), record.clone())?;
+                    report.imported += 1;
+                    existing.add(uid, record);
+                    continue;
+                }
+                None => (),
+            }
+        }
+        match trax.insert_record(record.clone()) {
+            Ok(uid) => {
+                report.imported += 1;
+                existing.add(uid, record);
+            }
+            Err(Error::UnknownActivity(id)) => report.unmapped.push(Unmapped {
+                line,
+                reason: format!("unknown activity: {}", id),
+            }),
+            Err(err) => return Err(err),
+        }
+    }
+
+    report.unmapped.sort_by_key(|unmapped| unmapped.line);
+    Ok(report)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::CleanupFile;
+    use crate::Params;
+    use chrono::TimeZone;
+    use chrono_tz::America::New_York;
+    use std::path;
+
+    #[test]
+    fn it_does_not_overwrite_notes() {
+        let series_path = path::PathBuf::from("var/it_does_not_overwrite_notes.series");
+        let _cleanup = CleanupFile(series_path.clone());
+        let mut trax = Trax::create(Params { series_path }).expect("the app to be created");
+        let date = New_York.ymd(2020, 3, 1);
+        trax.set_note(&date, "Written in the journal")
+            .expect("the note to be saved");
+
+        let note =
+            |text| TraxRecord::Comments(Comments::new(DateTimeTz(date.and_hms(9, 0, 0)), text));
+        let parsed = Parsed {
+            records: vec![(1, note("Written in the journal")), (2, note("Imported"))],
+            unmapped: vec![],
+        };
+        let report = import_records(&mut trax, parsed).expect("import should succeed");
+
+        assert_eq!(report.imported, 0);
+        assert_eq!(report.duplicates, 1);
+        assert_eq!(
+            report
+                .conflicts
+                .iter()
+                .map(|c| c.line)
+                .collect::<Vec<usize>>(),
+            vec![2]
+        );
+        assert_eq!(
+            trax.note(&date)
+                .unwrap()
+                .map(|(_, note)| note.text().to_owned()),
+            Some(String::from("Written in the journal"))
+        );
+    }
+
+    #[test]
+    fn it_skips_records_repeated_within_an_import() {
+        let series_path =
+            path::PathBuf::from("var/it_skips_records_repeated_within_an_import.series");
+        let _cleanup = CleanupFile(series_path.clone());
+        let mut trax = Trax::create(Params { series_path }).expect("the app to be created");
+        let timestamp = DateTimeTz(New_York.ymd(2020, 3, 1).and_hms(9, 0, 0));
+
+        let parsed = Parsed {
+            records: vec![
+                (1, TraxRecord::steps(timestamp.clone(), 6500)),
+                (2, TraxRecord::steps(timestamp.clone(), 6500)),
+                (3, TraxRecord::steps(timestamp, 7000)),
+            ],
+            unmapped: vec![],
+        };
+        let report = import_records(&mut trax, parsed).expect("import should succeed");
+
+        assert_eq!(report.imported, 2);
+        assert_eq!(report.duplicates, 1);
+    }
+}
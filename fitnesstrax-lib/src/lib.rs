@@ -5,20 +5,33 @@ extern crate serde;
 extern crate serde_derive;
 extern crate serde_json;
 
+use activity::{ActivityId, ActivityKind, ActivityRegistry};
 use dimensioned::si::{Kilogram, Meter, Second};
-use emseries::{DateTimeTz, UniqueId};
+use emseries::{DateTimeTz, Recordable, UniqueId};
+use std::fs;
+use std::io;
 use std::path;
 
+pub mod analytics;
+pub mod csv;
 pub mod error;
+pub mod goals;
+pub mod import;
+pub mod personal_records;
+pub mod streaks;
+pub mod timezone;
 mod types;
+#[cfg(test)]
 mod utils;
 pub use error::{Error, Result};
+pub use types::activity;
 pub use types::comments;
 pub use types::repduration;
 pub use types::setrep;
 pub use types::steps;
 pub use types::timedistance;
 pub use types::weight;
+pub use types::weightedset;
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum TraxRecord {
@@ -28,6 +41,7 @@ pub enum TraxRecord {
     Steps(steps::StepRecord),
     TimeDistance(timedistance::TimeDistanceRecord),
     Weight(weight::WeightRecord),
+    WeightedSet(weightedset::WeightedSetRecord),
 }
 
 impl TraxRecord {
@@ -37,7 +51,7 @@ impl TraxRecord {
 
     pub fn repduration(
         timestamp: DateTimeTz,
-        activity: repduration::ActivityType,
+        activity: ActivityId,
         reps: Vec<Second<f64>>,
         comments: Option<String>,
     ) -> TraxRecord {
@@ -48,17 +62,16 @@ impl TraxRecord {
 
     pub fn setrep(
         timestamp: DateTimeTz,
-        activity: setrep::ActivityType,
+        activity: ActivityId,
         sets: Vec<u32>,
         comments: Option<String>,
     ) -> Result<TraxRecord> {
-        setrep::SetRepRecord::new(timestamp, activity, sets, comments)
-            .map(|r| TraxRecord::SetRep(r))
+        setrep::SetRepRecord::new(timestamp, activity, sets, comments).map(TraxRecord::SetRep)
     }
 
     pub fn timedistance(
         timestamp: DateTimeTz,
-        activity: timedistance::ActivityType,
+        activity: ActivityId,
         distance: Option<Meter<f64>>,
         duration: Option<Second<f64>>,
         comments: Option<String>,
@@ -71,6 +84,50 @@ impl TraxRecord {
     pub fn weight(timestamp: DateTimeTz, weight: Kilogram<f64>) -> TraxRecord {
         TraxRecord::Weight(weight::WeightRecord::new(timestamp, weight))
     }
+
+    /// The day that this record belongs to, which is the date in the timezone that the record
+    /// was written in. A run at 23:30 in New York stays on that day even though it is already
+    /// the next day in UTC.
+    pub fn date(&self) -> chrono::NaiveDate {
+        self.timestamp().0.date().naive_local()
+    }
+
+    /// The activity that this record refers to, along with the kind of activity that the record
+    /// requires. Records that are not tied to an activity return `None`.
+    pub fn activity(&self) -> Option<(&ActivityId, ActivityKind)> {
+        match self {
+            TraxRecord::Comments(_) => None,
+            TraxRecord::RepDuration(rec) => Some((&rec.activity, ActivityKind::RepDuration)),
+            TraxRecord::SetRep(rec) => Some((&rec.activity, ActivityKind::SetRep)),
+            TraxRecord::Steps(_) => None,
+            TraxRecord::TimeDistance(rec) => Some((&rec.activity, ActivityKind::TimeDistance)),
+            TraxRecord::Weight(_) => None,
+            TraxRecord::WeightedSet(rec) => Some((&rec.activity, ActivityKind::WeightedSet)),
+        }
+    }
+
+    pub fn weightedset(
+        timestamp: DateTimeTz,
+        activity: ActivityId,
+        sets: Vec<weightedset::WeightedSet>,
+        comments: Option<String>,
+    ) -> TraxRecord {
+        TraxRecord::WeightedSet(weightedset::WeightedSetRecord::new(
+            timestamp, activity, sets, comments,
+        ))
+    }
+}
+
+impl From<repduration::RepDurationRecord> for TraxRecord {
+    fn from(r: repduration::RepDurationRecord) -> TraxRecord {
+        TraxRecord::RepDuration(r)
+    }
+}
+
+impl From<setrep::SetRepRecord> for TraxRecord {
+    fn from(r: setrep::SetRepRecord) -> TraxRecord {
+        TraxRecord::SetRep(r)
+    }
 }
 
 impl From<steps::StepRecord> for TraxRecord {
@@ -91,6 +148,12 @@ impl From<weight::WeightRecord> for TraxRecord {
     }
 }
 
+impl From<weightedset::WeightedSetRecord> for TraxRecord {
+    fn from(r: weightedset::WeightedSetRecord) -> TraxRecord {
+        TraxRecord::WeightedSet(r)
+    }
+}
+
 impl emseries::Recordable for TraxRecord {
     fn timestamp(&self) -> DateTimeTz {
         match self {
@@ -100,6 +163,7 @@ impl emseries::Recordable for TraxRecord {
             TraxRecord::Steps(rec) => rec.timestamp(),
             TraxRecord::TimeDistance(rec) => rec.timestamp(),
             TraxRecord::Weight(rec) => rec.timestamp(),
+            TraxRecord::WeightedSet(rec) => rec.timestamp(),
         }
     }
 
@@ -111,6 +175,7 @@ impl emseries::Recordable for TraxRecord {
             TraxRecord::Steps(rec) => rec.tags(),
             TraxRecord::TimeDistance(rec) => rec.tags(),
             TraxRecord::Weight(rec) => rec.tags(),
+            TraxRecord::WeightedSet(rec) => rec.tags(),
         }
     }
 }
@@ -120,22 +185,176 @@ pub struct Params {
     pub series_path: path::PathBuf,
 }
 
+impl Params {
+    /// The activity registry gets stored next to the series, with the same name and an
+    /// `.activities` extension.
+    pub fn activities_path(&self) -> path::PathBuf {
+        self.series_path.with_extension("activities")
+    }
+
+    /// Goals get stored next to the series as well, with a `.goals` extension.
+    pub fn goals_path(&self) -> path::PathBuf {
+        self.series_path.with_extension("goals")
+    }
+
+    /// And so do the conditions for streaks, with a `.streaks` extension.
+    pub fn streaks_path(&self) -> path::PathBuf {
+        self.series_path.with_extension("streaks")
+    }
+}
+
 pub struct Trax {
     series: emseries::Series<TraxRecord>,
+    activities: ActivityRegistry,
+    goals: goals::Goals,
+    streak_conditions: streaks::StreakConditions,
 }
 
 impl Trax {
-    pub fn new(params: Params) -> Result<Trax> {
-        let series = emseries::Series::open(params.series_path.to_str().unwrap())
-            .map_err(|err| Error::SeriesError(err))?;
+    /// Open an existing series. Opening a path with no file at it is an error, so that a mistyped
+    /// path does not quietly start a new, empty series.
+    pub fn open(params: Params) -> Result<Trax> {
+        let path = &params.series_path;
+        match fs::metadata(path) {
+            Ok(_) => (),
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => {
+                return Err(Error::SeriesMissing(path.clone()))
+            }
+            Err(err) => return Err(Error::SeriesUnreadable(path.clone(), err)),
+        }
+        let series = emseries::Series::open(path.to_str().unwrap()).map_err(|err| match err {
+            emseries::Error::IOError(err) if err.kind() != io::ErrorKind::InvalidData => {
+                Error::SeriesUnreadable(path.clone(), err)
+            }
+            err => Error::SeriesCorrupt(path.clone(), err),
+        })?;
+        let activities = ActivityRegistry::open(&params.activities_path())?;
+        let goals = goals::Goals::open(&params.goals_path())?;
+        let streak_conditions = streaks::StreakConditions::open(&params.streaks_path())?;
+
+        Ok(Trax {
+            series,
+            activities,
+            goals,
+            streak_conditions,
+        })
+    }
+
+    /// Create a new, empty series and open it. There must not be a file at the path already.
+    pub fn create(params: Params) -> Result<Trax> {
+        fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&params.series_path)
+            .map_err(|err| match err.kind() {
+                io::ErrorKind::AlreadyExists => Error::SeriesExists(params.series_path.clone()),
+                _ => Error::IOError(err),
+            })?;
+        Trax::open(params)
+    }
+
+    pub fn activities(&self) -> &ActivityRegistry {
+        &self.activities
+    }
+
+    pub fn add_activity(&mut self, activity: activity::Activity) -> Result<()> {
+        self.activities.add(activity)
+    }
+
+    pub fn goals(&self) -> &[goals::Goal] {
+        self.goals.goals()
+    }
 
-        Ok(Trax { series })
+    pub fn add_goal(&mut self, goal: goals::Goal) -> Result<()> {
+        self.goals.add(goal)
     }
 
+    pub fn remove_goal(&mut self, idx: usize) -> Result<()> {
+        self.goals.remove(idx)
+    }
+
+    /// Evaluate every goal against the whole series as of `today`.
+    pub fn goal_progress(
+        &self,
+        today: chrono::Date<chrono_tz::Tz>,
+        display: timezone::TimezoneDisplay,
+    ) -> Result<Vec<(goals::Goal, goals::Progress)>> {
+        let records = self.all_records()?;
+        Ok(self
+            .goals
+            .goals()
+            .iter()
+            .map(|goal| {
+                (
+                    goal.clone(),
+                    goal.progress(records.iter().map(|(_, record)| record), today, display),
+                )
+            })
+            .collect())
+    }
+
+    pub fn streak_conditions(&self) -> &[streaks::Condition] {
+        self.streak_conditions.conditions()
+    }
+
+    pub fn add_streak_condition(&mut self, condition: streaks::Condition) -> Result<()> {
+        self.streak_conditions.add(condition)
+    }
+
+    pub fn remove_streak_condition(&mut self, idx: usize) -> Result<()> {
+        self.streak_conditions.remove(idx)
+    }
+
+    /// Find the streaks for every tracked condition as of `today`, which should be the current
+    /// date in the user's timezone.
+    pub fn streaks(
+        &self,
+        today: chrono::Date<chrono_tz::Tz>,
+        display: timezone::TimezoneDisplay,
+    ) -> Result<Vec<(streaks::Condition, streaks::Streak)>> {
+        let records = self.all_records()?;
+        Ok(self
+            .streak_conditions
+            .conditions()
+            .iter()
+            .map(|condition| {
+                (
+                    condition.clone(),
+                    streaks::streak(
+                        condition,
+                        records.iter().map(|(_, record)| record),
+                        today,
+                        display,
+                    ),
+                )
+            })
+            .collect())
+    }
+
+    fn validate(&self, record: &TraxRecord) -> Result<()> {
+        match record.activity() {
+            Some((id, kind)) => self.activities.validate(id, kind),
+            None => Ok(()),
+        }
+    }
+
+    /// Add a record to the series. Since there is only one note per day, adding comments for a
+    /// day that already has a note replaces that note.
     pub fn add_record(&mut self, record: TraxRecord) -> Result<emseries::UniqueId> {
-        self.series
-            .put(record)
-            .map_err(|err| Error::SeriesError(err))
+        if let TraxRecord::Comments(ref comments) = record {
+            let date = comments.timestamp().0.date();
+            if let Some((uid, _)) = self.note(&date)? {
+                return self.replace_record(uid, record);
+            }
+        }
+        self.insert_record(record)
+    }
+
+    /// Add a record without looking for a note to replace, for callers which already know that
+    /// there is none.
+    pub(crate) fn insert_record(&mut self, record: TraxRecord) -> Result<emseries::UniqueId> {
+        self.validate(&record)?;
+        self.series.put(record).map_err(Error::SeriesError)
     }
 
     pub fn replace_record(
@@ -143,23 +362,115 @@ impl Trax {
         uid: emseries::UniqueId,
         record: TraxRecord,
     ) -> Result<emseries::UniqueId> {
+        self.validate(&record)?;
         self.series
             .update(uid.clone(), record)
-            .map_err(|err| Error::SeriesError(err))?;
+            .map_err(Error::SeriesError)?;
         Ok(uid)
     }
 
+    pub fn all_records(&self) -> Result<Vec<(UniqueId, TraxRecord)>> {
+        self.series.all_records().map_err(Error::SeriesError)
+    }
+
     pub fn get_record(&self, uid: &emseries::UniqueId) -> Result<Option<TraxRecord>> {
-        self.series
-            .get(uid)
-            .map(|mr| mr)
-            .map_err(|err| Error::SeriesError(err))
+        self.series.get(uid).map_err(Error::SeriesError)
     }
 
     pub fn remove_record(&mut self, uid: &emseries::UniqueId) -> Result<()> {
-        self.series
-            .delete(uid)
-            .map_err(|err| Error::SeriesError(err))
+        self.series.delete(uid).map_err(Error::SeriesError)
+    }
+
+    /// Get the note for a day, along with its id.
+    pub fn note(
+        &self,
+        date: &chrono::Date<chrono_tz::Tz>,
+    ) -> Result<Option<(UniqueId, comments::Comments)>> {
+        let notes = self.notes(date)?;
+        Ok(notes.into_iter().next())
+    }
+
+    /// Set the note for a day, replacing any note that the day already has. Setting an empty note
+    /// removes the note from the day entirely, in which case there is no id to return.
+    pub fn set_note(
+        &mut self,
+        date: &chrono::Date<chrono_tz::Tz>,
+        text: &str,
+    ) -> Result<Option<UniqueId>> {
+        let mut notes = self.notes(date)?.into_iter();
+        let current = notes.next();
+        /* Older series may have several notes on a day. Those get collapsed into one here. */
+        for (uid, _) in notes {
+            self.remove_record(&uid)?;
+        }
+
+        match (current, text.trim().is_empty()) {
+            (Some((uid, _)), true) => self.remove_record(&uid).map(|_| None),
+            (None, true) => Ok(None),
+            (Some((uid, comments)), false) => self
+                .replace_record(
+                    uid,
+                    TraxRecord::Comments(comments::Comments::new(comments.timestamp(), text)),
+                )
+                .map(Some),
+            (None, false) => self
+                .series
+                .put(TraxRecord::Comments(comments::Comments::new(
+                    DateTimeTz(timezone::day_start(date)),
+                    text,
+                )))
+                .map(Some)
+                .map_err(Error::SeriesError),
+        }
+    }
+
+    fn notes(
+        &self,
+        date: &chrono::Date<chrono_tz::Tz>,
+    ) -> Result<Vec<(UniqueId, comments::Comments)>> {
+        let mut notes: Vec<(UniqueId, comments::Comments)> = self
+            .get_history(
+                DateTimeTz(timezone::day_start(date)),
+                DateTimeTz(timezone::day_start(&date.succ())),
+            )?
+            .into_iter()
+            .filter_map(|(uid, record)| match record {
+                TraxRecord::Comments(comments) => Some((uid.clone(), comments.clone())),
+                _ => None,
+            })
+            .collect();
+        notes.sort_by_key(|(_, comments)| comments.timestamp());
+        Ok(notes)
+    }
+
+    /// Summarize the records from `start` to `end`, inclusive, by `period`. See
+    /// `analytics::summarize`.
+    pub fn summarize(
+        &self,
+        start: chrono::Date<chrono_tz::Tz>,
+        end: chrono::Date<chrono_tz::Tz>,
+        period: analytics::Period,
+        display: timezone::TimezoneDisplay,
+    ) -> Result<Vec<analytics::Summary>> {
+        let records = self.get_history(
+            DateTimeTz(timezone::day_start(&start) - display.search_margin()),
+            DateTimeTz(timezone::day_start(&end.succ()) + display.search_margin()),
+        )?;
+        Ok(analytics::summarize(
+            records.into_iter().map(|(_, record)| record),
+            start,
+            end,
+            period,
+            display,
+        ))
+    }
+
+    /// Find the personal records over the whole series. See `personal_records::personal_records`.
+    pub fn personal_records(&self) -> Result<personal_records::PersonalRecords> {
+        let records = self.all_records()?;
+        Ok(personal_records::personal_records(
+            records.iter().map(|(id, record)| (id, record)),
+        ))
     }
 
     pub fn get_history(
@@ -188,6 +499,8 @@ mod tests {
     use chrono::TimeZone;
     use chrono_tz::Etc::UTC;
     use dimensioned::si::{KG, M, S};
+    use std::collections::HashMap;
+    use std::fs;
     use utils::CleanupFile;
 
     use types::timedistance;
@@ -195,7 +508,7 @@ mod tests {
 
     fn standard_app(filename: &str) -> (Trax, CleanupFile) {
         let series_path = path::PathBuf::from(format!("var/{}", filename));
-        let trax = Trax::new(Params {
+        let trax = Trax::create(Params {
             series_path: series_path.clone(),
         })
         .expect("the app to be created");
@@ -203,6 +516,44 @@ mod tests {
         (trax, cleanup)
     }
 
+    #[test]
+    fn it_opens_only_an_existing_series() {
+        let series_path = path::PathBuf::from("var/it_opens_only_an_existing_series.series");
+        let params = || Params {
+            series_path: series_path.clone(),
+        };
+
+        match Trax::open(params()) {
+            Err(Error::SeriesMissing(path)) => assert_eq!(path, series_path),
+            Err(err) => panic!("unexpected error: {}", err),
+            Ok(_) => panic!("a missing series should not open"),
+        }
+
+        Trax::create(params()).expect("the series to be created");
+        let _cleanup = CleanupFile(series_path.clone());
+        match Trax::create(params()) {
+            Err(Error::SeriesExists(_)) => (),
+            Err(err) => panic!("unexpected error: {}", err),
+            Ok(_) => panic!("an existing series should not be created again"),
+        }
+        Trax::open(params()).expect("the new series to open");
+
+        fs::write(&series_path, "not a record\n").expect("the series to be overwritten");
+        match Trax::open(params()) {
+            Err(Error::SeriesCorrupt(_, _)) => (),
+            Err(err) => panic!("unexpected error: {}", err),
+            Ok(_) => panic!("a corrupt series should not open"),
+        }
+
+        match Trax::open(Params {
+            series_path: path::PathBuf::from("var"),
+        }) {
+            Err(Error::SeriesUnreadable(_, _)) => (),
+            Err(err) => panic!("unexpected error: {}", err),
+            Ok(_) => panic!("a directory should not open as a series"),
+        }
+    }
+
     #[test]
     fn it_records_and_retrieves_a_new_weight() {
         let (mut app, _cleanup) = standard_app("it_records_and_retrieves_a_new_weight.series");
@@ -226,7 +577,7 @@ mod tests {
         let date = DateTimeTz(UTC.ymd(2019, 5, 15).and_hms(12, 0, 0).with_timezone(&UTC));
         let record = timedistance::TimeDistanceRecord::new(
             date,
-            timedistance::ActivityType::Running,
+            timedistance::ActivityType::Running.into(),
             Some(25.0 * M),
             Some(15.0 * S),
             Some(String::from("just some notes")),
@@ -248,7 +599,7 @@ mod tests {
         let date = DateTimeTz(UTC.ymd(2019, 5, 15).and_hms(12, 0, 0).with_timezone(&UTC));
         let td_record = timedistance::TimeDistanceRecord::new(
             date.clone(),
-            timedistance::ActivityType::Running,
+            timedistance::ActivityType::Running.into(),
             Some(25.0 * M),
             Some(15.0 * S),
             Some(String::from("just some notes")),
@@ -279,14 +630,14 @@ mod tests {
         let _cleanup = CleanupFile(series_path.clone());
 
         let (td_id, w_id) = {
-            let mut trax = Trax::new(Params {
+            let mut trax = Trax::create(Params {
                 series_path: series_path.clone(),
             })
             .expect("the app to be created");
 
             let td_record = timedistance::TimeDistanceRecord::new(
                 date.clone(),
-                timedistance::ActivityType::Running,
+                timedistance::ActivityType::Running.into(),
                 Some(25.0 * M),
                 Some(15.0 * S),
                 Some(String::from("just some notes")),
@@ -303,7 +654,7 @@ mod tests {
             (td_id, w_id)
         };
 
-        let trax = Trax::new(Params {
+        let trax = Trax::open(Params {
             series_path: series_path.clone(),
         })
         .expect("the app to load again");
@@ -316,7 +667,7 @@ mod tests {
             td_record,
             Some(TraxRecord::timedistance(
                 date,
-                timedistance::ActivityType::Running,
+                timedistance::ActivityType::Running.into(),
                 Some(25.0 * M),
                 Some(15.0 * S),
                 Some(String::from("just some notes")),
@@ -351,14 +702,14 @@ mod tests {
         let date = DateTimeTz(UTC.ymd(2019, 5, 15).and_hms(12, 0, 0).with_timezone(&UTC));
         let record = timedistance::TimeDistanceRecord::new(
             date.clone(),
-            timedistance::ActivityType::Running,
+            timedistance::ActivityType::Running.into(),
             Some(25.0 * M),
             Some(15.0 * S),
             Some(String::from("just some notes")),
         );
         let record_ = timedistance::TimeDistanceRecord::new(
             date,
-            timedistance::ActivityType::Running,
+            timedistance::ActivityType::Running.into(),
             Some(27.0 * M),
             Some(15.0 * S),
             Some(String::from("just some notes")),
@@ -376,15 +727,201 @@ mod tests {
         assert_eq!(rec, Some(TraxRecord::TimeDistance(record_)));
     }
 
-    /*
+    #[test]
+    fn it_reads_legacy_activities_from_the_registry() {
+        let series_path = path::PathBuf::from("var/it_reads_legacy_activities.series");
+        let _cleanup = CleanupFile(series_path.clone());
+        fs::write(
+            &series_path,
+            "{\"id\":\"2b6e3e5e-5f62-4a3c-8b46-3c6c2d0c2f4a\",\"data\":{\"TimeDistance\":{\"date\":\"2017-10-28T19:27:00Z\",\"activity\":\"Cycling\",\"distance\":12200,\"duration\":3120,\"comments\":null}}}\n",
+        )
+        .expect("legacy series should be written");
+
+        let trax = Trax::open(Params {
+            series_path: series_path.clone(),
+        })
+        .expect("the app to be created");
+        let uid = "2b6e3e5e-5f62-4a3c-8b46-3c6c2d0c2f4a".parse().unwrap();
+        match trax.get_record(&uid).unwrap() {
+            Some(TraxRecord::TimeDistance(rec)) => {
+                assert_eq!(
+                    rec.activity,
+                    ActivityId::from(timedistance::ActivityType::Cycling)
+                );
+                assert_eq!(
+                    trax.activities().get(&rec.activity).map(|a| a.kind),
+                    Some(ActivityKind::TimeDistance)
+                );
+            }
+            rec => panic!("unexpected record: {:?}", rec),
+        }
+    }
+
+    #[test]
+    fn it_records_user_defined_activities() {
+        let (mut app, _cleanup) = standard_app("it_records_user_defined_activities.series");
+        let _cleanup_activities = CleanupFile(path::PathBuf::from(
+            "var/it_records_user_defined_activities.activities",
+        ));
+
+        let date = DateTimeTz(UTC.ymd(2019, 5, 15).and_hms(12, 0, 0).with_timezone(&UTC));
+        let skiing = ActivityId::new("skiing");
+
+        assert!(app
+            .add_record(TraxRecord::timedistance(
+                date.clone(),
+                skiing.clone(),
+                Some(5000. * M),
+                None,
+                None
+            ))
+            .is_err());
+
+        app.add_activity(activity::Activity::new(
+            skiing.clone(),
+            "Skiing",
+            ActivityKind::TimeDistance,
+            HashMap::new(),
+        ))
+        .expect("add_activity should succeed");
+        let uuid = app
+            .add_record(TraxRecord::timedistance(
+                date,
+                skiing.clone(),
+                Some(5000. * M),
+                None,
+                None,
+            ))
+            .expect("add_record should succeed");
+
+        match app.get_record(&uuid).unwrap() {
+            Some(TraxRecord::TimeDistance(rec)) => assert_eq!(rec.activity, skiing),
+            rec => panic!("unexpected record: {:?}", rec),
+        }
+    }
+
     #[test]
     fn it_deletes_a_weight() {
-        unimplemented!()
+        let (mut app, _cleanup) = standard_app("it_deletes_a_weight.series");
+
+        let date = DateTimeTz(UTC.ymd(2019, 5, 15).and_hms(12, 0, 0).with_timezone(&UTC));
+        let uuid = app
+            .add_record(TraxRecord::weight(date.clone(), 85.0 * KG))
+            .expect("did not create a record");
+        let other_uuid = app
+            .add_record(TraxRecord::weight(date.clone(), 86.0 * KG))
+            .expect("did not create a record");
+
+        app.remove_record(&uuid)
+            .expect("remove_record should succeed");
+
+        assert_eq!(app.get_record(&uuid).unwrap(), None);
+        assert_eq!(
+            app.get_record(&other_uuid).unwrap(),
+            Some(TraxRecord::weight(date.clone(), 86.0 * KG))
+        );
+        let history = app
+            .get_history(
+                DateTimeTz(UTC.ymd(2019, 5, 15).and_hms(0, 0, 0)),
+                DateTimeTz(UTC.ymd(2019, 5, 16).and_hms(0, 0, 0)),
+            )
+            .unwrap();
+        assert_eq!(history.len(), 1);
     }
 
     #[test]
     fn it_deletes_a_time_distance() {
-        unimplemented!()
+        let date = DateTimeTz(UTC.ymd(2019, 5, 15).and_hms(12, 0, 0).with_timezone(&UTC));
+        let series_path = path::PathBuf::from("var/it_deletes_a_time_distance.series");
+        let _cleanup = CleanupFile(series_path.clone());
+
+        let uuid = {
+            let mut trax = Trax::create(Params {
+                series_path: series_path.clone(),
+            })
+            .expect("the app to be created");
+
+            let uuid = trax
+                .add_record(TraxRecord::timedistance(
+                    date,
+                    timedistance::ActivityType::Running.into(),
+                    Some(25.0 * M),
+                    Some(15.0 * S),
+                    None,
+                ))
+                .expect("add_record should succeed");
+            trax.remove_record(&uuid)
+                .expect("remove_record should succeed");
+            assert_eq!(trax.get_record(&uuid).unwrap(), None);
+            uuid
+        };
+
+        let trax = Trax::open(Params {
+            series_path: series_path.clone(),
+        })
+        .expect("the app to load again");
+        assert_eq!(trax.get_record(&uuid).unwrap(), None);
+    }
+
+    #[test]
+    fn it_keeps_one_note_per_day() {
+        let (mut app, _cleanup) = standard_app("it_keeps_one_note_per_day.series");
+        let date = chrono_tz::America::New_York.ymd(2020, 3, 1);
+
+        assert_eq!(app.note(&date).unwrap(), None);
+
+        let uid = app
+            .set_note(&date, "a first note")
+            .expect("set_note should succeed")
+            .expect("a note should have been saved");
+        let uid_ = app
+            .set_note(&date, "a second note")
+            .expect("set_note should succeed");
+        assert_eq!(uid_, Some(uid.clone()));
+
+        app.add_record(TraxRecord::Comments(comments::Comments::new(
+            DateTimeTz(date.and_hms(18, 0, 0)),
+            "a third note",
+        )))
+        .expect("add_record should succeed");
+
+        let (note_id, note) = app.note(&date).unwrap().expect("the note should exist");
+        assert_eq!(note_id, uid);
+        assert_eq!(note.text(), "a third note");
+
+        assert_eq!(app.note(&date.succ()).unwrap(), None);
+
+        assert_eq!(app.set_note(&date, "  ").unwrap(), None);
+        assert_eq!(app.note(&date).unwrap(), None);
+    }
+
+    #[test]
+    fn it_handles_days_without_a_midnight() {
+        // Clocks in São Paulo went from 00:00 straight to 01:00 on the 16th.
+        let (mut app, _cleanup) = standard_app("it_handles_days_without_a_midnight.series");
+        let date = chrono_tz::America::Sao_Paulo.ymd(2016, 10, 16);
+
+        app.set_note(&date, "a note")
+            .expect("set_note should succeed");
+        let (_, note) = app.note(&date).unwrap().expect("the note should exist");
+        assert_eq!(
+            note.timestamp(),
+            DateTimeTz(UTC.ymd(2016, 10, 16).and_hms(3, 0, 0))
+        );
+        assert_eq!(app.note(&date.pred()).unwrap(), None);
+
+        app.add_record(TraxRecord::steps(DateTimeTz(date.and_hms(9, 0, 0)), 5000))
+            .expect("add_record should succeed");
+        let days = app
+            .summarize(
+                date.pred(),
+                date,
+                analytics::Period::Day,
+                timezone::TimezoneDisplay::Home,
+            )
+            .expect("summarize should succeed");
+        assert_eq!(days.len(), 2);
+        assert_eq!(days[0].steps, 0);
+        assert_eq!(days[1].steps, 5000);
     }
-    */
 }
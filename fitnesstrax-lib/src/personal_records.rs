@@ -0,0 +1,294 @@
+//! Personal records, such as the fastest 5k run or the most pushups in a single set.
+//!
+//! Records are replayed in the order that they happened, so that each record can say which
+//! personal records it set at the time, even if those have been beaten since.
+
+use dimensioned::si::{Kilogram, Meter, MeterPerSecond, Second};
+use emseries::{DateTimeTz, Recordable, UniqueId};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::activity::ActivityId;
+use crate::timedistance::ActivityType;
+use crate::TraxRecord;
+
+/// The standard distances, in meters, for most time/distance activities.
+const DISTANCES: [u32; 5] = [1_000, 5_000, 10_000, 21_097, 42_195];
+const ROWING_DISTANCES: [u32; 4] = [500, 2_000, 5_000, 10_000];
+const SWIMMING_DISTANCES: [u32; 4] = [100, 400, 1_500, 3_800];
+
+/// What a personal record measures.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Category {
+    /// The fastest average speed over a workout of at least this many meters.
+    FastestOver(u32),
+    LongestDistance,
+    LongestDuration,
+    /// The most repetitions in a single set.
+    MostReps,
+    /// The longest single set of a timed exercise, like a plank.
+    LongestSet,
+    /// The heaviest load lifted in a single set.
+    HeaviestSet,
+}
+
+/// The value which set a personal record.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Mark {
+    Speed(MeterPerSecond<f64>),
+    Distance(Meter<f64>),
+    Duration(Second<f64>),
+    Reps(u32),
+    Load(Kilogram<f64>),
+}
+
+impl Mark {
+    /// Whether this mark is strictly better than another. A tie does not set a new record.
+    fn beats(&self, other: &Mark) -> bool {
+        match (self, other) {
+            (Mark::Speed(a), Mark::Speed(b)) => a > b,
+            (Mark::Distance(a), Mark::Distance(b)) => a > b,
+            (Mark::Duration(a), Mark::Duration(b)) => a > b,
+            (Mark::Reps(a), Mark::Reps(b)) => a > b,
+            (Mark::Load(a), Mark::Load(b)) => a > b,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PersonalRecord {
+    /// The id of the record which set this personal record.
+    pub id: UniqueId,
+    pub timestamp: DateTimeTz,
+    pub activity: ActivityId,
+    pub category: Category,
+    pub mark: Mark,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct PersonalRecords {
+    /// The personal records which stand today, for each activity and category.
+    pub best: BTreeMap<(ActivityId, Category), PersonalRecord>,
+    set_by: HashMap<UniqueId, Vec<PersonalRecord>>,
+}
+
+impl PersonalRecords {
+    /// The personal records that a record set at the time that it happened.
+    pub fn set_by(&self, id: &UniqueId) -> &[PersonalRecord] {
+        self.set_by.get(id).map(|prs| prs.as_slice()).unwrap_or(&[])
+    }
+
+    fn add(&mut self, id: &UniqueId, record: &TraxRecord, category: Category, mark: Mark) {
+        let activity = match record.activity() {
+            Some((activity, _)) => activity.clone(),
+            None => return,
+        };
+        let key = (activity.clone(), category);
+        if let Some(best) = self.best.get(&key) {
+            if !mark.beats(&best.mark) {
+                return;
+            }
+        }
+        let pr = PersonalRecord {
+            id: id.clone(),
+            timestamp: record.timestamp(),
+            activity,
+            category,
+            mark,
+        };
+        self.set_by.entry(id.clone()).or_default().push(pr.clone());
+        self.best.insert(key, pr);
+    }
+}
+
+/// The standard distances, in meters, that personal records are kept for.
+pub fn distances(activity: &ActivityId) -> &'static [u32] {
+    if *activity == ActivityType::Rowing.into() {
+        &ROWING_DISTANCES
+    } else if *activity == ActivityType::Swimming.into() {
+        &SWIMMING_DISTANCES
+    } else {
+        &DISTANCES
+    }
+}
+
+/// Find the personal records in a set of records, which is usually the whole series. The first
+/// workout of an activity sets a personal record in every category that it has a value for.
+pub fn personal_records<'a, I>(records: I) -> PersonalRecords
+where
+    I: IntoIterator<Item = (&'a UniqueId, &'a TraxRecord)>,
+{
+    let mut records: Vec<(&UniqueId, &TraxRecord)> = records.into_iter().collect();
+    records.sort_by_key(|(_, record)| record.timestamp());
+
+    let mut prs = PersonalRecords::default();
+    for (id, record) in records {
+        match record {
+            TraxRecord::TimeDistance(ref rec) => {
+                if let Some(distance) = rec.distance {
+                    prs.add(
+                        id,
+                        record,
+                        Category::LongestDistance,
+                        Mark::Distance(distance),
+                    );
+                }
+                if let Some(duration) = rec.duration {
+                    prs.add(
+                        id,
+                        record,
+                        Category::LongestDuration,
+                        Mark::Duration(duration),
+                    );
+                }
+                if let (Some(distance), Some(duration)) = (rec.distance, rec.duration) {
+                    if duration.value_unsafe > 0. {
+                        for bucket in distances(&rec.activity) {
+                            if distance.value_unsafe >= f64::from(*bucket) {
+                                prs.add(
+                                    id,
+                                    record,
+                                    Category::FastestOver(*bucket),
+                                    Mark::Speed(distance / duration),
+                                );
+                            }
+                        }
+                    }
+                }
+            }
+            TraxRecord::SetRep(ref rec) => {
+                if let Some(reps) = rec.sets.iter().max() {
+                    prs.add(id, record, Category::MostReps, Mark::Reps(*reps));
+                }
+            }
+            TraxRecord::RepDuration(ref rec) => {
+                let longest =
+                    rec.sets
+                        .iter()
+                        .cloned()
+                        .fold(None, |longest: Option<Second<f64>>, set| match longest {
+                            Some(longest) if longest >= set => Some(longest),
+                            _ => Some(set),
+                        });
+                if let Some(longest) = longest {
+                    prs.add(id, record, Category::LongestSet, Mark::Duration(longest));
+                }
+            }
+            TraxRecord::WeightedSet(ref rec) => {
+                let heaviest = rec.sets.iter().map(|set| set.load).fold(
+                    None,
+                    |heaviest: Option<Kilogram<f64>>, load| match heaviest {
+                        Some(heaviest) if heaviest >= load => Some(heaviest),
+                        _ => Some(load),
+                    },
+                );
+                if let Some(heaviest) = heaviest {
+                    prs.add(id, record, Category::HeaviestSet, Mark::Load(heaviest));
+                }
+            }
+            TraxRecord::Comments(_) | TraxRecord::Steps(_) | TraxRecord::Weight(_) => (),
+        }
+    }
+    prs
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::setrep::SetRepRecord;
+    use crate::timedistance::TimeDistanceRecord;
+    use chrono::TimeZone;
+    use chrono_tz::Etc::UTC;
+    use dimensioned::si::{M, S};
+
+    fn run(day: u32, distance: f64, duration: f64) -> (UniqueId, TraxRecord) {
+        (
+            UniqueId::new(),
+            TraxRecord::TimeDistance(TimeDistanceRecord::new(
+                DateTimeTz(UTC.ymd(2020, 3, day).and_hms(7, 0, 0)),
+                ActivityType::Running.into(),
+                Some(distance * M),
+                Some(duration * S),
+                None,
+            )),
+        )
+    }
+
+    #[test]
+    fn it_finds_records_in_the_order_they_were_set() {
+        let records = [
+            run(3, 5000., 1500.),
+            run(1, 5000., 1600.),
+            // Faster, but too short to count for 5k.
+            run(5, 4000., 1000.),
+            run(7, 10000., 3100.),
+        ];
+        let prs = personal_records(records.iter().map(|(id, rec)| (id, rec)));
+
+        let categories = |idx: usize| {
+            prs.set_by(&records[idx].0)
+                .iter()
+                .map(|pr| pr.category)
+                .collect::<Vec<Category>>()
+        };
+        assert_eq!(
+            categories(1),
+            vec![
+                Category::LongestDistance,
+                Category::LongestDuration,
+                Category::FastestOver(1000),
+                Category::FastestOver(5000),
+            ]
+        );
+        assert_eq!(
+            categories(0),
+            vec![Category::FastestOver(1000), Category::FastestOver(5000)]
+        );
+        assert_eq!(categories(2), vec![Category::FastestOver(1000)]);
+        assert_eq!(
+            categories(3),
+            vec![
+                Category::LongestDistance,
+                Category::LongestDuration,
+                Category::FastestOver(10000),
+            ]
+        );
+
+        let running: ActivityId = ActivityType::Running.into();
+        assert_eq!(
+            prs.best[&(running.clone(), Category::FastestOver(5000))].id,
+            records[0].0
+        );
+        assert_eq!(
+            prs.best[&(running, Category::FastestOver(1000))].mark,
+            Mark::Speed(4000. * M / (1000. * S))
+        );
+    }
+
+    #[test]
+    fn it_needs_a_better_set_to_beat_a_record() {
+        let pushups = |day: u32, sets: Vec<u32>| {
+            (
+                UniqueId::new(),
+                TraxRecord::SetRep(
+                    SetRepRecord::new(
+                        DateTimeTz(UTC.ymd(2020, 3, day).and_hms(7, 0, 0)),
+                        crate::setrep::ActivityType::Pushups.into(),
+                        sets,
+                        None,
+                    )
+                    .unwrap(),
+                ),
+            )
+        };
+        let records = [
+            pushups(1, vec![20, 15]),
+            pushups(2, vec![10, 20, 10]),
+            pushups(3, vec![21]),
+        ];
+        let prs = personal_records(records.iter().map(|(id, rec)| (id, rec)));
+        assert_eq!(prs.set_by(&records[0].0).len(), 1);
+        assert_eq!(prs.set_by(&records[1].0), &[]);
+        assert_eq!(prs.set_by(&records[2].0)[0].mark, Mark::Reps(21),);
+    }
+}
@@ -0,0 +1,258 @@
+//! Streaks of consecutive days that meet a condition, such as working out or logging a weight.
+//!
+//! The conditions to track are stored as a JSON list in a file alongside the series, in the same
+//! way as the goals.
+
+use emseries::Recordable;
+use std::collections::HashMap;
+use std::fs;
+use std::path;
+
+use crate::activity::ActivityId;
+use crate::error::{Error, Result};
+use crate::timezone::TimezoneDisplay;
+use crate::TraxRecord;
+
+/// What a day needs in order to count towards a streak.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub enum Condition {
+    /// At least one workout of any activity.
+    AnyWorkout,
+    /// At least one workout of this activity.
+    Activity(ActivityId),
+    /// At least this many steps.
+    Steps(u32),
+    WeightLogged,
+}
+
+impl Condition {
+    fn met(&self, records: &[&TraxRecord]) -> bool {
+        match self {
+            Condition::AnyWorkout => records.iter().any(|record| record.activity().is_some()),
+            Condition::Activity(activity) => records.iter().any(|record| match record.activity() {
+                Some((id, _)) => id == activity,
+                None => false,
+            }),
+            Condition::Steps(minimum) => {
+                records
+                    .iter()
+                    .map(|record| match record {
+                        TraxRecord::Steps(ref rec) => rec.steps,
+                        _ => 0,
+                    })
+                    .sum::<u32>()
+                    >= *minimum
+            }
+            Condition::WeightLogged => records
+                .iter()
+                .any(|record| matches!(record, TraxRecord::Weight(_))),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Streak {
+    /// The number of days in a row, up to today, that met the condition. A streak that reached
+    /// yesterday is still current, since today is not over yet.
+    pub current: u32,
+    pub longest: u32,
+    /// The last day of the longest streak. When there are several streaks of the longest length,
+    /// this is the most recent one.
+    pub longest_end: Option<chrono::NaiveDate>,
+}
+
+/// Find the current and longest streaks for a condition as of `today`, which should be the
+/// current date in the user's timezone. Records are placed on days in the same way as the
+/// history, by `display`.
+pub fn streak<'a, I>(
+    condition: &Condition,
+    records: I,
+    today: chrono::Date<chrono_tz::Tz>,
+    display: TimezoneDisplay,
+) -> Streak
+where
+    I: IntoIterator<Item = &'a TraxRecord>,
+{
+    let timezone = today.timezone();
+    let today = today.naive_local();
+    let mut days: HashMap<chrono::NaiveDate, Vec<&TraxRecord>> = HashMap::new();
+    for record in records {
+        let date = display.date(&record.timestamp(), &timezone);
+        days.entry(date).or_default().push(record);
+    }
+    let mut met: Vec<chrono::NaiveDate> = days
+        .iter()
+        .filter(|(date, records)| **date <= today && condition.met(records))
+        .map(|(date, _)| *date)
+        .collect();
+    met.sort();
+
+    let mut result = Streak::default();
+    let mut length = 0;
+    let mut previous: Option<chrono::NaiveDate> = None;
+    for date in met {
+        length = match previous {
+            Some(previous) if previous.succ() == date => length + 1,
+            _ => 1,
+        };
+        if length >= result.longest {
+            result.longest = length;
+            result.longest_end = Some(date);
+        }
+        previous = Some(date);
+    }
+    if let Some(last) = previous {
+        if last == today || last.succ() == today {
+            result.current = length;
+        }
+    }
+    result
+}
+
+/// The list of conditions to track streaks for, stored in a file alongside the series.
+#[derive(Clone, Debug, Default)]
+pub struct StreakConditions {
+    path: Option<path::PathBuf>,
+    conditions: Vec<Condition>,
+}
+
+impl StreakConditions {
+    /// Open the conditions stored at `path`. A missing file means that no streaks are tracked
+    /// yet.
+    pub fn open(path: &path::Path) -> Result<StreakConditions> {
+        let conditions = if path.exists() {
+            let file = fs::File::open(path).map_err(Error::IOError)?;
+            serde_json::from_reader(file).map_err(Error::JSONError)?
+        } else {
+            Vec::new()
+        };
+        Ok(StreakConditions {
+            path: Some(path.to_path_buf()),
+            conditions,
+        })
+    }
+
+    pub fn conditions(&self) -> &[Condition] {
+        &self.conditions
+    }
+
+    pub fn add(&mut self, condition: Condition) -> Result<()> {
+        self.conditions.push(condition);
+        self.save()
+    }
+
+    /// Remove the condition at `idx` in the list.
+    pub fn remove(&mut self, idx: usize) -> Result<()> {
+        if idx >= self.conditions.len() {
+            return Err(Error::InvalidParameter);
+        }
+        self.conditions.remove(idx);
+        self.save()
+    }
+
+    fn save(&self) -> Result<()> {
+        if let Some(ref path) = self.path {
+            let file = fs::File::create(path).map_err(Error::IOError)?;
+            serde_json::to_writer(file, &self.conditions).map_err(Error::JSONError)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::timedistance::{ActivityType, TimeDistanceRecord};
+    use chrono::TimeZone;
+    use chrono_tz::America::New_York;
+    use chrono_tz::Etc::UTC;
+    use dimensioned::si::{KG, M};
+    use emseries::DateTimeTz;
+
+    fn run(time: chrono::DateTime<chrono_tz::Tz>) -> TraxRecord {
+        TraxRecord::TimeDistance(TimeDistanceRecord::new(
+            DateTimeTz(time),
+            ActivityType::Running.into(),
+            Some(5000. * M),
+            None,
+            None,
+        ))
+    }
+
+    #[test]
+    fn it_finds_current_and_longest_streaks() {
+        let records = [
+            run(New_York.ymd(2020, 3, 1).and_hms(7, 0, 0)),
+            run(New_York.ymd(2020, 3, 2).and_hms(7, 0, 0)),
+            run(New_York.ymd(2020, 3, 3).and_hms(7, 0, 0)),
+            // 03:30 on the 6th in UTC is still the evening of the 5th in New York, so the run
+            // belongs to the 5th.
+            run(UTC.ymd(2020, 3, 6).and_hms(3, 30, 0)),
+            run(New_York.ymd(2020, 3, 6).and_hms(7, 0, 0)),
+            TraxRecord::weight(DateTimeTz(UTC.ymd(2020, 3, 6).and_hms(7, 0, 0)), 80. * KG),
+        ];
+
+        let today = New_York.ymd(2020, 3, 7);
+        let streak = streak(
+            &Condition::AnyWorkout,
+            records.iter(),
+            today,
+            TimezoneDisplay::Home,
+        );
+        assert_eq!(streak.current, 2);
+        assert_eq!(streak.longest, 3);
+        assert_eq!(
+            streak.longest_end,
+            Some(chrono::NaiveDate::from_ymd(2020, 3, 3))
+        );
+
+        let later = super::streak(
+            &Condition::AnyWorkout,
+            records.iter(),
+            New_York.ymd(2020, 3, 8),
+            TimezoneDisplay::Home,
+        );
+        assert_eq!(later.current, 0);
+
+        // In the timezone that it was recorded in, the late run is on the 6th and the streak of
+        // the 5th and 6th is broken.
+        let recorded = super::streak(
+            &Condition::AnyWorkout,
+            records.iter(),
+            today,
+            TimezoneDisplay::Recorded,
+        );
+        assert_eq!(recorded.current, 1);
+        assert_eq!(recorded.longest, 3);
+
+        let weights = super::streak(
+            &Condition::WeightLogged,
+            records.iter(),
+            today,
+            TimezoneDisplay::Home,
+        );
+        assert_eq!(weights.current, 1);
+        assert_eq!(weights.longest, 1);
+    }
+
+    #[test]
+    fn it_counts_steps_against_a_minimum() {
+        let steps = |day: u32, count: u32| {
+            TraxRecord::steps(DateTimeTz(UTC.ymd(2020, 3, day).and_hms(0, 0, 0)), count)
+        };
+        let records = [
+            steps(1, 12000),
+            steps(2, 8000),
+            steps(3, 10000),
+            steps(4, 11000),
+        ];
+        let streak = streak(
+            &Condition::Steps(10000),
+            records.iter(),
+            UTC.ymd(2020, 3, 4),
+            TimezoneDisplay::Home,
+        );
+        assert_eq!(streak.current, 2);
+        assert_eq!(streak.longest, 2);
+    }
+}
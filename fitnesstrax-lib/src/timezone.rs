@@ -0,0 +1,165 @@
+//! Deciding which day a record belongs to. Records keep the timezone that they were written in,
+//! which is not always the timezone that the person lives in.
+
+use chrono::offset::{LocalResult, Offset, TimeZone};
+use emseries::DateTimeTz;
+use std::convert::TryFrom;
+
+use crate::error::Error;
+
+/// Which timezone decides the day that a record belongs to and the time that it shows.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TimezoneDisplay {
+    /// The timezone in the settings, so that every record lines up with the days at home.
+    Home,
+    /// The timezone that the record was written in. A run at 07:00 in Tokyo stays on that morning,
+    /// even though it was still the day before at home.
+    Recorded,
+}
+
+impl From<&TimezoneDisplay> for String {
+    fn from(display: &TimezoneDisplay) -> String {
+        format!("{:?}", display)
+    }
+}
+
+impl TryFrom<&str> for TimezoneDisplay {
+    type Error = Error;
+
+    fn try_from(s: &str) -> std::result::Result<TimezoneDisplay, Error> {
+        match s {
+            "Home" => Ok(TimezoneDisplay::Home),
+            "Recorded" => Ok(TimezoneDisplay::Recorded),
+            _ => Err(Error::UnknownTimezoneDisplay(s.to_string())),
+        }
+    }
+}
+
+impl TimezoneDisplay {
+    /// The timezone to show a record in.
+    pub fn zone(self, timestamp: &DateTimeTz, home: &chrono_tz::Tz) -> chrono_tz::Tz {
+        match self {
+            TimezoneDisplay::Home => *home,
+            TimezoneDisplay::Recorded => timestamp.0.timezone(),
+        }
+    }
+
+    /// The day that a record belongs to.
+    pub fn date(self, timestamp: &DateTimeTz, home: &chrono_tz::Tz) -> chrono::NaiveDate {
+        timestamp
+            .0
+            .with_timezone(&self.zone(timestamp, home))
+            .date()
+            .naive_local()
+    }
+
+    /// How many days either side of a range to search so that every record which belongs in the
+    /// range gets found. Timezones can be as much as 26 hours apart, from UTC-12 to UTC+14.
+    pub fn search_margin(self) -> chrono::Duration {
+        match self {
+            TimezoneDisplay::Home => chrono::Duration::zero(),
+            TimezoneDisplay::Recorded => chrono::Duration::days(2),
+        }
+    }
+}
+
+/// The instant that a local time refers to. When the clocks go back and the time happens twice,
+/// this is the first of the two. When the clocks skip forward over the time, it moves forward by
+/// the length of the gap, to where a clock that was never changed would have shown it.
+pub fn local_time(
+    timezone: &chrono_tz::Tz,
+    time: &chrono::NaiveDateTime,
+) -> chrono::DateTime<chrono_tz::Tz> {
+    match timezone.from_local_datetime(time) {
+        LocalResult::Single(time) => time,
+        LocalResult::Ambiguous(earliest, _) => earliest,
+        // No timezone changes its offset twice in a day, so a day earlier is still in the offset
+        // from before the change.
+        LocalResult::None => {
+            let before = timezone
+                .offset_from_utc_datetime(&(*time - chrono::Duration::days(1)))
+                .fix();
+            timezone.from_utc_datetime(&(*time - before))
+        }
+    }
+}
+
+/// The first instant of a day. This is usually midnight, but some timezones change the clocks
+/// at midnight, so that midnight either never happens or happens twice.
+pub fn day_start(date: &chrono::Date<chrono_tz::Tz>) -> chrono::DateTime<chrono_tz::Tz> {
+    local_time(&date.timezone(), &date.naive_local().and_hms(0, 0, 0))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::{Date, NaiveDate, Utc};
+    use chrono_tz::America::{Havana, New_York, Sao_Paulo};
+    use chrono_tz::Australia::Lord_Howe;
+    use chrono_tz::Europe::London;
+    use chrono_tz::Tz;
+
+    #[test]
+    fn it_starts_days_at_midnight_around_daylight_saving_changes() {
+        // The clocks change at 02:00 in New York, 01:00 in London and 02:00 in Lord Howe, so
+        // every one of these days still starts at midnight.
+        for date in &[
+            New_York.ymd(2019, 3, 10),
+            New_York.ymd(2019, 11, 3),
+            London.ymd(2019, 3, 31),
+            London.ymd(2019, 10, 27),
+            Lord_Howe.ymd(2019, 4, 7),
+            Lord_Howe.ymd(2019, 10, 6),
+        ] {
+            let start = day_start(date);
+            assert_eq!(start.naive_local(), date.naive_local().and_hms(0, 0, 0));
+            assert_eq!(start.date(), *date);
+        }
+    }
+
+    #[test]
+    fn it_starts_days_when_the_clocks_skip_midnight() {
+        // Clocks went from 00:00 straight to 01:00 in São Paulo and Havana.
+        let start = day_start(&Sao_Paulo.ymd(2016, 10, 16));
+        assert_eq!(start, Utc.ymd(2016, 10, 16).and_hms(3, 0, 0));
+        assert_eq!(
+            start.naive_local(),
+            NaiveDate::from_ymd(2016, 10, 16).and_hms(1, 0, 0)
+        );
+
+        let start = day_start(&Havana.ymd(2019, 3, 10));
+        assert_eq!(start, Utc.ymd(2019, 3, 10).and_hms(5, 0, 0));
+        assert_eq!(
+            start.naive_local(),
+            NaiveDate::from_ymd(2019, 3, 10).and_hms(1, 0, 0)
+        );
+    }
+
+    #[test]
+    fn it_starts_days_at_the_first_of_two_midnights() {
+        // Clocks in Havana went from 01:00 back to 00:00, so midnight happened twice.
+        let start = day_start(&Havana.ymd(2019, 11, 3));
+        assert_eq!(start, Utc.ymd(2019, 11, 3).and_hms(4, 0, 0));
+
+        // Clocks in São Paulo went from 00:00 back to 23:00 on the day before, so the 18th had
+        // two 23:00s but the 19th only one midnight.
+        let start = day_start(&Sao_Paulo.ymd(2017, 2, 19));
+        assert_eq!(start, Utc.ymd(2017, 2, 19).and_hms(3, 0, 0));
+    }
+
+    #[test]
+    fn it_measures_days_across_daylight_saving_changes() {
+        let minutes = |date: Date<Tz>| (day_start(&date.succ()) - day_start(&date)).num_minutes();
+        assert_eq!(minutes(New_York.ymd(2019, 3, 10)), 23 * 60);
+        assert_eq!(minutes(New_York.ymd(2019, 11, 3)), 25 * 60);
+        assert_eq!(minutes(London.ymd(2019, 3, 31)), 23 * 60);
+        assert_eq!(minutes(London.ymd(2019, 10, 27)), 25 * 60);
+        // Lord Howe only moves the clocks by half an hour.
+        assert_eq!(minutes(Lord_Howe.ymd(2019, 4, 7)), 24 * 60 + 30);
+        assert_eq!(minutes(Lord_Howe.ymd(2019, 10, 6)), 23 * 60 + 30);
+        assert_eq!(minutes(Sao_Paulo.ymd(2016, 10, 16)), 23 * 60);
+        assert_eq!(minutes(Sao_Paulo.ymd(2017, 2, 18)), 25 * 60);
+        assert_eq!(minutes(Havana.ymd(2019, 3, 10)), 23 * 60);
+        assert_eq!(minutes(Havana.ymd(2019, 11, 3)), 25 * 60);
+    }
+}
@@ -0,0 +1,392 @@
+use crate::error::{Error, Result};
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+use std::fmt;
+use std::fs;
+use std::path;
+
+use crate::types::{repduration, setrep, timedistance, weightedset};
+
+/// Identifies an activity in the registry. The built-in activities use the names of the legacy
+/// activity enumerations as their identifiers, which means that records written before the
+/// registry existed deserialize without any conversion.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Deserialize, Serialize)]
+pub struct ActivityId(String);
+
+impl ActivityId {
+    pub fn new(id: &str) -> ActivityId {
+        ActivityId(String::from(id))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for ActivityId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<timedistance::ActivityType> for ActivityId {
+    fn from(activity: timedistance::ActivityType) -> ActivityId {
+        ActivityId(format!("{:?}", activity))
+    }
+}
+
+impl From<setrep::ActivityType> for ActivityId {
+    fn from(activity: setrep::ActivityType) -> ActivityId {
+        ActivityId(format!("{:?}", activity))
+    }
+}
+
+impl From<repduration::ActivityType> for ActivityId {
+    fn from(activity: repduration::ActivityType) -> ActivityId {
+        ActivityId(format!("{:?}", activity))
+    }
+}
+
+impl From<weightedset::ActivityType> for ActivityId {
+    fn from(activity: weightedset::ActivityType) -> ActivityId {
+        ActivityId(format!("{:?}", activity))
+    }
+}
+
+/// The kind of record that an activity can be used with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum ActivityKind {
+    RepDuration,
+    SetRep,
+    TimeDistance,
+    WeightedSet,
+}
+
+impl TryFrom<&str> for ActivityKind {
+    type Error = &'static str;
+
+    fn try_from(inp: &str) -> std::result::Result<ActivityKind, Self::Error> {
+        match inp {
+            "RepDuration" => Ok(ActivityKind::RepDuration),
+            "SetRep" => Ok(ActivityKind::SetRep),
+            "TimeDistance" => Ok(ActivityKind::TimeDistance),
+            "WeightedSet" => Ok(ActivityKind::WeightedSet),
+            _ => Err("invalid activity kind string"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Activity {
+    pub id: ActivityId,
+    pub name: String,
+    pub kind: ActivityKind,
+    /// Translated names, keyed by language code (i.e., "en", "eo").
+    pub translations: HashMap<String, String>,
+}
+
+impl Activity {
+    pub fn new(
+        id: ActivityId,
+        name: &str,
+        kind: ActivityKind,
+        translations: HashMap<String, String>,
+    ) -> Activity {
+        Activity {
+            id,
+            name: String::from(name),
+            kind,
+            translations,
+        }
+    }
+
+    /// Get the name of the activity in the requested language, falling back to the untranslated
+    /// name.
+    pub fn name_for(&self, language: &str) -> &str {
+        self.translations
+            .get(language)
+            .map(|name| name.as_str())
+            .unwrap_or(&self.name)
+    }
+}
+
+fn builtin<A: Into<ActivityId>>(
+    activity: A,
+    kind: ActivityKind,
+    translations: &[(&str, &str)],
+) -> Activity {
+    let translations: HashMap<String, String> = translations
+        .iter()
+        .map(|(lang, name)| (String::from(*lang), String::from(*name)))
+        .collect();
+    let name = translations
+        .get("en")
+        .cloned()
+        .expect("built-in activities always have an English name");
+    Activity::new(activity.into(), &name, kind, translations)
+}
+
+/// The activities that were available before the registry existed. The `ActivityType` enum of
+/// each record type lists them, but records only refer to them through an `ActivityId`, so the
+/// enums do no more than seed the registry.
+pub fn builtin_activities() -> Vec<Activity> {
+    vec![
+        builtin(
+            timedistance::ActivityType::Cycling,
+            ActivityKind::TimeDistance,
+            &[("en", "Cycling"), ("eo", "Biciklado")],
+        ),
+        builtin(
+            timedistance::ActivityType::Rowing,
+            ActivityKind::TimeDistance,
+            &[("en", "Rowing"), ("eo", "Remado")],
+        ),
+        builtin(
+            timedistance::ActivityType::Running,
+            ActivityKind::TimeDistance,
+            &[("en", "Running"), ("eo", "Kurado")],
+        ),
+        builtin(
+            timedistance::ActivityType::Swimming,
+            ActivityKind::TimeDistance,
+            &[("en", "Swimming"), ("eo", "Naĝado")],
+        ),
+        builtin(
+            timedistance::ActivityType::Walking,
+            ActivityKind::TimeDistance,
+            &[("en", "Walking"), ("eo", "Promenadi")],
+        ),
+        builtin(
+            setrep::ActivityType::Pushups,
+            ActivityKind::SetRep,
+            &[("en", "Pushups"), ("eo", "Supraj Puŝoj")],
+        ),
+        builtin(
+            setrep::ActivityType::Situps,
+            ActivityKind::SetRep,
+            &[("en", "Situps"), ("eo", "Sidiĝoj")],
+        ),
+        builtin(
+            setrep::ActivityType::Squats,
+            ActivityKind::SetRep,
+            &[("en", "Squats")],
+        ),
+        builtin(
+            repduration::ActivityType::MartialArts,
+            ActivityKind::RepDuration,
+            &[("en", "Martial Arts")],
+        ),
+        builtin(
+            repduration::ActivityType::Planks,
+            ActivityKind::RepDuration,
+            &[("en", "Planks")],
+        ),
+        builtin(
+            repduration::ActivityType::Yoga,
+            ActivityKind::RepDuration,
+            &[("en", "Yoga")],
+        ),
+        builtin(
+            weightedset::ActivityType::BenchPress,
+            ActivityKind::WeightedSet,
+            &[("en", "Bench Press")],
+        ),
+        builtin(
+            weightedset::ActivityType::Deadlift,
+            ActivityKind::WeightedSet,
+            &[("en", "Deadlift")],
+        ),
+        builtin(
+            weightedset::ActivityType::OverheadPress,
+            ActivityKind::WeightedSet,
+            &[("en", "Overhead Press")],
+        ),
+        builtin(
+            weightedset::ActivityType::Row,
+            ActivityKind::WeightedSet,
+            &[("en", "Row")],
+        ),
+        builtin(
+            weightedset::ActivityType::Squat,
+            ActivityKind::WeightedSet,
+            &[("en", "Squat")],
+        ),
+    ]
+}
+
+fn builtin_ids() -> Vec<ActivityId> {
+    builtin_activities()
+        .into_iter()
+        .map(|activity| activity.id)
+        .collect()
+}
+
+/// The set of activities that records may refer to. The built-in activities are always present.
+/// Any user-defined activities are stored as a JSON list in a file alongside the series.
+#[derive(Clone, Debug)]
+pub struct ActivityRegistry {
+    path: Option<path::PathBuf>,
+    activities: BTreeMap<ActivityId, Activity>,
+}
+
+impl Default for ActivityRegistry {
+    fn default() -> ActivityRegistry {
+        ActivityRegistry {
+            path: None,
+            activities: builtin_activities()
+                .into_iter()
+                .map(|activity| (activity.id.clone(), activity))
+                .collect(),
+        }
+    }
+}
+
+impl ActivityRegistry {
+    /// Open the registry stored at `path`. A missing file is not an error, as the file only gets
+    /// created once a user-defined activity gets added.
+    pub fn open(path: &path::Path) -> Result<ActivityRegistry> {
+        let mut registry = ActivityRegistry {
+            path: Some(path.to_path_buf()),
+            ..ActivityRegistry::default()
+        };
+
+        if path.exists() {
+            let file = fs::File::open(path).map_err(Error::IOError)?;
+            let activities: Vec<Activity> =
+                serde_json::from_reader(file).map_err(Error::JSONError)?;
+            for activity in activities {
+                registry.activities.insert(activity.id.clone(), activity);
+            }
+        }
+
+        Ok(registry)
+    }
+
+    pub fn get(&self, id: &ActivityId) -> Option<&Activity> {
+        self.activities.get(id)
+    }
+
+    pub fn activities(&self) -> Vec<&Activity> {
+        self.activities.values().collect()
+    }
+
+    pub fn activities_of_kind(&self, kind: ActivityKind) -> Vec<&Activity> {
+        self.activities
+            .values()
+            .filter(|activity| activity.kind == kind)
+            .collect()
+    }
+
+    /// Find an activity of the given kind by its id or, ignoring case, by any of its names.
+    pub fn find(&self, kind: ActivityKind, name: &str) -> Option<&Activity> {
+        let name = name.to_lowercase();
+        self.activities_of_kind(kind).into_iter().find(|activity| {
+            activity.id.as_str().to_lowercase() == name
+                || activity.name.to_lowercase() == name
+                || activity
+                    .translations
+                    .values()
+                    .any(|translation| translation.to_lowercase() == name)
+        })
+    }
+
+    /// Add an activity, or replace the user-defined activity which has the same id, and save the
+    /// user-defined activities. Built-in activities never get saved, so replacing one is an
+    /// error rather than a change that disappears on the next start.
+    pub fn add(&mut self, activity: Activity) -> Result<()> {
+        if builtin_ids().contains(&activity.id) {
+            return Err(Error::BuiltinActivity(activity.id));
+        }
+        self.activities.insert(activity.id.clone(), activity);
+        self.save()
+    }
+
+    /// Check that `id` refers to a registered activity of the right kind.
+    pub fn validate(&self, id: &ActivityId, kind: ActivityKind) -> Result<()> {
+        match self.activities.get(id) {
+            Some(activity) if activity.kind == kind => Ok(()),
+            _ => Err(Error::UnknownActivity(id.clone())),
+        }
+    }
+
+    fn save(&self) -> Result<()> {
+        if let Some(ref path) = self.path {
+            let builtins = builtin_ids();
+            let user_defined: Vec<&Activity> = self
+                .activities
+                .values()
+                .filter(|activity| !builtins.contains(&activity.id))
+                .collect();
+            let file = fs::File::create(path).map_err(Error::IOError)?;
+            serde_json::to_writer(file, &user_defined).map_err(Error::JSONError)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::CleanupFile;
+
+    #[test]
+    fn it_includes_the_legacy_activities() {
+        let registry = ActivityRegistry::default();
+        let running = registry
+            .get(&ActivityId::from(timedistance::ActivityType::Running))
+            .expect("running should be built in");
+        assert_eq!(running.kind, ActivityKind::TimeDistance);
+        assert_eq!(running.name_for("eo"), "Kurado");
+        assert_eq!(running.name_for("de"), "Running");
+        assert_eq!(registry.activities_of_kind(ActivityKind::SetRep).len(), 3);
+    }
+
+    #[test]
+    fn it_persists_user_defined_activities() {
+        let path = path::PathBuf::from("var/it_persists_user_defined_activities.activities");
+        let _cleanup = CleanupFile(path.clone());
+
+        let mut translations = HashMap::new();
+        translations.insert(String::from("eo"), String::from("Skiado"));
+        let skiing = Activity::new(
+            ActivityId::new("skiing"),
+            "Skiing",
+            ActivityKind::TimeDistance,
+            translations,
+        );
+
+        {
+            let mut registry = ActivityRegistry::open(&path).expect("registry should open");
+            registry.add(skiing.clone()).expect("add should succeed");
+        }
+
+        let registry = ActivityRegistry::open(&path).expect("registry should open again");
+        assert_eq!(registry.get(&ActivityId::new("skiing")), Some(&skiing));
+        assert!(registry
+            .get(&ActivityId::from(timedistance::ActivityType::Cycling))
+            .is_some());
+        assert!(registry
+            .validate(&ActivityId::new("skiing"), ActivityKind::SetRep)
+            .is_err());
+    }
+
+    #[test]
+    fn it_refuses_to_replace_builtin_activities() {
+        let mut registry = ActivityRegistry::default();
+        let running = ActivityId::from(timedistance::ActivityType::Running);
+        let renamed = Activity::new(
+            running.clone(),
+            "Jogging",
+            ActivityKind::TimeDistance,
+            HashMap::new(),
+        );
+        match registry.add(renamed) {
+            Err(Error::BuiltinActivity(id)) => assert_eq!(id, running),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert_eq!(
+            registry.get(&running).map(|a| a.name.as_str()),
+            Some("Running")
+        );
+    }
+}
@@ -1,5 +1,7 @@
 use emseries::{DateTimeTz, Recordable};
 
+/// A free-form note for a day. A series holds at most one of these per day, which makes them
+/// the daily journal.
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Comments {
     date: DateTimeTz,
@@ -13,6 +15,10 @@ impl Comments {
             val: String::from(val),
         }
     }
+
+    pub fn text(&self) -> &str {
+        &self.val
+    }
 }
 
 impl Recordable for Comments {
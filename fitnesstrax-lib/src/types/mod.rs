@@ -1,6 +1,8 @@
+pub mod activity;
 pub mod comments;
 pub mod repduration;
 pub mod setrep;
 pub mod steps;
 pub mod timedistance;
 pub mod weight;
+pub mod weightedset;
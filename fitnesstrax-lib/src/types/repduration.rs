@@ -1,3 +1,4 @@
+use crate::types::activity::ActivityId;
 use dimensioned::si::Second;
 use emseries::{DateTimeTz, Recordable};
 
@@ -11,8 +12,8 @@ pub enum ActivityType {
 #[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
 pub struct RepDurationRecord {
     #[serde(rename = "date")]
-    timestamp: DateTimeTz,
-    pub activity: ActivityType,
+    pub timestamp: DateTimeTz,
+    pub activity: ActivityId,
     pub sets: Vec<Second<f64>>,
     pub comments: Option<String>,
 }
@@ -20,7 +21,7 @@ pub struct RepDurationRecord {
 impl RepDurationRecord {
     pub fn new(
         timestamp: DateTimeTz,
-        activity: ActivityType,
+        activity: ActivityId,
         sets: Vec<Second<f64>>,
         comments: Option<String>,
     ) -> RepDurationRecord {
@@ -39,10 +40,6 @@ impl Recordable for RepDurationRecord {
     }
 
     fn tags(&self) -> Vec<String> {
-        match self.activity {
-            ActivityType::MartialArts => vec![String::from("MartialArts")],
-            ActivityType::Planks => vec![String::from("Planks")],
-            ActivityType::Yoga => vec![String::from("Yoga")],
-        }
+        vec![self.activity.to_string()]
     }
 }
@@ -1,4 +1,5 @@
 use crate::error::{Error, Result};
+use crate::types::activity::ActivityId;
 use emseries::{DateTimeTz, Recordable};
 
 #[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
@@ -11,8 +12,8 @@ pub enum ActivityType {
 #[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
 pub struct SetRepRecord {
     #[serde(rename = "date")]
-    timestamp: DateTimeTz,
-    pub activity: ActivityType,
+    pub timestamp: DateTimeTz,
+    pub activity: ActivityId,
     pub sets: Vec<u32>,
     pub comments: Option<String>,
 }
@@ -20,7 +21,7 @@ pub struct SetRepRecord {
 impl SetRepRecord {
     pub fn new(
         timestamp: DateTimeTz,
-        activity: ActivityType,
+        activity: ActivityId,
         sets: Vec<u32>,
         comments: Option<String>,
     ) -> Result<SetRepRecord> {
@@ -43,10 +44,6 @@ impl Recordable for SetRepRecord {
     }
 
     fn tags(&self) -> Vec<String> {
-        match self.activity {
-            ActivityType::Pushups => vec![String::from("Pushups")],
-            ActivityType::Situps => vec![String::from("Situps")],
-            ActivityType::Squats => vec![String::from("Squats")],
-        }
+        vec![self.activity.to_string()]
     }
 }
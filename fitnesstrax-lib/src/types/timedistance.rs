@@ -1,3 +1,4 @@
+use crate::types::activity::ActivityId;
 use dimensioned::si::{Meter, Second};
 use emseries::{DateTimeTz, Recordable};
 use std::convert::TryFrom;
@@ -40,7 +41,7 @@ impl TryFrom<&str> for ActivityType {
 pub struct TimeDistanceRecord {
     #[serde(rename = "date")]
     pub timestamp: DateTimeTz,
-    pub activity: ActivityType,
+    pub activity: ActivityId,
     pub distance: Option<Meter<f64>>,
     pub duration: Option<Second<f64>>,
     pub comments: Option<String>,
@@ -49,7 +50,7 @@ pub struct TimeDistanceRecord {
 impl TimeDistanceRecord {
     pub fn new(
         timestamp: DateTimeTz,
-        activity: ActivityType,
+        activity: ActivityId,
         distance: Option<Meter<f64>>,
         duration: Option<Second<f64>>,
         comments: Option<String>,
@@ -70,26 +71,24 @@ impl Recordable for TimeDistanceRecord {
     }
 
     fn tags(&self) -> Vec<String> {
-        match self.activity {
-            ActivityType::Cycling => vec![String::from("Cycling")],
-            ActivityType::Rowing => vec![String::from("Rowing")],
-            ActivityType::Running => vec![String::from("Running")],
-            ActivityType::Swimming => vec![String::from("Swimming")],
-            ActivityType::Walking => vec![String::from("Walking")],
-        }
+        vec![self.activity.to_string()]
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::{ActivityType, TimeDistanceRecord};
+    use crate::types::activity::ActivityId;
     use dimensioned::si::{M, S};
 
     #[test]
     pub fn deserialize_time_distance() {
         let cycling_track_str = "{\"distance\":12200,\"date\":\"2017-10-28T19:27:00Z\",\"activity\":\"Cycling\",\"comments\":null,\"duration\":3120}";
         let cycle_track: TimeDistanceRecord = serde_json::from_str(cycling_track_str).unwrap();
-        assert_eq!(cycle_track.activity, ActivityType::Cycling);
+        assert_eq!(
+            cycle_track.activity,
+            ActivityId::from(ActivityType::Cycling)
+        );
         assert_eq!(cycle_track.distance, Some(12200. * M));
         assert_eq!(cycle_track.duration, Some(3120. * S));
 
@@ -98,11 +97,11 @@ mod test {
             serde_json::from_str(running_track_str);
         match running_track {
             Ok(track) => {
-                assert_eq!(track.activity, ActivityType::Running);
+                assert_eq!(track.activity, ActivityId::from(ActivityType::Running));
                 assert_eq!(track.distance, Some(3630. * M));
                 assert_eq!(track.duration, Some(1800. * S));
             }
-            Err(err) => panic!(err),
+            Err(err) => panic!("{}", err),
         }
     }
 }
@@ -0,0 +1,121 @@
+use crate::error::{Error, Result};
+use crate::types::activity::ActivityId;
+use dimensioned::si::Kilogram;
+use emseries::{DateTimeTz, Recordable};
+use std::convert::TryFrom;
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub enum ActivityType {
+    BenchPress,
+    Deadlift,
+    OverheadPress,
+    Row,
+    Squat,
+}
+
+pub fn activity_types() -> Vec<ActivityType> {
+    vec![
+        ActivityType::BenchPress,
+        ActivityType::Deadlift,
+        ActivityType::OverheadPress,
+        ActivityType::Row,
+        ActivityType::Squat,
+    ]
+}
+
+impl TryFrom<&str> for ActivityType {
+    type Error = &'static str;
+
+    fn try_from(inp: &str) -> std::result::Result<ActivityType, Self::Error> {
+        match inp {
+            "BenchPress" => Ok(ActivityType::BenchPress),
+            "Deadlift" => Ok(ActivityType::Deadlift),
+            "OverheadPress" => Ok(ActivityType::OverheadPress),
+            "Row" => Ok(ActivityType::Row),
+            "Squat" => Ok(ActivityType::Squat),
+            _ => Err("invalid activity string"),
+        }
+    }
+}
+
+/// A single set of a weighted exercise. `rpe` is the optional Rate of Perceived Exertion, on the
+/// usual 1 to 10 scale.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct WeightedSet {
+    pub reps: u32,
+    pub load: Kilogram<f64>,
+    pub rpe: Option<f64>,
+}
+
+impl WeightedSet {
+    pub fn new(reps: u32, load: Kilogram<f64>, rpe: Option<f64>) -> Result<WeightedSet> {
+        let rpe_valid = rpe.map(|v| (1.0..=10.0).contains(&v)).unwrap_or(true);
+        if reps > 0 && load.value_unsafe >= 0.0 && rpe_valid {
+            Ok(WeightedSet { reps, load, rpe })
+        } else {
+            Err(Error::InvalidParameter)
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct WeightedSetRecord {
+    #[serde(rename = "date")]
+    pub timestamp: DateTimeTz,
+    pub activity: ActivityId,
+    pub sets: Vec<WeightedSet>,
+    pub comments: Option<String>,
+}
+
+impl WeightedSetRecord {
+    pub fn new(
+        timestamp: DateTimeTz,
+        activity: ActivityId,
+        sets: Vec<WeightedSet>,
+        comments: Option<String>,
+    ) -> WeightedSetRecord {
+        WeightedSetRecord {
+            timestamp,
+            activity,
+            sets,
+            comments,
+        }
+    }
+}
+
+impl Recordable for WeightedSetRecord {
+    fn timestamp(&self) -> DateTimeTz {
+        self.timestamp.clone()
+    }
+
+    fn tags(&self) -> Vec<String> {
+        vec![self.activity.to_string()]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ActivityType, WeightedSet, WeightedSetRecord};
+    use crate::types::activity::ActivityId;
+    use dimensioned::si::KG;
+
+    #[test]
+    pub fn it_rejects_invalid_sets() {
+        assert!(WeightedSet::new(0, 50. * KG, None).is_err());
+        assert!(WeightedSet::new(5, -1. * KG, None).is_err());
+        assert!(WeightedSet::new(5, 50. * KG, Some(11.)).is_err());
+        assert!(WeightedSet::new(5, 50. * KG, Some(8.5)).is_ok());
+    }
+
+    #[test]
+    pub fn deserialize_weighted_set() {
+        let record_str = "{\"date\":\"2020-03-01T18:00:00Z\",\"activity\":\"Deadlift\",\"sets\":[{\"reps\":5,\"load\":100.0,\"rpe\":8.0},{\"reps\":3,\"load\":110.0,\"rpe\":null}],\"comments\":null}";
+        let record: WeightedSetRecord = serde_json::from_str(record_str).unwrap();
+        assert_eq!(record.activity, ActivityId::from(ActivityType::Deadlift));
+        assert_eq!(record.sets.len(), 2);
+        assert_eq!(record.sets[0].load, 100. * KG);
+        assert_eq!(record.sets[0].rpe, Some(8.));
+        assert_eq!(record.sets[1].reps, 3);
+        assert_eq!(record.sets[1].rpe, None);
+    }
+}
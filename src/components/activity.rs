@@ -0,0 +1,49 @@
+use fitnesstrax_lib::activity::{ActivityId, ActivityKind, ActivityRegistry};
+
+use crate::components::basics::{dropmenu_c, labeled_widget_c, MenuOptions};
+use crate::i18n::Text;
+
+pub fn activity_name(id: &ActivityId, activities: &ActivityRegistry, text: &Text) -> String {
+    activities
+        .get(id)
+        .map(|activity| String::from(activity.name_for(text.language_id().get_language())))
+        .unwrap_or_else(|| id.to_string())
+}
+
+pub fn activity_c(id: &ActivityId, activities: &ActivityRegistry, text: &Text) -> gtk::Label {
+    gtk::Label::new(Some(&activity_name(id, activities, text)))
+}
+
+pub fn activity_edit_c(
+    kind: ActivityKind,
+    current: &ActivityId,
+    activities: &ActivityRegistry,
+    text: &Text,
+    on_update: Box<dyn Fn(ActivityId)>,
+) -> gtk::Widget {
+    let menu: Vec<(String, String)> = activities
+        .activities_of_kind(kind)
+        .iter()
+        .map(|activity| {
+            (
+                activity.id.to_string(),
+                String::from(activity.name_for(text.language_id().get_language())),
+            )
+        })
+        .collect();
+    /* It's really annoying that I have to do this, but the iterators won't convert a vec of
+     * (String, Cow) to (&str, &str), and trying to do it all in a single statement leads to a
+     * lot of temporaries getting dropped. */
+    let menu_: Vec<(&str, &str)> = menu
+        .iter()
+        .map(|(id, text)| (id.as_ref(), text.as_ref()))
+        .collect();
+    labeled_widget_c(
+        &text.activity(),
+        dropmenu_c(
+            MenuOptions(menu_),
+            current.as_str(),
+            Box::new(move |val| on_update(ActivityId::new(val))),
+        ),
+    )
+}
@@ -1,18 +1,15 @@
 use dimensioned::si;
 
 use super::validated_text_entry_c;
-use crate::i18n::UnitSystem;
+use crate::i18n::DistanceUnit;
 
-pub fn distance_c(distance: &si::Meter<f64>, units: &UnitSystem) -> gtk::Label {
-    gtk::Label::new(Some(&format!(
-        "{}",
-        units.render_distance(distance.clone())
-    )))
+pub fn distance_c(distance: &si::Meter<f64>, units: DistanceUnit) -> gtk::Label {
+    gtk::Label::new(Some(&units.render(*distance)))
 }
 
 pub fn distance_edit_c(
     distance: &Option<si::Meter<f64>>,
-    units: &UnitSystem,
+    units: DistanceUnit,
     on_update: Box<dyn Fn(Option<si::Meter<f64>>)>,
 ) -> gtk::Widget {
     let u1 = units.clone();
@@ -21,14 +18,13 @@ pub fn distance_edit_c(
         distance.clone(),
         Box::new(move |s| {
             let u1 = u1.clone();
-            s.map(move |s_| u1.render_distance(s_.clone()))
-                .unwrap_or(String::from(""))
+            s.map(move |s_| u1.render(s_)).unwrap_or(String::from(""))
         }),
         Box::new(move |s| {
             if s.len() == 0 {
                 Ok(None)
             } else {
-                u2.parse_distance(s).map(|v| Some(v))
+                u2.parse(s).map(Some)
             }
         }),
         on_update,
@@ -6,6 +6,7 @@ mod duration;
 mod labeled_widget;
 mod text_entry;
 mod time;
+mod timezone_selector;
 
 pub use container::Container;
 pub use date::date_c;
@@ -15,3 +16,4 @@ pub use duration::{duration_c, duration_edit_c};
 pub use labeled_widget::labeled_widget_c;
 pub use text_entry::{text_entry_c, validated_text_entry_c};
 pub use time::{time_c, time_edit_c};
+pub use timezone_selector::timezone_selector_c;
@@ -28,11 +28,13 @@ pub fn validated_text_entry_c<A: 'static + Clone>(
             Ok(val) => {
                 let context = w.get_style_context();
                 context.remove_class(&gtk::STYLE_CLASS_WARNING);
+                w.set_tooltip_text(None);
                 on_update(val);
             }
-            Err(_err) => {
+            Err(err) => {
                 let context = w.get_style_context();
                 context.add_class(&gtk::STYLE_CLASS_WARNING);
+                w.set_tooltip_text(Some(&format!("{}", err)));
             }
         },
         None => (),
@@ -0,0 +1,99 @@
+use chrono_tz::Tz;
+use gtk::prelude::*;
+
+use crate::timezones::timezones_by_region;
+
+const LABEL_COLUMN: u32 = 0;
+/// The full name of the timezone, which is empty for the region rows.
+const NAME_COLUMN: u32 = 1;
+
+fn fill(store: &gtk::TreeStore, search: &str) {
+    store.clear();
+    for (region, names) in timezones_by_region(search) {
+        let parent =
+            store.insert_with_values(None, None, &[LABEL_COLUMN, NAME_COLUMN], &[&region, &""]);
+        for name in names {
+            let place = name
+                .split_once('/')
+                .map(|(_, place)| place)
+                .unwrap_or(name)
+                .replace('_', " ");
+            store.insert_with_values(
+                Some(&parent),
+                None,
+                &[LABEL_COLUMN, NAME_COLUMN],
+                &[&place, &name],
+            );
+        }
+    }
+}
+
+/// A button which shows the current timezone and opens a searchable list of every timezone,
+/// grouped by region.
+pub fn timezone_selector_c(current: &Tz, on_changed: Box<dyn Fn(Tz)>) -> gtk::Widget {
+    let button = gtk::MenuButton::new();
+    button.set_label(current.name());
+
+    let store = gtk::TreeStore::new(&[String::static_type(), String::static_type()]);
+    fill(&store, "");
+
+    let view = gtk::TreeView::new_with_model(&store);
+    view.set_headers_visible(false);
+    view.set_activate_on_single_click(true);
+    let column = gtk::TreeViewColumn::new();
+    let cell = gtk::CellRendererText::new();
+    column.pack_start(&cell, true);
+    column.add_attribute(&cell, "text", LABEL_COLUMN as i32);
+    view.append_column(&column);
+
+    let search = gtk::SearchEntry::new();
+    search.connect_search_changed(enclose!(store, view => move |search| {
+        let text = search.get_text().map(|s| s.to_string()).unwrap_or_default();
+        fill(&store, &text);
+        if !text.is_empty() {
+            view.expand_all();
+        }
+    }));
+
+    let scroller = gtk::ScrolledWindow::new(None::<&gtk::Adjustment>, None::<&gtk::Adjustment>);
+    scroller.set_size_request(300, 400);
+    scroller.add(&view);
+
+    let content = gtk::Box::new(gtk::Orientation::Vertical, 5);
+    content.pack_start(&search, false, false, 5);
+    content.pack_start(&scroller, true, true, 5);
+    content.show_all();
+
+    let popover = gtk::Popover::new(Some(&button));
+    popover.add(&content);
+    button.set_popover(Some(&popover));
+
+    view.connect_row_activated(enclose!(button => move |view, path, _| {
+        let name = view
+            .get_model()
+            .and_then(|model| {
+                model
+                    .get_iter(path)
+                    .map(|iter| model.get_value(&iter, NAME_COLUMN as i32))
+            })
+            .and_then(|value| value.get::<String>().ok().flatten())
+            .unwrap_or_default();
+        match name.parse::<Tz>() {
+            Ok(timezone) => {
+                button.set_label(timezone.name());
+                button.set_active(false);
+                on_changed(timezone);
+            }
+            // A region rather than a timezone.
+            Err(_) => {
+                if view.row_expanded(path) {
+                    view.collapse_row(path);
+                } else {
+                    view.expand_row(path, false);
+                }
+            }
+        }
+    }));
+
+    button.upcast::<gtk::Widget>()
+}
@@ -1,25 +1,31 @@
 use dimensioned::si::KG;
 use emseries::{DateTimeTz, Recordable, UniqueId};
+use fitnesstrax_lib::activity::ActivityRegistry;
+use fitnesstrax_lib::comments::Comments;
+use fitnesstrax_lib::personal_records::PersonalRecord;
 use fitnesstrax_lib::steps::StepRecord;
 use fitnesstrax_lib::weight::WeightRecord;
 use fitnesstrax_lib::TraxRecord;
 use gtk::prelude::*;
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
 use std::sync::{Arc, RwLock};
 use std::thread;
 
 use crate::components::basics::date_c;
-use crate::components::rep_duration::rep_duration_c;
-use crate::components::set_rep::set_rep_c;
+use crate::components::journal::{journal_c, journal_edit_c};
+use crate::components::rep_duration::{rep_duration_c, rep_duration_edit_c};
+use crate::components::set_rep::{set_rep_c, set_rep_edit_c};
 use crate::components::steps::{steps_c, steps_edit_c};
-use crate::components::time_distance::TimeDistanceEdit;
+use crate::components::time_distance::time_distance_edit_c;
 use crate::components::time_distance_row::time_distance_c;
 use crate::components::weight::{weight_record_c, weight_record_edit_c};
+use crate::components::weighted_set::{weighted_set_c, weighted_set_edit_c};
 use crate::components::{Component, Container};
 use crate::context::Application;
 use crate::i18n::{Text, UnitSystem};
+use crate::range::{day_start, TimezoneDisplay};
 
 #[derive(Clone)]
 pub struct Day {
@@ -30,7 +36,10 @@ pub struct Day {
 
     date: chrono::Date<chrono_tz::Tz>,
     records: Vec<(UniqueId, TraxRecord)>,
+    personal_records: Vec<PersonalRecord>,
     timezone: chrono_tz::Tz,
+    timezone_display: TimezoneDisplay,
+    activities: ActivityRegistry,
     text: Text,
     units: UnitSystem,
 }
@@ -46,7 +55,10 @@ impl Day {
         ctx: Arc<RwLock<Application>>,
         date: chrono::Date<chrono_tz::Tz>,
         records: Vec<(UniqueId, TraxRecord)>,
+        personal_records: Vec<PersonalRecord>,
         timezone: chrono_tz::Tz,
+        timezone_display: TimezoneDisplay,
+        activities: ActivityRegistry,
         text: Text,
         units: UnitSystem,
     ) -> Day {
@@ -62,8 +74,11 @@ impl Day {
 
         let view = Container::new(Some(day_c(
             &date,
-            records.iter().map(|rec| &rec.1).collect(),
+            &records,
+            &personal_records,
             &timezone,
+            timezone_display,
+            &activities,
             &text,
             &units,
         )));
@@ -79,7 +94,10 @@ impl Day {
             ctx,
             date,
             records,
+            personal_records,
             timezone,
+            timezone_display,
+            activities,
             text,
             units,
         };
@@ -99,8 +117,11 @@ impl Day {
         self.view.borrow_mut().swap(Some(
             day_c(
                 &self.date,
-                self.records.iter().map(|rec| &rec.1).collect(),
+                &self.records,
+                &self.personal_records,
                 &self.timezone,
+                self.timezone_display,
+                &self.activities,
                 &self.text,
                 &self.units,
             )
@@ -114,35 +135,78 @@ impl Day {
             acc.insert(rec.0.clone(), rec.1.clone());
             acc
         });
-        self.view.borrow_mut().swap(
-            Some(DayEdit::new(
-                &self.date,
-                &record_map,
-                self.timezone.clone(),
-                self.text.clone(),
-                self.units.clone(),
-                Box::new(enclose!(component => move | updated_records, new_records| component.borrow_mut().save(updated_records, new_records))),
+        self.view.borrow_mut().swap(Some(DayEdit::new(
+            &self.date,
+            &record_map,
+            self.timezone,
+            self.timezone_display,
+            self.activities.clone(),
+            self.text.clone(),
+            self.units,
+            Box::new(
+                enclose!(component => move |updated_records, new_records, deleted_records| {
+                    component.borrow_mut().save(updated_records, new_records, deleted_records)
+                }),
+            ),
             Box::new(enclose!(component => move || component.borrow_mut().view())),
-            ))
-        );
+        )));
     }
 
-    fn save(&mut self, updated_records: Vec<(UniqueId, TraxRecord)>, new_records: Vec<TraxRecord>) {
+    fn save(
+        &mut self,
+        updated_records: Vec<(UniqueId, TraxRecord)>,
+        new_records: Vec<TraxRecord>,
+        deleted_records: Vec<UniqueId>,
+    ) {
         let ctx = self.ctx.clone();
+        // A failed save gets reported to the window through the application's channel.
         thread::spawn(move || {
             ctx.write()
                 .unwrap()
-                .save_records(updated_records, new_records)
-                .expect("record save failed");
+                .save_records(updated_records, new_records, deleted_records)
         });
         self.view();
     }
 }
 
+/// A badge listing the personal records that a record set.
+fn personal_records_c(
+    id: &UniqueId,
+    personal_records: &[PersonalRecord],
+    text: &Text,
+) -> Option<gtk::Label> {
+    let categories: Vec<String> = personal_records
+        .iter()
+        .filter(|pr| pr.id == *id)
+        .map(|pr| text.record_category(&pr.category))
+        .collect();
+    if categories.is_empty() {
+        return None;
+    }
+    let badge = gtk::Label::new(Some(&format!("🏆 {}", categories.join(", "))));
+    badge.set_tooltip_text(Some(&text.personal_record()));
+    Some(badge)
+}
+
+/// The abbreviation of the timezone that a record was written in, for records from somewhere
+/// other than home. The full name of the timezone is in the tooltip.
+fn recorded_zone_c(timestamp: &DateTimeTz, home: &chrono_tz::Tz) -> Option<gtk::Label> {
+    let zone = timestamp.0.timezone();
+    if zone == *home {
+        return None;
+    }
+    let label = gtk::Label::new(Some(&format!("{}", timestamp.0.format("%Z"))));
+    label.set_tooltip_text(Some(zone.name()));
+    Some(label)
+}
+
 fn day_c(
     _date: &chrono::Date<chrono_tz::Tz>,
-    data: Vec<&TraxRecord>,
+    data: &[(UniqueId, TraxRecord)],
+    personal_records: &[PersonalRecord],
     timezone: &chrono_tz::Tz,
+    timezone_display: TimezoneDisplay,
+    activities: &ActivityRegistry,
     text: &Text,
     units: &UnitSystem,
 ) -> gtk::Box {
@@ -153,23 +217,44 @@ fn day_c(
 
     let mut weight_component = None;
     let mut step_component = None;
+    let mut journal_component = None;
     let mut rep_duration_components: Vec<gtk::Box> = Vec::new();
     let mut set_rep_components: Vec<gtk::Box> = Vec::new();
     let mut time_distance_components: Vec<gtk::Box> = Vec::new();
-    let mut records = data.clone();
-    records.sort_unstable_by_key(|rec| rec.timestamp());
-    for record in records {
+    let mut weighted_set_components: Vec<gtk::Box> = Vec::new();
+    let mut records: Vec<&(UniqueId, TraxRecord)> = data.iter().collect();
+    records.sort_unstable_by_key(|(_, rec)| rec.timestamp());
+    for (id, record) in records {
+        let badge = personal_records_c(id, personal_records, text);
+        let recorded_zone = recorded_zone_c(&record.timestamp(), timezone);
+        let zone = timezone_display.zone(&record.timestamp(), timezone);
+        let with_badge = |component: gtk::Box| {
+            if let Some(ref recorded_zone) = recorded_zone {
+                component.pack_start(recorded_zone, false, false, 5);
+            }
+            if let Some(ref badge) = badge {
+                component.pack_start(badge, false, false, 5);
+            }
+            component
+        };
         match record {
-            TraxRecord::Comments(ref _rec) => (),
-            TraxRecord::RepDuration(ref rec) => rep_duration_components.push(rep_duration_c(&rec)),
-            TraxRecord::SetRep(ref rec) => set_rep_components.push(set_rep_c(&rec)),
-            TraxRecord::Steps(ref rec) => step_component = Some(steps_c(&rec, text)),
-            TraxRecord::TimeDistance(ref rec) => {
-                time_distance_components.push(time_distance_c(&rec, timezone, text, units))
+            TraxRecord::Comments(ref rec) => journal_component = Some(journal_c(rec)),
+            TraxRecord::RepDuration(ref rec) => {
+                rep_duration_components.push(with_badge(rep_duration_c(rec, activities, text)))
+            }
+            TraxRecord::SetRep(ref rec) => {
+                set_rep_components.push(with_badge(set_rep_c(rec, activities, text)))
             }
+            TraxRecord::Steps(ref rec) => step_component = Some(steps_c(rec, text)),
+            TraxRecord::TimeDistance(ref rec) => time_distance_components.push(with_badge(
+                time_distance_c(rec, &zone, activities, text, units),
+            )),
             TraxRecord::Weight(ref rec) => {
-                weight_component = Some(weight_record_c(&rec, text, units))
+                weight_component = Some(weight_record_c(rec, text, units))
             }
+            TraxRecord::WeightedSet(ref rec) => weighted_set_components.push(with_badge(
+                weighted_set_c(rec, &zone, activities, text, units),
+            )),
         }
     }
 
@@ -178,17 +263,26 @@ fn day_c(
     for component in time_distance_components {
         container.pack_start(&component, false, false, 5);
     }
+    for component in weighted_set_components {
+        container.pack_start(&component, false, false, 5);
+    }
     for component in set_rep_components {
         container.pack_start(&component, false, false, 5);
     }
     for component in rep_duration_components {
         container.pack_start(&component, false, false, 5);
     }
+    if let Some(c) = journal_component {
+        container.pack_start(&c, false, false, 5);
+    }
 
     container.show_all();
     return container;
 }
 
+/// Called with the updated records, the new records and the ids of the deleted records.
+type SaveDay = Box<dyn Fn(Vec<(UniqueId, TraxRecord)>, Vec<TraxRecord>, Vec<UniqueId>)>;
+
 #[derive(Clone)]
 struct DayEdit {
     widget: gtk::Box,
@@ -205,13 +299,17 @@ impl DayEdit {
         date: &chrono::Date<chrono_tz::Tz>,
         data: &HashMap<UniqueId, TraxRecord>,
         timezone: chrono_tz::Tz,
+        timezone_display: TimezoneDisplay,
+        activities: ActivityRegistry,
         text: Text,
         units: UnitSystem,
-        on_save: Box<dyn Fn(Vec<(UniqueId, TraxRecord)>, Vec<TraxRecord>)>,
+        on_save: SaveDay,
         on_cancel: Box<dyn Fn()>,
     ) -> DayEdit {
+        let date = *date;
         let updates = Rc::new(RefCell::new(HashMap::new()));
         let new_records = Rc::new(RefCell::new(HashMap::new()));
+        let deletions = Rc::new(RefCell::new(HashSet::new()));
 
         let widget = gtk::Box::new(gtk::Orientation::Vertical, 5);
 
@@ -221,7 +319,7 @@ impl DayEdit {
         let mut weight_component = {
             weight_record_edit_c(
                 UniqueId::new(),
-                WeightRecord::new(DateTimeTz(date.clone().and_hms(0, 0, 0)), 0.0 * KG),
+                WeightRecord::new(DateTimeTz(day_start(&date)), 0.0 * KG),
                 &text,
                 units.clone(),
                 Box::new(enclose!(new_records => move |id, rec| {
@@ -233,7 +331,7 @@ impl DayEdit {
         let mut step_component = {
             steps_edit_c(
                 UniqueId::new(),
-                StepRecord::new(DateTimeTz(date.clone().and_hms(0, 0, 0)), 0),
+                StepRecord::new(DateTimeTz(day_start(&date)), 0),
                 &text,
                 Box::new(enclose!(new_records => move |id, rec| {
                     new_records.borrow_mut().insert(id, TraxRecord::from(rec));
@@ -241,7 +339,11 @@ impl DayEdit {
             )
         };
 
+        let mut journal_record: Option<(UniqueId, Comments)> = None;
+        let mut rep_duration_records = Vec::new();
+        let mut set_rep_records = Vec::new();
         let mut time_distance_records = Vec::new();
+        let mut weighted_set_records = Vec::new();
 
         for (id, data) in data {
             match data {
@@ -254,7 +356,8 @@ impl DayEdit {
                         Box::new(enclose!(updates => move |id, rec| {
                             updates.borrow_mut().insert(id, TraxRecord::from(rec));
                         })),
-                    )
+                    );
+                    delete_button_c(&weight_component, id, &text, &updates, &deletions);
                 }
                 TraxRecord::Steps(ref rec) => {
                     step_component = steps_edit_c(
@@ -264,26 +367,86 @@ impl DayEdit {
                         Box::new(enclose!(updates => move |id_, rec| {
                             updates.borrow_mut().insert(id_.clone(), TraxRecord::from(rec));
                         })),
-                    )
+                    );
+                    delete_button_c(&step_component, id, &text, &updates, &deletions);
+                }
+                TraxRecord::Comments(ref rec) => {
+                    journal_record = Some((id.clone(), rec.clone()));
+                }
+                TraxRecord::RepDuration(ref rec) => {
+                    rep_duration_records.push((id, rec));
+                }
+                TraxRecord::SetRep(ref rec) => {
+                    set_rep_records.push((id, rec));
                 }
                 TraxRecord::TimeDistance(ref rec) => {
                     time_distance_records.push((id, rec));
                 }
-                _ => (),
+                TraxRecord::WeightedSet(ref rec) => {
+                    weighted_set_records.push((id, rec));
+                }
             }
         }
 
-        let time_distance_edit = TimeDistanceEdit::new(
+        let time_distance_edit = time_distance_edit_c(
             date.clone(),
             time_distance_records,
             timezone.clone(),
+            timezone_display,
+            activities.clone(),
             text.clone(),
             units.clone(),
         );
 
+        let weighted_set_edit = weighted_set_edit_c(
+            date,
+            weighted_set_records,
+            timezone,
+            timezone_display,
+            activities.clone(),
+            text.clone(),
+            units,
+        );
+
+        let set_rep_edit = set_rep_edit_c(
+            date,
+            set_rep_records,
+            timezone,
+            timezone_display,
+            activities.clone(),
+            text.clone(),
+        );
+
+        let rep_duration_edit = rep_duration_edit_c(
+            date,
+            rep_duration_records,
+            timezone,
+            timezone_display,
+            activities.clone(),
+            text.clone(),
+        );
+
+        /* The journal only gets saved if it was edited. Clearing the text of an existing note
+         * deletes the note. */
+        let journal_update: Rc<RefCell<Option<String>>> = Rc::new(RefCell::new(None));
+        let journal_component = journal_edit_c(
+            journal_record
+                .as_ref()
+                .map(|(_, rec)| rec.text())
+                .unwrap_or(""),
+            &text,
+            Box::new(enclose!(journal_update => move |val| {
+                *journal_update.borrow_mut() = Some(val);
+            })),
+        );
+
         first_row.pack_start(&weight_component, false, false, 5);
         first_row.pack_start(&step_component, false, false, 5);
         widget.pack_start(&time_distance_edit.widget, false, false, 5);
+        widget.pack_start(&weighted_set_edit.widget, false, false, 5);
+        widget.pack_start(&set_rep_edit.widget, false, false, 5);
+        widget.pack_start(&rep_duration_edit.widget, false, false, 5);
+        widget.pack_start(&journal_component, false, false, 5);
 
         let buttons_row = gtk::Box::new(gtk::Orientation::Horizontal, 5);
         let save_button = gtk::Button::new_with_label(&text.save());
@@ -292,7 +455,7 @@ impl DayEdit {
         buttons_row.pack_start(&cancel_button, false, false, 5);
         widget.pack_start(&buttons_row, false, false, 5);
 
-        save_button.connect_clicked(enclose!(updates, new_records => move |_| {
+        save_button.connect_clicked(enclose!(updates, new_records, deletions => move |_| {
             let mut updated_records: Vec<(UniqueId, TraxRecord)> = updates
                 .borrow()
                 .iter()
@@ -320,7 +483,75 @@ impl DayEdit {
                     .collect::<Vec<TraxRecord>>(),
             );
 
-            on_save(updated_records, new_records);
+            updated_records.append(
+                &mut weighted_set_edit
+                    .updated_records()
+                    .into_iter()
+                    .map(|(id, rec)| (id, TraxRecord::from(rec)))
+                    .collect::<Vec<(UniqueId, TraxRecord)>>(),
+            );
+
+            new_records.append(
+                &mut weighted_set_edit
+                    .new_records()
+                    .into_iter()
+                    .map(|(_, rec)| TraxRecord::from(rec))
+                    .collect::<Vec<TraxRecord>>(),
+            );
+
+            updated_records.append(
+                &mut set_rep_edit
+                    .updated_records()
+                    .into_iter()
+                    .map(|(id, rec)| (id, TraxRecord::from(rec)))
+                    .collect::<Vec<(UniqueId, TraxRecord)>>(),
+            );
+
+            new_records.append(
+                &mut set_rep_edit
+                    .new_records()
+                    .into_iter()
+                    .map(|(_, rec)| TraxRecord::from(rec))
+                    .collect::<Vec<TraxRecord>>(),
+            );
+
+            updated_records.append(
+                &mut rep_duration_edit
+                    .updated_records()
+                    .into_iter()
+                    .map(|(id, rec)| (id, TraxRecord::from(rec)))
+                    .collect::<Vec<(UniqueId, TraxRecord)>>(),
+            );
+
+            new_records.append(
+                &mut rep_duration_edit
+                    .new_records()
+                    .into_iter()
+                    .map(|(_, rec)| TraxRecord::from(rec))
+                    .collect::<Vec<TraxRecord>>(),
+            );
+
+            let mut deleted_records: Vec<UniqueId> = deletions.borrow().iter().cloned().collect();
+
+            if let Some(ref val) = *journal_update.borrow() {
+                match (&journal_record, val.trim().is_empty()) {
+                    (Some((id, _)), true) => deleted_records.push(id.clone()),
+                    (Some((id, rec)), false) => updated_records
+                        .push((id.clone(), TraxRecord::Comments(Comments::new(rec.timestamp(), val)))),
+                    (None, true) => (),
+                    (None, false) => new_records.push(TraxRecord::Comments(Comments::new(
+                        DateTimeTz(day_start(&date)),
+                        val,
+                    ))),
+                }
+            }
+
+            deleted_records.append(&mut time_distance_edit.deleted_records());
+            deleted_records.append(&mut weighted_set_edit.deleted_records());
+            deleted_records.append(&mut set_rep_edit.deleted_records());
+            deleted_records.append(&mut rep_duration_edit.deleted_records());
+
+            on_save(updated_records, new_records, deleted_records);
         }));
         cancel_button.connect_clicked(move |_| on_cancel());
 
@@ -329,3 +560,21 @@ impl DayEdit {
         DayEdit { widget }
     }
 }
+
+/// Add a delete button to the editor for a single existing record. Deleting the record discards any
+/// pending edits to it and hides the editor.
+fn delete_button_c(
+    component: &gtk::Box,
+    id: &UniqueId,
+    text: &Text,
+    updates: &Rc<RefCell<HashMap<UniqueId, TraxRecord>>>,
+    deletions: &Rc<RefCell<HashSet<UniqueId>>>,
+) {
+    let delete_button = gtk::Button::new_with_label(&text.delete());
+    delete_button.connect_clicked(enclose!(component, id, updates, deletions => move |_| {
+        updates.borrow_mut().remove(&id);
+        deletions.borrow_mut().insert(id.clone());
+        component.hide();
+    }));
+    component.pack_start(&delete_button, false, false, 5);
+}
@@ -0,0 +1,609 @@
+use dimensioned::si;
+use fitnesstrax_lib::activity::{ActivityId, ActivityKind, ActivityRegistry};
+use fitnesstrax_lib::goals::{Amount, Goal, Interval, Progress};
+use fitnesstrax_lib::streaks::{Condition, Streak};
+use fitnesstrax_lib::timedistance::ActivityType;
+use gtk::prelude::*;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::{Arc, RwLock};
+
+use crate::components::activity::activity_name;
+use crate::components::basics::{
+    dropmenu_c, labeled_widget_c, validated_text_entry_c, Container, MenuOptions,
+};
+use crate::components::Component;
+use crate::context::{Application, State};
+use crate::errors::{Error, Result};
+use crate::i18n::{DistanceUnit, Text, UnitSystem};
+
+/// The values entered into the form for a new goal. Only the fields for the selected kind of
+/// goal get used.
+#[derive(Clone, Debug)]
+struct GoalForm {
+    kind: String,
+    activity: ActivityId,
+    distance: Option<si::Meter<f64>>,
+    steps: Option<u32>,
+    interval: Interval,
+    start: Option<si::Kilogram<f64>>,
+    target: Option<si::Kilogram<f64>>,
+    by: Option<chrono::NaiveDate>,
+}
+
+impl GoalForm {
+    fn goal(&self) -> Option<Goal> {
+        match self.kind.as_str() {
+            "distance" => self.distance.map(|distance| Goal::Distance {
+                activity: self.activity.clone(),
+                distance,
+                interval: self.interval,
+            }),
+            "steps" => self.steps.map(|steps| Goal::Steps {
+                steps,
+                interval: self.interval,
+            }),
+            "weight" => match (self.start, self.target, self.by) {
+                (Some(start), Some(target), Some(by)) => Some(Goal::Weight { start, target, by }),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+/// Show why a change to the goals or streaks could not be saved, or clear the reason once a
+/// change has been saved.
+fn show_saved(status: &gtk::Label, text: &Text, result: Result<()>) {
+    match result {
+        Ok(()) => status.set_text(""),
+        Err(err) => status.set_text(&text.records_not_saved(&err)),
+    }
+}
+
+#[derive(Clone)]
+pub struct Goals {
+    widget: gtk::ScrolledWindow,
+    goals_box: gtk::Box,
+    status: gtk::Label,
+
+    goals: Vec<(Goal, Progress)>,
+    streaks: Vec<(Condition, Streak)>,
+    activities: ActivityRegistry,
+    text: Text,
+    units: UnitSystem,
+
+    ctx: Arc<RwLock<Application>>,
+}
+
+impl Goals {
+    pub fn new(
+        goals: Vec<(Goal, Progress)>,
+        streaks: Vec<(Condition, Streak)>,
+        activities: &ActivityRegistry,
+        text: &Text,
+        units: &UnitSystem,
+        ctx: Arc<RwLock<Application>>,
+    ) -> Goals {
+        let no_adjustment: Option<&gtk::Adjustment> = None;
+        let widget = gtk::ScrolledWindow::new(no_adjustment, no_adjustment);
+        let content = gtk::Box::new(gtk::Orientation::Vertical, 5);
+        let status = gtk::Label::new(None);
+        let goals_box = gtk::Box::new(gtk::Orientation::Vertical, 5);
+        content.pack_start(&status, false, false, 5);
+        content.pack_start(&goals_box, false, false, 5);
+        widget.add(&content);
+        widget.show_all();
+
+        let mut component = Goals {
+            widget,
+            goals_box,
+            status,
+
+            goals,
+            streaks,
+            activities: activities.clone(),
+            text: text.clone(),
+            units: *units,
+
+            ctx,
+        };
+
+        component.render();
+
+        component
+    }
+
+    pub fn set_goals(&mut self, goals: Vec<(Goal, Progress)>) {
+        self.goals = goals;
+        self.render();
+    }
+
+    pub fn set_streaks(&mut self, streaks: Vec<(Condition, Streak)>) {
+        self.streaks = streaks;
+        self.render();
+    }
+
+    pub fn set_activities(&mut self, activities: ActivityRegistry) {
+        self.activities = activities;
+        self.render();
+    }
+
+    pub fn set_language(&mut self, text: Text) {
+        self.text = text;
+        self.render();
+    }
+
+    pub fn set_units(&mut self, units: UnitSystem) {
+        self.units = units;
+        self.render();
+    }
+
+    fn render(&mut self) {
+        self.goals_box.foreach(|child| child.destroy());
+
+        for (idx, (goal, progress)) in self.goals.iter().enumerate() {
+            self.goals_box
+                .pack_start(&self.goal_c(idx, goal, progress), false, false, 5);
+        }
+        self.goals_box
+            .pack_start(&self.goal_form_c(), false, false, 5);
+        self.goals_box
+            .pack_start(&self.streaks_c(), false, false, 5);
+
+        self.goals_box.show_all();
+    }
+
+    fn goal_c(&self, idx: usize, goal: &Goal, progress: &Progress) -> gtk::Box {
+        let container = gtk::Box::new(gtk::Orientation::Vertical, 5);
+        let header = gtk::Box::new(gtk::Orientation::Horizontal, 5);
+
+        let description = match goal {
+            Goal::Distance {
+                activity,
+                distance,
+                interval,
+            } => self.text.distance_goal(
+                &activity_name(activity, &self.activities, &self.text),
+                &self
+                    .text
+                    .distance(*distance, self.units.distance_for(activity)),
+                *interval,
+            ),
+            Goal::Steps { steps, interval } => self
+                .text
+                .steps_goal(&self.text.step_count(*steps), *interval),
+            Goal::Weight { target, by, .. } => self
+                .text
+                .weight_goal(&self.text.mass(*target, self.units.body_mass), by),
+        };
+        header.pack_start(&gtk::Label::new(Some(&description)), false, false, 5);
+
+        let remove_button = gtk::Button::new_with_label(&self.text.remove());
+        let ctx = self.ctx.clone();
+        let status = self.status.clone();
+        let text = self.text.clone();
+        remove_button.connect_clicked(move |_| {
+            let result = ctx.write().unwrap().remove_goal(idx);
+            show_saved(&status, &text, result);
+        });
+        header.pack_end(&remove_button, false, false, 5);
+
+        let bar = gtk::ProgressBar::new();
+        bar.set_fraction(progress.complete);
+        bar.set_text(Some(&format!("{:.0}%", progress.complete * 100.)));
+        bar.set_show_text(true);
+
+        let distance_units = match goal {
+            Goal::Distance { activity, .. } => self.units.distance_for(activity),
+            _ => self.units.distance,
+        };
+        let target = match goal {
+            Goal::Distance { distance, .. } => Amount::Distance(*distance),
+            Goal::Steps { steps, .. } => Amount::Steps(*steps),
+            Goal::Weight { target, .. } => Amount::Weight(*target),
+        };
+        let mut details = match progress.current {
+            Some(current) => self.text.goal_progress(
+                &self.amount(current, distance_units),
+                &self.amount(target, distance_units),
+            ),
+            None => self.text.no_weights(),
+        };
+        if let Goal::Weight { .. } = goal {
+            if progress.complete < 1. {
+                details = format!(
+                    "{}. {}",
+                    details,
+                    match progress.projected {
+                        Some(ref date) => self.text.projected(date),
+                        None => self.text.not_projected(),
+                    }
+                );
+            }
+        }
+        let details = gtk::Label::new(Some(&details));
+        details.set_xalign(0.0);
+
+        container.pack_start(&header, false, false, 5);
+        container.pack_start(&bar, false, false, 5);
+        container.pack_start(&details, false, false, 5);
+        container
+    }
+
+    fn amount(&self, amount: Amount, distance_units: DistanceUnit) -> String {
+        match amount {
+            Amount::Distance(distance) => self.text.distance(distance, distance_units),
+            Amount::Steps(steps) => self.text.step_count(steps),
+            Amount::Weight(weight) => self.text.mass(weight, self.units.body_mass),
+        }
+    }
+
+    fn goal_form_c(&self) -> gtk::Widget {
+        let form = Rc::new(RefCell::new(GoalForm {
+            kind: String::from("distance"),
+            activity: ActivityType::Running.into(),
+            distance: None,
+            steps: None,
+            interval: Interval::Week,
+            start: None,
+            target: None,
+            by: None,
+        }));
+
+        let widget = gtk::Box::new(gtk::Orientation::Vertical, 5);
+        let fields = Rc::new(RefCell::new(Container::new(Some(self.fields_c(&form)))));
+
+        let component = self.clone();
+        let kind_menu = dropmenu_c(
+            MenuOptions(vec![
+                ("distance", &self.text.distance_goal_kind()),
+                ("steps", &self.text.steps_goal_kind()),
+                ("weight", &self.text.weight_goal_kind()),
+            ]),
+            &form.borrow().kind,
+            Box::new(enclose!(form, fields => move |kind| {
+                form.borrow_mut().kind = String::from(kind);
+                fields.borrow_mut().swap(Some(component.fields_c(&form)));
+            })),
+        );
+
+        let add_button = gtk::Button::new_with_label(&self.text.add_goal());
+        let ctx = self.ctx.clone();
+        let status = self.status.clone();
+        let text = self.text.clone();
+        add_button.connect_clicked(enclose!(form => move |_| {
+            if let Some(goal) = form.borrow().goal() {
+                let result = ctx.write().unwrap().add_goal(goal);
+                show_saved(&status, &text, result);
+            }
+        }));
+
+        let row = gtk::Box::new(gtk::Orientation::Horizontal, 5);
+        row.pack_start(&kind_menu, false, false, 5);
+        row.pack_start(&fields.borrow().widget(), false, false, 5);
+        row.pack_start(&add_button, false, false, 5);
+        widget.pack_start(&row, false, false, 5);
+
+        let frame = gtk::Frame::new(Some(&self.text.add_goal()));
+        frame.add(&widget);
+        frame.upcast::<gtk::Widget>()
+    }
+
+    /// The entries for the kind of goal that is selected in the form.
+    fn fields_c(&self, form: &Rc<RefCell<GoalForm>>) -> gtk::Box {
+        let fields = gtk::Box::new(gtk::Orientation::Horizontal, 5);
+        let kind = form.borrow().kind.clone();
+        match kind.as_str() {
+            "distance" => {
+                let activities: Vec<(String, String)> = self
+                    .activities
+                    .activities_of_kind(ActivityKind::TimeDistance)
+                    .into_iter()
+                    .map(|activity| {
+                        (
+                            activity.id.to_string(),
+                            activity_name(&activity.id, &self.activities, &self.text),
+                        )
+                    })
+                    .collect();
+                let activity_menu = dropmenu_c(
+                    MenuOptions(
+                        activities
+                            .iter()
+                            .map(|(id, name)| (id.as_ref(), name.as_ref()))
+                            .collect(),
+                    ),
+                    &form.borrow().activity.to_string(),
+                    Box::new(enclose!(form => move |id| {
+                        form.borrow_mut().activity = ActivityId::new(id);
+                    })),
+                );
+                let units = self.units;
+                let distance = validated_text_entry_c(
+                    form.borrow().distance,
+                    Box::new(move |distance| {
+                        distance
+                            .map(|distance| units.render_distance(distance))
+                            .unwrap_or_default()
+                    }),
+                    Box::new(move |s| units.parse_distance(s).map(Some)),
+                    Box::new(enclose!(form => move |distance| {
+                        form.borrow_mut().distance = distance;
+                    })),
+                );
+                fields.pack_start(
+                    &labeled_widget_c(&self.text.activity(), activity_menu),
+                    false,
+                    false,
+                    5,
+                );
+                fields.pack_start(
+                    &labeled_widget_c(&self.text.distance_label(self.units.distance), distance),
+                    false,
+                    false,
+                    5,
+                );
+                fields.pack_start(&self.interval_c(form), false, false, 5);
+            }
+            "steps" => {
+                let steps = validated_text_entry_c(
+                    form.borrow().steps,
+                    Box::new(|steps| steps.map(|s| format!("{}", s)).unwrap_or_default()),
+                    Box::new(|s| match s.parse::<u32>() {
+                        Ok(steps) if steps > 0 => Ok(Some(steps)),
+                        _ => Err(Error::ParseStepsError),
+                    }),
+                    Box::new(enclose!(form => move |steps| {
+                        form.borrow_mut().steps = steps;
+                    })),
+                );
+                fields.pack_start(
+                    &labeled_widget_c(&self.text.steps_label(), steps),
+                    false,
+                    false,
+                    5,
+                );
+                fields.pack_start(&self.interval_c(form), false, false, 5);
+            }
+            _ => {
+                /* The form only switches to a weight goal from the kind menu, so looking up the
+                 * latest weight here never happens while the application is already locked. */
+                if form.borrow().start.is_none() {
+                    form.borrow_mut().start = match self.ctx.read().unwrap().get_state() {
+                        State::Unconfigured(_) => None,
+                        State::Configured(state) => state.latest_weight().ok().and_then(|w| w),
+                    };
+                }
+                fields.pack_start(
+                    &labeled_widget_c(
+                        &self.text.current_weight(),
+                        self.mass_entry_c(
+                            form.borrow().start,
+                            Box::new(enclose!(form => move |weight| {
+                                form.borrow_mut().start = weight;
+                            })),
+                        ),
+                    ),
+                    false,
+                    false,
+                    5,
+                );
+                fields.pack_start(
+                    &labeled_widget_c(
+                        &self.text.target_weight(),
+                        self.mass_entry_c(
+                            form.borrow().target,
+                            Box::new(enclose!(form => move |weight| {
+                                form.borrow_mut().target = weight;
+                            })),
+                        ),
+                    ),
+                    false,
+                    false,
+                    5,
+                );
+                let by = validated_text_entry_c(
+                    form.borrow().by,
+                    Box::new(|by| {
+                        by.map(|by| format!("{}", by.format("%Y-%m-%d")))
+                            .unwrap_or_default()
+                    }),
+                    Box::new(|s| {
+                        chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d")
+                            .map(Some)
+                            .map_err(|_| Error::ParseDateError)
+                    }),
+                    Box::new(enclose!(form => move |by| {
+                        form.borrow_mut().by = by;
+                    })),
+                );
+                fields.pack_start(
+                    &labeled_widget_c(&self.text.target_date(), by),
+                    false,
+                    false,
+                    5,
+                );
+            }
+        }
+        fields
+    }
+
+    fn streaks_c(&self) -> gtk::Widget {
+        let widget = gtk::Box::new(gtk::Orientation::Vertical, 5);
+        for (idx, (condition, streak)) in self.streaks.iter().enumerate() {
+            let row = gtk::Box::new(gtk::Orientation::Horizontal, 5);
+            let activity = match condition {
+                Condition::Activity(ref activity) => {
+                    activity_name(activity, &self.activities, &self.text)
+                }
+                _ => String::new(),
+            };
+            row.pack_start(
+                &gtk::Label::new(Some(&self.text.streak_condition(condition, &activity))),
+                false,
+                false,
+                5,
+            );
+            row.pack_start(
+                &gtk::Label::new(Some(&self.text.streak(streak))),
+                false,
+                false,
+                5,
+            );
+            let remove_button = gtk::Button::new_with_label(&self.text.remove());
+            let ctx = self.ctx.clone();
+            let status = self.status.clone();
+            let text = self.text.clone();
+            remove_button.connect_clicked(move |_| {
+                let result = ctx.write().unwrap().remove_streak_condition(idx);
+                show_saved(&status, &text, result);
+            });
+            row.pack_end(&remove_button, false, false, 5);
+            widget.pack_start(&row, false, false, 5);
+        }
+        widget.pack_start(&self.streak_form_c(), false, false, 5);
+
+        let frame = gtk::Frame::new(Some(&self.text.streaks()));
+        frame.add(&widget);
+        frame.upcast::<gtk::Widget>()
+    }
+
+    fn streak_form_c(&self) -> gtk::Box {
+        let condition = Rc::new(RefCell::new(Some(Condition::AnyWorkout)));
+        let activity: Rc<RefCell<ActivityId>> = Rc::new(RefCell::new(ActivityType::Running.into()));
+        let steps = Rc::new(RefCell::new(10000));
+        let fields = Rc::new(RefCell::new(Container::new(None::<gtk::Widget>)));
+
+        let activities: Vec<(String, String)> = self
+            .activities
+            .activities()
+            .into_iter()
+            .map(|activity| {
+                (
+                    activity.id.to_string(),
+                    activity_name(&activity.id, &self.activities, &self.text),
+                )
+            })
+            .collect();
+        let text = self.text.clone();
+        let kind_menu = dropmenu_c(
+            MenuOptions(vec![
+                ("any-workout", &self.text.streak_any_workout()),
+                ("activity", &self.text.activity()),
+                ("steps", &self.text.steps_label()),
+                ("weight", &self.text.streak_weight()),
+            ]),
+            "any-workout",
+            Box::new(enclose!(condition, activity, steps, fields => move |kind| {
+                let widget = match kind {
+                    "activity" => {
+                        *condition.borrow_mut() = Some(Condition::Activity(activity.borrow().clone()));
+                        let menu = dropmenu_c(
+                            MenuOptions(
+                                activities
+                                    .iter()
+                                    .map(|(id, name)| (id.as_ref(), name.as_ref()))
+                                    .collect(),
+                            ),
+                            &activity.borrow().to_string(),
+                            Box::new(enclose!(condition, activity => move |id| {
+                                *activity.borrow_mut() = ActivityId::new(id);
+                                *condition.borrow_mut() = Some(Condition::Activity(ActivityId::new(id)));
+                            })),
+                        );
+                        Some(menu)
+                    }
+                    "steps" => {
+                        *condition.borrow_mut() = Some(Condition::Steps(*steps.borrow()));
+                        let entry = validated_text_entry_c(
+                            *steps.borrow(),
+                            Box::new(|steps| format!("{}", steps)),
+                            Box::new(|s| match s.parse::<u32>() {
+                                Ok(steps) if steps > 0 => Ok(steps),
+                                _ => Err(Error::ParseStepsError),
+                            }),
+                            Box::new(enclose!(condition, steps => move |count| {
+                                *steps.borrow_mut() = count;
+                                *condition.borrow_mut() = Some(Condition::Steps(count));
+                            })),
+                        );
+                        Some(labeled_widget_c(&text.steps_label(), entry))
+                    }
+                    "weight" => {
+                        *condition.borrow_mut() = Some(Condition::WeightLogged);
+                        None
+                    }
+                    _ => {
+                        *condition.borrow_mut() = Some(Condition::AnyWorkout);
+                        None
+                    }
+                };
+                fields.borrow_mut().swap(widget);
+            })),
+        );
+
+        let add_button = gtk::Button::new_with_label(&self.text.add_streak());
+        let ctx = self.ctx.clone();
+        let status = self.status.clone();
+        let text = self.text.clone();
+        add_button.connect_clicked(enclose!(condition => move |_| {
+            if let Some(ref condition) = *condition.borrow() {
+                let result = ctx.write().unwrap().add_streak_condition(condition.clone());
+                show_saved(&status, &text, result);
+            }
+        }));
+
+        let row = gtk::Box::new(gtk::Orientation::Horizontal, 5);
+        row.pack_start(&kind_menu, false, false, 5);
+        row.pack_start(&fields.borrow().widget(), false, false, 5);
+        row.pack_start(&add_button, false, false, 5);
+        row
+    }
+
+    fn interval_c(&self, form: &Rc<RefCell<GoalForm>>) -> gtk::Widget {
+        let current = format!("{:?}", form.borrow().interval);
+        let daily = self.text.interval(Interval::Day);
+        let weekly = self.text.interval(Interval::Week);
+        let monthly = self.text.interval(Interval::Month);
+        dropmenu_c(
+            MenuOptions(vec![
+                ("Day", &daily),
+                ("Week", &weekly),
+                ("Month", &monthly),
+            ]),
+            &current,
+            Box::new(enclose!(form => move |id| {
+                form.borrow_mut().interval = match id {
+                    "Day" => Interval::Day,
+                    "Month" => Interval::Month,
+                    _ => Interval::Week,
+                };
+            })),
+        )
+    }
+
+    fn mass_entry_c(
+        &self,
+        weight: Option<si::Kilogram<f64>>,
+        on_update: Box<dyn Fn(Option<si::Kilogram<f64>>)>,
+    ) -> gtk::Widget {
+        let units = self.units;
+        validated_text_entry_c(
+            weight,
+            Box::new(move |weight| {
+                weight
+                    .map(|weight| units.render_mass(weight))
+                    .unwrap_or_default()
+            }),
+            Box::new(move |s| units.parse_mass(s).map(Some)),
+            on_update,
+        )
+    }
+}
+
+impl Component for Goals {
+    fn widget(&self) -> gtk::Widget {
+        self.widget.clone().upcast::<gtk::Widget>()
+    }
+}
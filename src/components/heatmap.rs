@@ -0,0 +1,247 @@
+use chrono::Datelike;
+use emseries::UniqueId;
+use fitnesstrax_lib::TraxRecord;
+use gtk::prelude::*;
+use std::cell::Cell;
+use std::rc::Rc;
+use std::sync::{Arc, RwLock};
+
+use crate::components::basics::{dropmenu_c, MenuOptions};
+use crate::context::Application;
+use crate::i18n::Text;
+use crate::range::{dates_in_range, group_by_date, Range, TimezoneDisplay};
+use crate::types::DateRange;
+
+const CELL: f64 = 12.;
+const GAP: f64 = 2.;
+const MARGIN_LEFT: f64 = 30.;
+const MARGIN_TOP: f64 = 15.;
+
+/// The colours for each level of activity, from no workouts up to the busiest days.
+const LEVELS: [(f64, f64, f64); 5] = [
+    (0.92, 0.93, 0.94),
+    (0.61, 0.91, 0.66),
+    (0.25, 0.77, 0.39),
+    (0.19, 0.63, 0.31),
+    (0.13, 0.43, 0.22),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Measure {
+    Duration,
+    Distance,
+}
+
+struct Day {
+    date: chrono::Date<chrono_tz::Tz>,
+    duration: f64,
+    distance: f64,
+}
+
+impl Day {
+    fn value(&self, measure: Measure) -> f64 {
+        match measure {
+            Measure::Duration => self.duration,
+            Measure::Distance => self.distance,
+        }
+    }
+}
+
+fn daily_totals(
+    range: &DateRange,
+    records: Vec<(UniqueId, TraxRecord)>,
+    timezone_display: TimezoneDisplay,
+) -> Vec<Day> {
+    let groups = group_by_date(range, records, timezone_display);
+    dates_in_range(range)
+        .into_iter()
+        .map(|date| {
+            let mut day = Day {
+                date,
+                duration: 0.,
+                distance: 0.,
+            };
+            for (_, record) in groups.get(&date).into_iter().flatten() {
+                if let TraxRecord::TimeDistance(ref rec) = record {
+                    day.duration += rec.duration.map(|d| d.value_unsafe).unwrap_or(0.);
+                    day.distance += rec.distance.map(|d| d.value_unsafe).unwrap_or(0.);
+                }
+            }
+            day
+        })
+        .collect()
+}
+
+/// Split the activity into four levels, relative to the busiest day.
+fn level(value: f64, max: f64) -> usize {
+    if value <= 0. || max <= 0. {
+        0
+    } else {
+        ((value / max * 4.).ceil() as usize).clamp(1, 4)
+    }
+}
+
+/// The column and row of each day, with weeks as columns that start on Monday.
+fn cell(days: &[Day], idx: usize) -> (usize, usize) {
+    let offset = days
+        .first()
+        .map(|day| day.date.weekday().num_days_from_monday() as usize)
+        .unwrap_or(0);
+    ((idx + offset) / 7, (idx + offset) % 7)
+}
+
+fn cell_at(days: &[Day], x: f64, y: f64) -> Option<usize> {
+    if x < MARGIN_LEFT || y < MARGIN_TOP {
+        return None;
+    }
+    let column = ((x - MARGIN_LEFT) / (CELL + GAP)) as usize;
+    let row = ((y - MARGIN_TOP) / (CELL + GAP)) as usize;
+    if row > 6 {
+        return None;
+    }
+    (0..days.len()).find(|idx| cell(days, *idx) == (column, row))
+}
+
+/// The names of the weekdays along the side of the calendar, and of the months along the top.
+struct Labels {
+    weekdays: Vec<(usize, String)>,
+    months: Vec<(usize, String)>,
+}
+
+impl Labels {
+    fn new(days: &[Day], text: &Text) -> Labels {
+        Labels {
+            weekdays: vec![
+                (0, text.weekday_abbreviation(chrono::Weekday::Mon)),
+                (2, text.weekday_abbreviation(chrono::Weekday::Wed)),
+                (4, text.weekday_abbreviation(chrono::Weekday::Fri)),
+            ],
+            months: days
+                .iter()
+                .enumerate()
+                .filter(|(_, day)| day.date.day() == 1)
+                .map(|(idx, day)| (idx, text.month_abbreviation(&day.date)))
+                .collect(),
+        }
+    }
+}
+
+fn draw(
+    cr: &cairo::Context,
+    days: &[Day],
+    labels: &Labels,
+    measure: Measure,
+    selected: &DateRange,
+) {
+    let max = days.iter().map(|day| day.value(measure)).fold(0., f64::max);
+    let position = |idx: usize| {
+        let (column, row) = cell(days, idx);
+        (
+            MARGIN_LEFT + column as f64 * (CELL + GAP),
+            MARGIN_TOP + row as f64 * (CELL + GAP),
+        )
+    };
+
+    cr.set_font_size(9.);
+    cr.set_source_rgb(0.4, 0.4, 0.4);
+    for (row, weekday) in &labels.weekdays {
+        cr.move_to(0., MARGIN_TOP + *row as f64 * (CELL + GAP) + CELL - 2.);
+        cr.show_text(weekday);
+    }
+    for (idx, month) in &labels.months {
+        let (x, _) = position(*idx);
+        cr.move_to(x, MARGIN_TOP - 4.);
+        cr.show_text(month);
+    }
+
+    for (idx, day) in days.iter().enumerate() {
+        let (x, y) = position(idx);
+        let (r, g, b) = LEVELS[level(day.value(measure), max)];
+        cr.set_source_rgb(r, g, b);
+        cr.rectangle(x, y, CELL, CELL);
+        cr.fill();
+
+        if day.date >= selected.start && day.date <= selected.end {
+            cr.set_source_rgb(0.2, 0.2, 0.2);
+            cr.set_line_width(1.);
+            cr.rectangle(x + 0.5, y + 0.5, CELL - 1., CELL - 1.);
+            cr.stroke();
+        }
+    }
+}
+
+/// A calendar of the workouts in `range`, with one cell per day that is coloured by the total
+/// duration or distance of that day's time/distance workouts. Clicking on a day selects it in the
+/// history.
+pub fn heatmap_c(
+    ctx: Arc<RwLock<Application>>,
+    range: &DateRange,
+    records: Vec<(UniqueId, TraxRecord)>,
+    timezone_display: TimezoneDisplay,
+    selected: &DateRange,
+    text: &Text,
+) -> gtk::Box {
+    let container = gtk::Box::new(gtk::Orientation::Vertical, 5);
+    let days = Rc::new(daily_totals(range, records, timezone_display));
+    let labels = Labels::new(&days, text);
+    let measure = Rc::new(Cell::new(Measure::Duration));
+    let selected = selected.clone();
+
+    let weeks = days.len().saturating_sub(1) / 7 + 2;
+    let area = gtk::DrawingArea::new();
+    area.set_size_request(
+        (MARGIN_LEFT + weeks as f64 * (CELL + GAP)) as i32,
+        (MARGIN_TOP + 7. * (CELL + GAP)) as i32,
+    );
+    area.connect_draw(enclose!(days, measure => move |_, cr| {
+        draw(cr, &days, &labels, measure.get(), &selected);
+        gtk::Inhibit(false)
+    }));
+
+    let events = gtk::EventBox::new();
+    events.add(&area);
+    events.connect_button_press_event(enclose!(days => move |_, event| {
+        let (x, y) = event.get_position();
+        if let Some(idx) = cell_at(&days, x, y) {
+            let date = days[idx].date;
+            ctx.write().unwrap().set_range(Range::new(date, date)).unwrap();
+        }
+        gtk::Inhibit(true)
+    }));
+
+    let menu = dropmenu_c(
+        MenuOptions(vec![
+            ("duration", &text.by_duration()),
+            ("distance", &text.by_distance()),
+        ]),
+        "duration",
+        Box::new(enclose!(area => move |id| {
+            measure.set(if id == "distance" {
+                Measure::Distance
+            } else {
+                Measure::Duration
+            });
+            area.queue_draw();
+        })),
+    );
+
+    container.pack_start(&menu, false, false, 5);
+    container.pack_start(&events, false, false, 5);
+    container
+}
+
+#[cfg(test)]
+mod test {
+    use super::level;
+
+    #[test]
+    fn it_splits_activity_into_levels() {
+        assert_eq!(level(0., 100.), 0);
+        assert_eq!(level(1., 100.), 1);
+        assert_eq!(level(25., 100.), 1);
+        assert_eq!(level(26., 100.), 2);
+        assert_eq!(level(75., 100.), 3);
+        assert_eq!(level(100., 100.), 4);
+        assert_eq!(level(10., 0.), 0);
+    }
+}
@@ -1,6 +1,8 @@
 use chrono::Date;
 use chrono_tz::Tz;
 use emseries::UniqueId;
+use fitnesstrax_lib::activity::ActivityRegistry;
+use fitnesstrax_lib::personal_records::PersonalRecords;
 use fitnesstrax_lib::TraxRecord;
 use gtk::prelude::*;
 use std::sync::{Arc, RwLock};
@@ -8,7 +10,7 @@ use std::sync::{Arc, RwLock};
 use crate::components::{Component, Day, RangeSelector};
 use crate::context::Application;
 use crate::i18n::{Text, UnitSystem};
-use crate::range::group_by_date;
+use crate::range::{group_by_date, TimezoneDisplay};
 use crate::settings::Settings;
 use crate::types::DateRange;
 
@@ -16,22 +18,28 @@ use crate::types::DateRange;
 pub struct History {
     widget: gtk::Box,
     history_box: gtk::Box,
+    status: gtk::Label,
 
     range: DateRange,
     records: Vec<(UniqueId, TraxRecord)>,
+    personal_records: PersonalRecords,
+    activities: ActivityRegistry,
     text: Text,
     timezone: chrono_tz::Tz,
+    timezone_display: TimezoneDisplay,
     units: UnitSystem,
 
     ctx: Arc<RwLock<Application>>,
 }
 
 impl History {
+    /// The page stays empty until it gets records with `set_records`.
     pub fn new(
         range: DateRange,
-        records: Vec<(UniqueId, TraxRecord)>,
+        activities: &ActivityRegistry,
         text: &Text,
         timezone: &chrono_tz::Tz,
+        timezone_display: TimezoneDisplay,
         units: &UnitSystem,
         ctx: Arc<RwLock<Application>>,
     ) -> History {
@@ -49,39 +57,64 @@ impl History {
         let scrolling_history = gtk::ScrolledWindow::new(no_adjustment, no_adjustment);
         scrolling_history.add(&history_box);
 
+        let status = gtk::Label::new(None);
+        status.set_selectable(true);
+        let main_column = gtk::Box::new(gtk::Orientation::Vertical, 5);
+        main_column.pack_start(&status, false, false, 5);
+        main_column.pack_start(&scrolling_history, true, true, 5);
+
         widget.pack_start(&range_bar.widget, false, false, 25);
-        widget.pack_start(&scrolling_history, true, true, 5);
+        widget.pack_start(&main_column, true, true, 5);
 
         widget.show();
         history_box.show_all();
+        main_column.show();
         scrolling_history.show();
         range_bar.show();
 
-        let mut component = History {
+        History {
             widget,
             history_box,
+            status,
 
             range,
-            records,
+            records: Vec::new(),
+            personal_records: PersonalRecords::default(),
+            activities: activities.clone(),
             text: text.clone(),
             timezone: timezone.clone(),
+            timezone_display,
             units: units.clone(),
 
             ctx,
-        };
-
-        component.render();
-
-        component
+        }
     }
 
-    pub fn set_range(&mut self, range: DateRange) {
+    /// Show the records of a range, along with the personal records which they set. These come in
+    /// together so that the page renders once for each change, and never has to go back to the
+    /// application while it renders.
+    pub fn set_records(
+        &mut self,
+        range: DateRange,
+        records: Vec<(UniqueId, TraxRecord)>,
+        personal_records: PersonalRecords,
+    ) {
         self.range = range;
+        self.records = records;
+        self.personal_records = personal_records;
+        self.set_status("");
         self.render();
     }
 
-    pub fn set_records(&mut self, records: Vec<(UniqueId, TraxRecord)>) {
-        self.records = records;
+    /// Show a message above the days, such as why a change could not be saved. An empty message
+    /// hides it.
+    pub fn set_status(&mut self, status: &str) {
+        self.status.set_text(status);
+        self.status.set_visible(!status.is_empty());
+    }
+
+    pub fn set_activities(&mut self, activities: ActivityRegistry) {
+        self.activities = activities;
         self.render();
     }
 
@@ -95,24 +128,38 @@ impl History {
         self.render();
     }
 
+    pub fn set_timezone_display(&mut self, timezone_display: TimezoneDisplay) {
+        self.timezone_display = timezone_display;
+        self.render();
+    }
+
     pub fn set_units(&mut self, units: UnitSystem) {
         self.units = units;
         self.render();
     }
 
     fn render(&mut self) {
-        let grouped_history = group_by_date(&self.range, self.records.clone());
+        let grouped_history =
+            group_by_date(&self.range, self.records.clone(), self.timezone_display);
         self.history_box.foreach(|child| child.destroy());
         let mut dates = grouped_history.keys().collect::<Vec<&Date<Tz>>>();
         dates.sort_unstable();
         dates.reverse();
         dates.iter().for_each(|date| {
             let ctx = self.ctx.clone();
+            let records = grouped_history.get(date).unwrap().clone();
+            let day_records = records
+                .iter()
+                .flat_map(|(id, _)| self.personal_records.set_by(id).to_vec())
+                .collect();
             let day = Day::new(
                 ctx,
                 *date.clone(),
-                grouped_history.get(date).unwrap().clone(),
+                records,
+                day_records,
                 self.timezone.clone(),
+                self.timezone_display,
+                self.activities.clone(),
                 self.text.clone(),
                 self.units.clone(),
             );
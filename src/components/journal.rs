@@ -0,0 +1,36 @@
+use fitnesstrax_lib::comments::Comments;
+use gtk::prelude::*;
+
+use crate::i18n::Text;
+
+pub fn journal_c(record: &Comments) -> gtk::Label {
+    let label = gtk::Label::new(Some(record.text()));
+    label.set_line_wrap(true);
+    label.set_xalign(0.0);
+    label.set_selectable(true);
+    label
+}
+
+pub fn journal_edit_c(current: &str, text: &Text, on_update: Box<dyn Fn(String)>) -> gtk::Box {
+    let container = gtk::Box::new(gtk::Orientation::Vertical, 5);
+
+    let text_view = gtk::TextView::new();
+    text_view.set_wrap_mode(gtk::WrapMode::Word);
+    text_view.set_size_request(-1, 100);
+
+    if let Some(buffer) = text_view.get_buffer() {
+        buffer.set_text(current);
+        buffer.connect_changed(move |buffer| {
+            let (start, end) = buffer.get_bounds();
+            if let Some(val) = buffer.get_text(&start, &end, false) {
+                on_update(String::from(val.as_str()));
+            }
+        });
+    }
+
+    let frame = gtk::Frame::new(Some(&text.journal()));
+    frame.add(&text_view);
+    container.pack_start(&frame, true, true, 5);
+
+    container
+}
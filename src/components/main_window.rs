@@ -1,4 +1,9 @@
-use crate::context::{Application, Message, State};
+use crate::context::{Application, Calendar, Message, State};
+use crate::range::TimezoneDisplay;
+use crate::types::DateRange;
+use emseries::UniqueId;
+use fitnesstrax_lib::personal_records::PersonalRecords;
+use fitnesstrax_lib::TraxRecord;
 use gtk::prelude::*;
 use std::sync::{Arc, RwLock};
 
@@ -8,6 +13,8 @@ pub struct MainWindow {
     notebook: gtk::Notebook,
     history_idx: Option<u32>,
     history_page: Option<Page<History>>,
+    statistics_page: Option<Page<Statistics>>,
+    goals_page: Option<Page<Goals>>,
     about_page: Page<About>,
     settings_page: Page<Settings>,
     ctx: Arc<RwLock<Application>>,
@@ -35,17 +42,57 @@ impl MainWindow {
         let history_page = match state {
             State::Unconfigured(_) => None,
             State::Configured(state) => {
-                let history = History::new(
+                let mut history = History::new(
                     state.range(),
-                    state.get_history().unwrap(),
+                    state.activities(),
                     state.text(),
                     state.timezone(),
+                    state.timezone_display(),
                     state.units(),
                     ctx.clone(),
                 );
+                history.set_records(
+                    state.range(),
+                    state.get_history().unwrap(),
+                    state.personal_records().unwrap_or_default(),
+                );
                 Some(Page::new(&state.text().history(), history))
             }
         };
+        let statistics_page = match state {
+            State::Unconfigured(_) => None,
+            State::Configured(state) => {
+                let mut statistics =
+                    Statistics::new(state.activities(), state.text(), state.units(), ctx.clone());
+                statistics.set_records(
+                    state.range(),
+                    state.get_history().unwrap(),
+                    state.calendar().unwrap(),
+                    state.timezone_display(),
+                );
+                Some(Page::new(&state.text().statistics(), statistics))
+            }
+        };
+        let goals_page = match state {
+            State::Unconfigured(_) => None,
+            State::Configured(state) => {
+                let goals = Goals::new(
+                    state.goal_progress().unwrap(),
+                    state.streaks().unwrap(),
+                    state.activities(),
+                    state.text(),
+                    state.units(),
+                    ctx.clone(),
+                );
+                Some(Page::new(&state.text().goals(), goals))
+            }
+        };
+        if let Some(ref page) = goals_page {
+            notebook.prepend_page(&page.component.widget(), Some(&page.label));
+        }
+        if let Some(ref page) = statistics_page {
+            notebook.prepend_page(&page.component.widget(), Some(&page.label));
+        }
         let history_idx = match history_page {
             Some(ref page) => {
                 Some(notebook.prepend_page(&page.component.widget(), Some(&page.label)))
@@ -61,12 +108,23 @@ impl MainWindow {
             notebook,
             history_idx,
             history_page: history_page,
+            statistics_page,
+            goals_page,
             about_page,
             settings_page,
             ctx: ctx.clone(),
         };
         self_.select_history_page();
 
+        let first_run = match state {
+            State::Unconfigured(_) => true,
+            State::Configured(_) => false,
+        };
+        drop(ctx_);
+        if first_run {
+            onboarding_c(ctx, &widget);
+        }
+
         self_
     }
 
@@ -75,18 +133,66 @@ impl MainWindow {
      */
     pub fn update_from(&mut self, message: Message) {
         match message {
-            Message::ChangeSeriesFile { range, records } => match self.history_page {
+            Message::ChangeSeriesFile {
+                range,
+                records,
+                activities,
+            } => match self.history_page {
                 None => {
+                    // The series may have come from the first-run assistant, along with new
+                    // settings.
+                    self.settings_page.component.reload();
+
                     let ctx_ = self.ctx.read().unwrap();
                     let state = ctx_.get_state();
-                    let history = History::new(
-                        range,
-                        records,
+                    if let State::Configured(ref configured) = state {
+                        let goals = Goals::new(
+                            configured.goal_progress().unwrap(),
+                            configured.streaks().unwrap(),
+                            &activities,
+                            state.text(),
+                            state.units(),
+                            self.ctx.clone(),
+                        );
+                        let goals_page = Page::new(&state.text().goals(), goals);
+                        self.notebook
+                            .prepend_page(&goals_page.component.widget(), Some(&goals_page.label));
+                        self.goals_page = Some(goals_page);
+                    }
+
+                    let mut statistics =
+                        Statistics::new(&activities, state.text(), state.units(), self.ctx.clone());
+                    if let State::Configured(ref configured) = state {
+                        statistics.set_records(
+                            range.clone(),
+                            records.clone(),
+                            configured.calendar().unwrap(),
+                            state.timezone_display(),
+                        );
+                    }
+                    let statistics_page = Page::new(&state.text().statistics(), statistics);
+                    self.notebook.prepend_page(
+                        &statistics_page.component.widget(),
+                        Some(&statistics_page.label),
+                    );
+                    self.statistics_page = Some(statistics_page);
+
+                    let mut history = History::new(
+                        range.clone(),
+                        &activities,
                         state.text(),
                         state.timezone(),
+                        state.timezone_display(),
                         state.units(),
                         self.ctx.clone(),
                     );
+                    if let State::Configured(ref configured) = state {
+                        history.set_records(
+                            range,
+                            records,
+                            configured.personal_records().unwrap_or_default(),
+                        );
+                    }
                     let history_page = Page::new(&state.text().history(), history);
                     self.history_idx =
                         Some(self.notebook.prepend_page(
@@ -96,21 +202,57 @@ impl MainWindow {
                     self.history_page = Some(history_page);
                 }
                 Some(ref mut page) => {
-                    page.component.set_range(range);
-                    page.component.set_records(records);
+                    if let Some(ref mut goals_page) = self.goals_page {
+                        goals_page.component.set_activities(activities.clone());
+                    }
+                    let data = page_data(&self.ctx);
+                    if let Some(ref mut statistics_page) = self.statistics_page {
+                        statistics_page.component.set_activities(activities.clone());
+                        if let Some(ref data) = data {
+                            statistics_page.component.set_records(
+                                range.clone(),
+                                records.clone(),
+                                data.calendar.clone(),
+                                data.timezone_display,
+                            );
+                        }
+                    }
+                    page.component.set_activities(activities);
+                    if let Some(data) = data {
+                        page.component
+                            .set_records(range, records, data.personal_records);
+                    }
                 }
             },
-            Message::ChangeRange { range, records } => {
+            Message::ChangeActivities(activities) => {
+                if let Some(ref mut page) = self.goals_page {
+                    page.component.set_activities(activities.clone());
+                }
+                if let Some(ref mut page) = self.statistics_page {
+                    page.component.set_activities(activities.clone());
+                }
                 if let Some(ref mut page) = self.history_page {
-                    page.component.set_range(range);
-                    page.component.set_records(records);
-                };
+                    page.component.set_activities(activities);
+                }
+            }
+            Message::ChangeRange { range, records } => {
+                if let Some(data) = page_data(&self.ctx) {
+                    self.set_records(range, records, data);
+                }
             }
             Message::ChangeLanguage(ref text) => {
                 if let Some(ref mut page) = self.history_page {
                     page.set_label(&text.history());
                     page.component.set_language(text.clone());
                 }
+                if let Some(ref mut page) = self.statistics_page {
+                    page.set_label(&text.statistics());
+                    page.component.set_language(text.clone());
+                }
+                if let Some(ref mut page) = self.goals_page {
+                    page.set_label(&text.goals());
+                    page.component.set_language(text.clone());
+                }
                 self.about_page.set_label(&text.about());
                 self.about_page.component.set_language(text);
                 self.settings_page.set_label(&text.preferences());
@@ -119,17 +261,88 @@ impl MainWindow {
                 self.history_page
                     .as_mut()
                     .map(|page| page.component.set_timezone(timezone));
+                self.reload_records();
+            }
+            Message::ChangeTimezoneDisplay(timezone_display) => {
+                if let Some(ref mut page) = self.history_page {
+                    page.component.set_timezone_display(timezone_display);
+                }
+                self.reload_records();
             }
             Message::ChangeUnits(units) => {
+                if let Some(ref mut page) = self.goals_page {
+                    page.component.set_units(units);
+                }
+                if let Some(ref mut page) = self.statistics_page {
+                    page.component.set_units(units);
+                }
                 self.history_page
                     .as_mut()
                     .map(|page| page.component.set_units(units));
             }
-            Message::RecordsUpdated(records) => {
-                self.history_page
-                    .as_mut()
-                    .map(|page| page.component.set_records(records));
+            Message::GoalsUpdated(goals) => {
+                if let Some(ref mut page) = self.goals_page {
+                    page.component.set_goals(goals);
+                }
+            }
+            Message::StreaksUpdated(streaks) => {
+                if let Some(ref mut page) = self.goals_page {
+                    page.component.set_streaks(streaks);
+                }
+            }
+            Message::ImportFinished(summary) => {
+                self.settings_page.component.set_import_status(&summary);
             }
+            Message::RecordsNotSaved(reason) => {
+                if let Some(ref mut page) = self.history_page {
+                    page.component.set_status(&reason);
+                }
+            }
+            Message::RecordsUpdated(records) => self.update_records(records),
+        }
+    }
+
+    fn update_records(&mut self, records: Vec<(UniqueId, TraxRecord)>) {
+        if let Some(ref mut page) = self.goals_page {
+            if let State::Configured(ref state) = self.ctx.read().unwrap().get_state() {
+                page.component.set_goals(state.goal_progress().unwrap());
+                page.component.set_streaks(state.streaks().unwrap());
+            }
+        }
+        if let Some(data) = page_data(&self.ctx) {
+            self.set_records(data.range.clone(), records, data);
+        }
+    }
+
+    /// Read the records of the range again, for changes to the settings that move records onto
+    /// other days.
+    fn reload_records(&mut self) {
+        let records = match self.ctx.read().unwrap().get_state() {
+            State::Configured(ref state) => state.get_history(),
+            State::Unconfigured(_) => return,
+        };
+        if let Ok(records) = records {
+            self.update_records(records);
+        }
+    }
+
+    fn set_records(
+        &mut self,
+        range: DateRange,
+        records: Vec<(UniqueId, TraxRecord)>,
+        data: PageData,
+    ) {
+        if let Some(ref mut page) = self.statistics_page {
+            page.component.set_records(
+                range.clone(),
+                records.clone(),
+                data.calendar,
+                data.timezone_display,
+            );
+        }
+        if let Some(ref mut page) = self.history_page {
+            page.component
+                .set_records(range, records, data.personal_records);
         }
     }
 
@@ -161,3 +374,25 @@ impl<T: Component> Page<T> {
         self.label.set_markup(label_text);
     }
 }
+
+/// What the Statistics and History pages show besides the records of the selected range. The
+/// window reads this once for each message, so that the pages never have to read the application
+/// while they render, at a time when the window may already hold it.
+struct PageData {
+    range: DateRange,
+    calendar: Calendar,
+    timezone_display: TimezoneDisplay,
+    personal_records: PersonalRecords,
+}
+
+fn page_data(ctx: &Arc<RwLock<Application>>) -> Option<PageData> {
+    match ctx.read().unwrap().get_state() {
+        State::Configured(ref state) => Some(PageData {
+            range: state.range(),
+            calendar: state.calendar().ok()?,
+            timezone_display: state.timezone_display(),
+            personal_records: state.personal_records().unwrap_or_default(),
+        }),
+        State::Unconfigured(_) => None,
+    }
+}
@@ -19,28 +19,43 @@ macro_rules! enclose {
 use gtk::prelude::*;
 
 mod about;
+mod activity;
 mod basics;
 mod date_selector;
 mod day;
+mod goals;
+mod heatmap;
 mod history;
+mod journal;
 mod main_window;
+mod onboarding;
 mod range_selector;
+mod record_list_edit;
 mod rep_duration;
 mod set_rep;
+mod sets_edit;
 mod settings;
+mod statistics;
 mod steps;
 mod time_distance;
 mod time_distance_row;
 mod weight;
+mod weight_chart;
+mod weighted_set;
+mod workout_import;
 
 pub use about::About;
 pub use basics::*;
 pub use date_selector::date_selector_c;
 pub use day::Day;
+pub use goals::Goals;
 pub use history::History;
 pub use main_window::MainWindow;
+pub use onboarding::onboarding_c;
 pub use range_selector::RangeSelector;
 pub use settings::Settings;
+pub use statistics::Statistics;
+pub use workout_import::workout_preview_c;
 
 pub trait Component {
     fn widget(&self) -> gtk::Widget;
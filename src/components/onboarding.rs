@@ -0,0 +1,338 @@
+use chrono_tz::Tz;
+use dimensioned::si::Kilogram;
+use gtk::prelude::*;
+use std::cell::{Cell, RefCell};
+use std::convert::TryFrom;
+use std::rc::Rc;
+use std::sync::{Arc, RwLock};
+
+use crate::components::settings::{choose_new_database, LANGUAGES, UNIT_PRESETS};
+use crate::components::{
+    dropmenu_c, labeled_widget_c, timezone_selector_c, validated_text_entry_c, Component,
+    Container, MenuOptions,
+};
+use crate::config::Configuration;
+use crate::context::{Application, SeriesChoice};
+use crate::errors::Error;
+use crate::i18n::{Text, UnitSystem};
+use crate::range::TimezoneDisplay;
+use crate::settings::Settings;
+
+/// Everything chosen so far in the assistant. None of it takes effect until the last page.
+#[derive(Clone)]
+struct Choices {
+    series: Option<SeriesChoice>,
+    language: String,
+    timezone: Tz,
+    timezone_display: TimezoneDisplay,
+    units: UnitSystem,
+    weight: Option<Kilogram<f64>>,
+}
+
+/// The first-run assistant, which sets up a series and the preferences and then leaves the
+/// application configured. Cancelling it leaves the application unconfigured, and the settings
+/// page can still open or create a series.
+pub fn onboarding_c(
+    ctx: Arc<RwLock<Application>>,
+    parent: &gtk::ApplicationWindow,
+) -> gtk::Assistant {
+    let (settings, series_error) = {
+        let ctx = ctx.read().unwrap();
+        let state = ctx.get_state();
+        // Settings only mean something once a series has been chosen. Before then, the system
+        // locale and timezone are better guesses than the defaults.
+        let settings = match state.series_path() {
+            None => Settings::from_config(&Configuration::from_system()),
+            Some(_) => state.settings().clone(),
+        };
+        let series_error = state
+            .series_error()
+            .map(|err| settings.text.series_error(err));
+        (settings, series_error)
+    };
+    let text = settings.text.clone();
+    let choices = Rc::new(RefCell::new(Choices {
+        series: None,
+        language: settings.text.language_id().get_language().to_owned(),
+        timezone: settings.timezone,
+        timezone_display: settings.timezone_display,
+        units: settings.units,
+        weight: None,
+    }));
+
+    let assistant = gtk::Assistant::new();
+    assistant.set_title(&text.setup());
+    assistant.set_transient_for(Some(parent));
+    assistant.set_modal(true);
+    assistant.set_default_size(500, 350);
+
+    let intro = gtk::Label::new(Some(&text.welcome_intro()));
+    intro.set_line_wrap(true);
+    assistant.append_page(&intro);
+    assistant.set_page_title(&intro, &text.welcome());
+    assistant.set_page_type(&intro, gtk::AssistantPageType::Intro);
+    assistant.set_page_complete(&intro, true);
+
+    // The series from the last run could not be opened, and the database page says why.
+    let status = gtk::Label::new(series_error.as_deref());
+    status.set_selectable(true);
+    let database = database_page(&text, &assistant, choices.clone(), &status);
+    let database_idx = assistant.append_page(&database);
+    assistant.set_page_title(&database, &text.database_path());
+
+    let preferences = preferences_page(&text, choices.clone());
+    assistant.append_page(&preferences);
+    assistant.set_page_title(&preferences, &text.preferences());
+    assistant.set_page_complete(&preferences, true);
+
+    // The weight and summary pages depend on the earlier choices, so they get filled in as they
+    // are shown.
+    let weight = Container::new(None::<gtk::Widget>);
+    assistant.append_page(&weight.widget);
+    assistant.set_page_title(&weight.widget, &text.starting_weight());
+    assistant.set_page_complete(&weight.widget, true);
+
+    let summary = Container::new(None::<gtk::Widget>);
+    assistant.append_page(&summary.widget);
+    assistant.set_page_title(&summary.widget, &text.ready());
+    assistant.set_page_type(&summary.widget, gtk::AssistantPageType::Confirm);
+    assistant.set_page_complete(&summary.widget, true);
+
+    assistant.connect_prepare(enclose!(text, choices => move |_, page| {
+        if *page == weight.widget() {
+            weight
+                .clone()
+                .swap(Some(weight_form(&text, choices.clone())));
+        } else if *page == summary.widget() {
+            summary.clone().swap(Some(summary_c(&text, &choices.borrow())));
+        }
+    }));
+
+    let configured = Rc::new(Cell::new(false));
+    assistant.connect_apply(enclose!(configured => move |_| {
+        let choices = choices.borrow().clone();
+        let series = match choices.series {
+            Some(series) => series,
+            None => return,
+        };
+        let settings = Settings::new(
+            choices.language.parse().expect("menu languages should always parse"),
+            choices.units,
+            choices.timezone,
+            choices.timezone_display,
+        );
+        let result = ctx.write().unwrap().configure(series, settings, choices.weight);
+        match result {
+            Ok(()) => configured.set(true),
+            // The series is open, even though it will not be opened again on the next start.
+            Err(Error::SettingsNotSaved(_)) => configured.set(true),
+            Err(err) => status.set_text(&text.series_error(&err)),
+        }
+    }));
+    assistant.connect_close(move |assistant| {
+        if configured.get() {
+            assistant.destroy();
+        } else {
+            // The series could not be opened or created, and the reason is shown on the
+            // database page.
+            assistant.set_current_page(database_idx);
+        }
+    });
+    assistant.connect_cancel(|assistant| assistant.destroy());
+
+    assistant.show_all();
+    assistant
+}
+
+/// Open an existing database or choose where to create a new one. The page is complete once
+/// there is a database.
+fn database_page(
+    text: &Text,
+    assistant: &gtk::Assistant,
+    choices: Rc<RefCell<Choices>>,
+    status: &gtk::Label,
+) -> gtk::Box {
+    let page = gtk::Box::new(gtk::Orientation::Vertical, 5);
+    let explanation = gtk::Label::new(Some(&text.setup_database()));
+    explanation.set_line_wrap(true);
+    let chosen = gtk::Label::new(None);
+
+    let chooser = gtk::FileChooserButton::new(&text.open_database(), gtk::FileChooserAction::Open);
+    chooser.connect_file_set(
+        enclose!(assistant, page, choices, chosen, status => move |chooser| {
+            if let Some(path) = chooser.get_filename() {
+                chosen.set_text(&path.to_string_lossy());
+                status.set_text("");
+                choices.borrow_mut().series = Some(SeriesChoice::Open(path));
+                assistant.set_page_complete(&page, true);
+            }
+        }),
+    );
+
+    let new_button = gtk::Button::new_with_label(&text.new_database());
+    new_button.connect_clicked(
+        enclose!(text, assistant, page, chosen, status => move |button| {
+            if let Some(path) = choose_new_database(&text.new_database(), button) {
+                chosen.set_text(&path.to_string_lossy());
+                status.set_text("");
+                choices.borrow_mut().series = Some(SeriesChoice::Create(path));
+                assistant.set_page_complete(&page, true);
+            }
+        }),
+    );
+
+    let buttons = gtk::Box::new(gtk::Orientation::Horizontal, 5);
+    buttons.pack_start(
+        &labeled_widget_c(&text.open_database(), chooser.upcast::<gtk::Widget>()),
+        false,
+        false,
+        5,
+    );
+    buttons.pack_start(&new_button, false, false, 5);
+
+    page.pack_start(&explanation, false, false, 5);
+    page.pack_start(&buttons, false, false, 5);
+    page.pack_start(&chosen, false, false, 5);
+    page.pack_start(status, false, false, 5);
+    page
+}
+
+fn preferences_page(text: &Text, choices: Rc<RefCell<Choices>>) -> gtk::Box {
+    let page = gtk::Box::new(gtk::Orientation::Vertical, 5);
+    let current = choices.borrow().clone();
+
+    page.pack_start(
+        &labeled_widget_c(
+            &text.language(),
+            dropmenu_c(
+                MenuOptions(LANGUAGES.to_vec()),
+                &current.language,
+                Box::new(enclose!(choices => move |s| {
+                    choices.borrow_mut().language = s.to_owned();
+                })),
+            ),
+        ),
+        false,
+        false,
+        5,
+    );
+    page.pack_start(
+        &labeled_widget_c(
+            &text.timezone(),
+            timezone_selector_c(
+                &current.timezone,
+                Box::new(enclose!(choices => move |timezone| {
+                    choices.borrow_mut().timezone = timezone;
+                })),
+            ),
+        ),
+        false,
+        false,
+        5,
+    );
+    page.pack_start(
+        &labeled_widget_c(
+            &text.units(),
+            dropmenu_c(
+                MenuOptions(UNIT_PRESETS.to_vec()),
+                &String::from(&current.units),
+                Box::new(move |s| {
+                    if let Ok(units) = UnitSystem::try_from(s) {
+                        choices.borrow_mut().units = units;
+                    }
+                }),
+            ),
+        ),
+        false,
+        false,
+        5,
+    );
+    page
+}
+
+/// An optional starting weight, in the units chosen on the previous page. A blank entry means no
+/// weight.
+fn weight_form(text: &Text, choices: Rc<RefCell<Choices>>) -> gtk::Box {
+    let page = gtk::Box::new(gtk::Orientation::Vertical, 5);
+    let explanation = gtk::Label::new(Some(&text.setup_weight()));
+    explanation.set_line_wrap(true);
+
+    let (units, weight) = {
+        let choices = choices.borrow();
+        (choices.units, choices.weight)
+    };
+    let entry = validated_text_entry_c(
+        weight,
+        Box::new(move |weight| {
+            weight
+                .map(|weight| units.render_mass(weight))
+                .unwrap_or_default()
+        }),
+        Box::new(move |s| {
+            if s.trim().is_empty() {
+                Ok(None)
+            } else {
+                units.parse_mass(s).map(Some)
+            }
+        }),
+        Box::new(move |weight| choices.borrow_mut().weight = weight),
+    );
+
+    page.pack_start(&explanation, false, false, 5);
+    page.pack_start(
+        &labeled_widget_c(&text.starting_weight(), entry),
+        false,
+        false,
+        5,
+    );
+    page
+}
+
+fn summary_c(text: &Text, choices: &Choices) -> gtk::Box {
+    let page = gtk::Box::new(gtk::Orientation::Vertical, 5);
+    let label_for = |options: &[(&'static str, &'static str)], id: &str| {
+        options
+            .iter()
+            .find(|(option, _)| *option == id)
+            .map(|(_, label)| *label)
+            .unwrap_or("")
+            .to_owned()
+    };
+
+    let series = match choices.series {
+        Some(SeriesChoice::Open(ref path)) | Some(SeriesChoice::Create(ref path)) => {
+            path.to_string_lossy().into_owned()
+        }
+        None => String::new(),
+    };
+    let rows = vec![
+        (text.database_path(), series),
+        (text.language(), label_for(&LANGUAGES, &choices.language)),
+        (text.timezone(), choices.timezone.name().to_owned()),
+        (
+            text.units(),
+            label_for(&UNIT_PRESETS, &String::from(&choices.units)),
+        ),
+        (
+            text.starting_weight(),
+            choices
+                .weight
+                .map(|weight| choices.units.render_mass(weight))
+                .unwrap_or_default(),
+        ),
+    ];
+
+    page.pack_start(&gtk::Label::new(Some(&text.setup_ready())), false, false, 5);
+    for (label, value) in rows {
+        page.pack_start(
+            &labeled_widget_c(
+                &label,
+                gtk::Label::new(Some(&value)).upcast::<gtk::Widget>(),
+            ),
+            false,
+            false,
+            5,
+        );
+    }
+    page
+}
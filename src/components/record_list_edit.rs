@@ -0,0 +1,184 @@
+use emseries::{Recordable, UniqueId};
+use gtk::prelude::*;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+use crate::i18n::Text;
+use crate::range::TimezoneDisplay;
+
+/// The editor for a single record. The list puts the delete button at the end of the header.
+pub struct RecordEdit {
+    pub widget: gtk::Box,
+    pub header: gtk::Box,
+}
+
+impl From<gtk::Box> for RecordEdit {
+    fn from(row: gtk::Box) -> RecordEdit {
+        RecordEdit {
+            widget: row.clone(),
+            header: row,
+        }
+    }
+}
+
+/// Builds the editor for a record, given its id, the record, the timezone to show it in and the
+/// function to call with every change.
+pub type RecordEditor<R> =
+    Rc<dyn Fn(UniqueId, R, chrono_tz::Tz, Box<dyn Fn(UniqueId, R)>) -> RecordEdit>;
+
+/// The day editor's list of one kind of record. It keeps track of which records were changed,
+/// added and deleted until the day gets saved.
+#[derive(Clone)]
+pub struct RecordListEdit<R> {
+    pub widget: gtk::Box,
+    record_box: gtk::Box,
+
+    /// The records that already existed, in order of time.
+    records: Vec<(UniqueId, R)>,
+    timezone: chrono_tz::Tz,
+    timezone_display: TimezoneDisplay,
+    text: Text,
+    editor: RecordEditor<R>,
+    updated_records: Rc<RefCell<HashMap<UniqueId, R>>>,
+    /// New records stay in the order that they were added in.
+    new_records: Rc<RefCell<Vec<(UniqueId, R)>>>,
+    deleted_records: Rc<RefCell<HashSet<UniqueId>>>,
+}
+
+impl<R: Recordable + Clone + 'static> RecordListEdit<R> {
+    pub fn new(
+        records: Vec<(&UniqueId, &R)>,
+        timezone: chrono_tz::Tz,
+        timezone_display: TimezoneDisplay,
+        text: &Text,
+        add_label: &str,
+        new_record: Box<dyn Fn() -> R>,
+        editor: RecordEditor<R>,
+    ) -> RecordListEdit<R> {
+        let mut records: Vec<(UniqueId, R)> = records
+            .into_iter()
+            .map(|(id, record)| (id.clone(), record.clone()))
+            .collect();
+        records.sort_by_key(|(_, record)| record.timestamp());
+
+        let w = RecordListEdit {
+            widget: gtk::Box::new(gtk::Orientation::Vertical, 5),
+            record_box: gtk::Box::new(gtk::Orientation::Vertical, 5),
+
+            records,
+            timezone,
+            timezone_display,
+            text: text.clone(),
+            editor,
+            updated_records: Rc::new(RefCell::new(HashMap::new())),
+            new_records: Rc::new(RefCell::new(Vec::new())),
+            deleted_records: Rc::new(RefCell::new(HashSet::new())),
+        };
+
+        let button_box = gtk::Box::new(gtk::Orientation::Horizontal, 5);
+        let new_button = gtk::Button::new_with_label(add_label);
+        new_button.show();
+        button_box.pack_start(&new_button, false, false, 5);
+        new_button.connect_clicked(enclose!(w => move |_| {
+            w.new_records
+                .borrow_mut()
+                .push((UniqueId::new(), new_record()));
+            w.render();
+        }));
+
+        w.widget.pack_start(&w.record_box, false, false, 5);
+        w.widget.pack_start(&button_box, false, false, 5);
+
+        w.render();
+
+        w
+    }
+
+    pub fn render(&self) {
+        self.record_box.foreach(|child| child.destroy());
+
+        for (id, record) in self.records.iter() {
+            if self.deleted_records.borrow().contains(id) {
+                continue;
+            }
+            let record = self
+                .updated_records
+                .borrow()
+                .get(id)
+                .cloned()
+                .unwrap_or_else(|| record.clone());
+            let zone = self
+                .timezone_display
+                .zone(&record.timestamp(), &self.timezone);
+            let updated_records = self.updated_records.clone();
+            let row = (self.editor)(
+                id.clone(),
+                record,
+                zone,
+                Box::new(move |id, rec| {
+                    updated_records.borrow_mut().insert(id, rec);
+                }),
+            );
+            let w = self.clone();
+            let id = id.clone();
+            self.pack_row(
+                &row,
+                Box::new(move || {
+                    w.updated_records.borrow_mut().remove(&id);
+                    w.deleted_records.borrow_mut().insert(id.clone());
+                    w.render();
+                }),
+            );
+        }
+
+        for (id, record) in self.new_records.borrow().iter() {
+            let new_records = self.new_records.clone();
+            let row = (self.editor)(
+                id.clone(),
+                record.clone(),
+                self.timezone,
+                Box::new(move |id, rec| {
+                    let mut new_records = new_records.borrow_mut();
+                    if let Some(entry) = new_records.iter_mut().find(|(id_, _)| *id_ == id) {
+                        entry.1 = rec;
+                    }
+                }),
+            );
+            let w = self.clone();
+            let id = id.clone();
+            self.pack_row(
+                &row,
+                Box::new(move || {
+                    w.new_records.borrow_mut().retain(|(id_, _)| *id_ != id);
+                    w.render();
+                }),
+            );
+        }
+
+        self.record_box.show_all();
+    }
+
+    fn pack_row(&self, row: &RecordEdit, on_delete: Box<dyn Fn()>) {
+        let delete_button = gtk::Button::new_with_label(&self.text.delete());
+        delete_button.connect_clicked(move |_| on_delete());
+        row.header.pack_start(&delete_button, false, false, 5);
+        self.record_box.pack_start(&row.widget, false, false, 5);
+    }
+
+    pub fn updated_records(&self) -> Vec<(UniqueId, R)> {
+        self.updated_records
+            .borrow()
+            .iter()
+            .map(|(id, record)| (id.clone(), record.clone()))
+            .collect()
+    }
+
+    pub fn new_records(&self) -> Vec<(UniqueId, R)> {
+        self.new_records.borrow().clone()
+    }
+
+    pub fn deleted_records(&self) -> Vec<UniqueId> {
+        self.deleted_records.borrow().iter().cloned().collect()
+    }
+}
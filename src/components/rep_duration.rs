@@ -1,27 +1,118 @@
-use dimensioned::si::Second;
+use dimensioned::si::{Second, S};
+use emseries::{DateTimeTz, UniqueId};
+use fitnesstrax_lib::activity::{ActivityId, ActivityKind, ActivityRegistry};
+use fitnesstrax_lib::repduration::{ActivityType, RepDurationRecord};
 use gtk::prelude::*;
+use std::rc::Rc;
 
-use crate::settings::Settings;
-use fitnesstrax_lib;
+use crate::components::activity::activity_c;
+use crate::components::basics::duration_edit_c;
+use crate::components::record_list_edit::RecordListEdit;
+use crate::components::sets_edit::{sets_list_edit_c, SetsRecord};
+use crate::i18n::Text;
+use crate::range::TimezoneDisplay;
 
-fn activity_c(activity: &fitnesstrax_lib::repduration::ActivityType) -> gtk::Label {
-    gtk::Label::new(match activity {
-        fitnesstrax_lib::repduration::ActivityType::MartialArts => Some("MartialArts"),
-        fitnesstrax_lib::repduration::ActivityType::Planks => Some("Planks"),
-        fitnesstrax_lib::repduration::ActivityType::Yoga => Some("Yoga"),
-    })
-}
-
-fn sets_c(sets: &Vec<Second<f64>>) -> gtk::Label {
+fn sets_c(sets: &[Second<f64>]) -> gtk::Label {
     let set_strs: Vec<String> = sets.iter().map(|r| format!("{}", r)).collect();
     gtk::Label::new(Some(&set_strs.join(" ")))
 }
 
-pub fn rep_duration_c(record: &fitnesstrax_lib::repduration::RepDurationRecord) -> gtk::Box {
+pub fn rep_duration_c(
+    record: &fitnesstrax_lib::repduration::RepDurationRecord,
+    activities: &ActivityRegistry,
+    text: &Text,
+) -> gtk::Box {
     let container = gtk::Box::new(gtk::Orientation::Horizontal, 5);
 
-    container.add(&activity_c(&record.activity));
+    container.add(&activity_c(&record.activity, activities, text));
     container.add(&sets_c(&record.sets));
 
     container
 }
+
+fn set_edit_c(
+    duration: Second<f64>,
+    text: &Text,
+    on_update: Box<dyn Fn(Second<f64>)>,
+    on_remove: Box<dyn Fn()>,
+) -> gtk::Box {
+    let container = gtk::Box::new(gtk::Orientation::Horizontal, 5);
+
+    /* A set without a duration makes no sense, so clearing the field leaves the set unchanged. */
+    let duration_entry = duration_edit_c(
+        &Some(duration),
+        Box::new(move |val| {
+            if let Some(val) = val {
+                on_update(val)
+            }
+        }),
+    );
+
+    let remove_button = gtk::Button::new_with_label(&text.remove());
+    remove_button.connect_clicked(move |_| on_remove());
+
+    container.pack_start(&duration_entry, false, false, 5);
+    container.pack_start(&remove_button, false, false, 5);
+
+    container
+}
+
+impl SetsRecord for RepDurationRecord {
+    type Set = Second<f64>;
+
+    const KIND: ActivityKind = ActivityKind::RepDuration;
+
+    fn add_label(text: &Text) -> String {
+        text.add_rep_duration_workout()
+    }
+
+    fn new_record(timestamp: DateTimeTz) -> RepDurationRecord {
+        RepDurationRecord::new(timestamp, ActivityType::Planks.into(), vec![], None)
+    }
+
+    fn next_set(last: Option<&Second<f64>>) -> Second<f64> {
+        last.cloned().unwrap_or(60. * S)
+    }
+
+    fn activity(&self) -> &ActivityId {
+        &self.activity
+    }
+
+    fn set_activity(&mut self, activity: ActivityId) {
+        self.activity = activity;
+    }
+
+    fn set_timestamp(&mut self, timestamp: DateTimeTz) {
+        self.timestamp = timestamp;
+    }
+
+    fn sets(&self) -> &[Second<f64>] {
+        &self.sets
+    }
+
+    fn sets_mut(&mut self) -> &mut Vec<Second<f64>> {
+        &mut self.sets
+    }
+}
+
+pub fn rep_duration_edit_c(
+    date: chrono::Date<chrono_tz::Tz>,
+    records: Vec<(&UniqueId, &RepDurationRecord)>,
+    timezone: chrono_tz::Tz,
+    timezone_display: TimezoneDisplay,
+    activities: ActivityRegistry,
+    text: Text,
+) -> RecordListEdit<RepDurationRecord> {
+    let text_ = text.clone();
+    sets_list_edit_c(
+        date,
+        records,
+        timezone,
+        timezone_display,
+        activities,
+        text,
+        Rc::new(move |duration, on_update, on_remove| {
+            set_edit_c(*duration, &text_, on_update, on_remove)
+        }),
+    )
+}
@@ -1,25 +1,117 @@
+use emseries::{DateTimeTz, UniqueId};
+use fitnesstrax_lib::activity::{ActivityId, ActivityKind, ActivityRegistry};
+use fitnesstrax_lib::setrep::{ActivityType, SetRepRecord};
 use gtk::prelude::*;
+use std::rc::Rc;
 
-use fitnesstrax_lib;
+use crate::components::activity::activity_c;
+use crate::components::basics::{labeled_widget_c, validated_text_entry_c};
+use crate::components::record_list_edit::RecordListEdit;
+use crate::components::sets_edit::{sets_list_edit_c, SetsRecord};
+use crate::errors::Error;
+use crate::i18n::Text;
+use crate::range::TimezoneDisplay;
 
-fn activity_c(activity: &fitnesstrax_lib::setrep::ActivityType) -> gtk::Label {
-    gtk::Label::new(match activity {
-        fitnesstrax_lib::setrep::ActivityType::Pushups => Some("Pushups"),
-        fitnesstrax_lib::setrep::ActivityType::Situps => Some("Situps"),
-        fitnesstrax_lib::setrep::ActivityType::Squats => Some("Squats"),
-    })
-}
-
-fn sets_c(sets: &Vec<u32>) -> gtk::Label {
+fn sets_c(sets: &[u32]) -> gtk::Label {
     let set_strs: Vec<String> = sets.iter().map(|r| format!("{}", r)).collect();
     gtk::Label::new(Some(&set_strs.join(" ")))
 }
 
-pub fn set_rep_c(record: &fitnesstrax_lib::setrep::SetRepRecord) -> gtk::Box {
+pub fn set_rep_c(
+    record: &fitnesstrax_lib::setrep::SetRepRecord,
+    activities: &ActivityRegistry,
+    text: &Text,
+) -> gtk::Box {
     let container = gtk::Box::new(gtk::Orientation::Horizontal, 5);
 
-    container.add(&activity_c(&record.activity));
+    container.add(&activity_c(&record.activity, activities, text));
     container.add(&sets_c(&record.sets));
 
     container
 }
+
+fn set_edit_c(
+    reps: u32,
+    text: &Text,
+    on_update: Box<dyn Fn(u32)>,
+    on_remove: Box<dyn Fn()>,
+) -> gtk::Box {
+    let container = gtk::Box::new(gtk::Orientation::Horizontal, 5);
+
+    let reps_entry = validated_text_entry_c(
+        reps,
+        Box::new(|r| format!("{}", r)),
+        Box::new(|s| match s.parse::<u32>() {
+            Ok(v) if v > 0 => Ok(v),
+            _ => Err(Error::ParseRepsError),
+        }),
+        on_update,
+    );
+
+    let remove_button = gtk::Button::new_with_label(&text.remove());
+    remove_button.connect_clicked(move |_| on_remove());
+
+    container.pack_start(&labeled_widget_c(&text.reps(), reps_entry), false, false, 5);
+    container.pack_start(&remove_button, false, false, 5);
+
+    container
+}
+
+impl SetsRecord for SetRepRecord {
+    type Set = u32;
+
+    const KIND: ActivityKind = ActivityKind::SetRep;
+
+    fn add_label(text: &Text) -> String {
+        text.add_set_rep_workout()
+    }
+
+    fn new_record(timestamp: DateTimeTz) -> SetRepRecord {
+        SetRepRecord::new(timestamp, ActivityType::Pushups.into(), vec![], None)
+            .expect("an empty list of sets is always valid")
+    }
+
+    fn next_set(last: Option<&u32>) -> u32 {
+        last.cloned().unwrap_or(10)
+    }
+
+    fn activity(&self) -> &ActivityId {
+        &self.activity
+    }
+
+    fn set_activity(&mut self, activity: ActivityId) {
+        self.activity = activity;
+    }
+
+    fn set_timestamp(&mut self, timestamp: DateTimeTz) {
+        self.timestamp = timestamp;
+    }
+
+    fn sets(&self) -> &[u32] {
+        &self.sets
+    }
+
+    fn sets_mut(&mut self) -> &mut Vec<u32> {
+        &mut self.sets
+    }
+}
+
+pub fn set_rep_edit_c(
+    date: chrono::Date<chrono_tz::Tz>,
+    records: Vec<(&UniqueId, &SetRepRecord)>,
+    timezone: chrono_tz::Tz,
+    timezone_display: TimezoneDisplay,
+    activities: ActivityRegistry,
+    text: Text,
+) -> RecordListEdit<SetRepRecord> {
+    let text_ = text.clone();
+    sets_list_edit_c(
+        date,
+        records,
+        timezone,
+        timezone_display,
+        activities,
+        text,
+        Rc::new(move |reps, on_update, on_remove| set_edit_c(*reps, &text_, on_update, on_remove)),
+    )
+}
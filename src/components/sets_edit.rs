@@ -0,0 +1,205 @@
+use emseries::{DateTimeTz, Recordable, UniqueId};
+use fitnesstrax_lib::activity::{ActivityId, ActivityKind, ActivityRegistry};
+use gtk::prelude::*;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::components::activity::activity_edit_c;
+use crate::components::basics::time_edit_c;
+use crate::components::record_list_edit::{RecordEdit, RecordListEdit};
+use crate::i18n::Text;
+use crate::range::{day_start, set_time, TimezoneDisplay};
+
+/// A record of a workout that is made up of sets, such as pushups or squats.
+pub trait SetsRecord: Recordable + Clone + 'static {
+    type Set: Clone + 'static;
+
+    /// The kind of activity to offer in the activity menu.
+    const KIND: ActivityKind;
+
+    /// The label of the button that adds a new record.
+    fn add_label(text: &Text) -> String;
+
+    /// A new record, with no sets yet, for the workout button.
+    fn new_record(timestamp: DateTimeTz) -> Self;
+
+    /// The set that the add set button adds after `last`, or to a record with no sets yet.
+    fn next_set(last: Option<&Self::Set>) -> Self::Set;
+
+    fn activity(&self) -> &ActivityId;
+    fn set_activity(&mut self, activity: ActivityId);
+    fn set_timestamp(&mut self, timestamp: DateTimeTz);
+    fn sets(&self) -> &[Self::Set];
+    fn sets_mut(&mut self) -> &mut Vec<Self::Set>;
+}
+
+/// Builds the editor for a single set, given the set, the function to call when it changes and
+/// the function to call to remove it.
+pub type SetEditor<S> = Rc<dyn Fn(&S, Box<dyn Fn(S)>, Box<dyn Fn()>) -> gtk::Box>;
+
+/// The day editor's list of set-based records of one kind.
+pub fn sets_list_edit_c<R: SetsRecord>(
+    date: chrono::Date<chrono_tz::Tz>,
+    records: Vec<(&UniqueId, &R)>,
+    timezone: chrono_tz::Tz,
+    timezone_display: TimezoneDisplay,
+    activities: ActivityRegistry,
+    text: Text,
+    set_editor: SetEditor<R::Set>,
+) -> RecordListEdit<R> {
+    let text_ = text.clone();
+    RecordListEdit::new(
+        records,
+        timezone,
+        timezone_display,
+        &text,
+        &R::add_label(&text),
+        Box::new(move || R::new_record(DateTimeTz(day_start(&date)))),
+        Rc::new(move |id, record, timezone, on_update| {
+            SetsRecordEdit::new(
+                id,
+                record,
+                timezone,
+                &activities,
+                &text_,
+                set_editor.clone(),
+                on_update,
+            )
+            .into()
+        }),
+    )
+}
+
+#[derive(Clone)]
+struct SetsRecordEdit<R: SetsRecord> {
+    widget: gtk::Box,
+    header: gtk::Box,
+    sets_box: gtk::Box,
+
+    id: UniqueId,
+    record: Rc<RefCell<R>>,
+    set_editor: SetEditor<R::Set>,
+    on_update: Rc<Box<dyn Fn(UniqueId, R)>>,
+}
+
+impl<R: SetsRecord> From<SetsRecordEdit<R>> for RecordEdit {
+    fn from(edit: SetsRecordEdit<R>) -> RecordEdit {
+        RecordEdit {
+            widget: edit.widget,
+            header: edit.header,
+        }
+    }
+}
+
+impl<R: SetsRecord> SetsRecordEdit<R> {
+    fn new(
+        id: UniqueId,
+        record: R,
+        timezone: chrono_tz::Tz,
+        activities: &ActivityRegistry,
+        text: &Text,
+        set_editor: SetEditor<R::Set>,
+        on_update: Box<dyn Fn(UniqueId, R)>,
+    ) -> SetsRecordEdit<R> {
+        let on_update = Rc::new(on_update);
+        let record = Rc::new(RefCell::new(record));
+
+        let w = SetsRecordEdit {
+            widget: gtk::Box::new(gtk::Orientation::Vertical, 5),
+            header: gtk::Box::new(gtk::Orientation::Horizontal, 5),
+            sets_box: gtk::Box::new(gtk::Orientation::Vertical, 5),
+            id: id.clone(),
+            record: record.clone(),
+            set_editor,
+            on_update: on_update.clone(),
+        };
+
+        let time_entry = {
+            let time = record
+                .borrow()
+                .timestamp()
+                .0
+                .with_timezone(&timezone)
+                .time();
+            time_edit_c(
+                &time,
+                Box::new(enclose!(id, record, on_update => move |val| {
+                    let mut r = record.borrow_mut();
+                    let timestamp = set_time(&r.timestamp(), &timezone, val);
+                    r.set_timestamp(timestamp);
+                    on_update(id.clone(), r.clone());
+                })),
+            )
+        };
+
+        let activity_selection = {
+            let activity_id = record.borrow().activity().clone();
+            activity_edit_c(
+                R::KIND,
+                &activity_id,
+                activities,
+                text,
+                Box::new(enclose!(id, record, on_update => move |val| {
+                    let mut r = record.borrow_mut();
+                    r.set_activity(val);
+                    on_update(id.clone(), r.clone());
+                })),
+            )
+        };
+
+        let add_set_button = gtk::Button::new_with_label(&text.add_set());
+        add_set_button.connect_clicked(enclose!(w => move |_| {
+            {
+                let mut r = w.record.borrow_mut();
+                let set = R::next_set(r.sets().last());
+                r.sets_mut().push(set);
+                (w.on_update)(w.id.clone(), r.clone());
+            }
+            w.render_sets();
+        }));
+
+        w.header.pack_start(&time_entry, false, false, 5);
+        w.header.pack_start(&activity_selection, false, false, 5);
+        w.header.pack_start(&add_set_button, false, false, 5);
+
+        w.widget.pack_start(&w.header, false, false, 5);
+        w.widget.pack_start(&w.sets_box, false, false, 5);
+
+        w.render_sets();
+
+        w
+    }
+
+    fn render_sets(&self) {
+        self.sets_box.foreach(|child| child.destroy());
+
+        let sets = self.record.borrow().sets().to_vec();
+        for (idx, set) in sets.iter().enumerate() {
+            let w = self.clone();
+            let w_ = self.clone();
+            self.sets_box.pack_start(
+                &(self.set_editor)(
+                    set,
+                    Box::new(move |set| {
+                        let mut r = w.record.borrow_mut();
+                        r.sets_mut()[idx] = set;
+                        (w.on_update)(w.id.clone(), r.clone());
+                    }),
+                    Box::new(move || {
+                        {
+                            let mut r = w_.record.borrow_mut();
+                            r.sets_mut().remove(idx);
+                            (w_.on_update)(w_.id.clone(), r.clone());
+                        }
+                        w_.render_sets();
+                    }),
+                ),
+                false,
+                false,
+                5,
+            );
+        }
+
+        self.sets_box.show_all();
+    }
+}
@@ -1,15 +1,22 @@
 use chrono_tz::Tz;
+use fitnesstrax_lib::activity::ActivityKind;
+use fitnesstrax_lib::import::{ImportReport, Parsed};
 use gtk::prelude::*;
 use std::cell::RefCell;
+use std::convert::TryFrom;
 use std::path::PathBuf;
 use std::rc::Rc;
 use std::sync::{Arc, RwLock};
+use std::thread;
 
 use crate::components::{
-    dropmenu_c, labeled_widget_c, text_entry_c, Component, Container, MenuOptions,
+    dropmenu_c, labeled_widget_c, timezone_selector_c, workout_preview_c, Component, Container,
+    MenuOptions,
 };
-use crate::context::Application;
-use crate::i18n::{Text, UnitSystem};
+use crate::context::{read_apple_health, read_csv, Application, State};
+use crate::errors::Result;
+use crate::i18n::{DistanceUnit, MassUnit, Text, UnitSystem};
+use crate::range::TimezoneDisplay;
 
 #[derive(Clone)]
 pub struct Settings {
@@ -19,6 +26,10 @@ pub struct Settings {
     language_widget: Container,
     timezone_widget: Container,
     units_widget: Container,
+    preferences_status: gtk::Label,
+    activities_widget: Container,
+    data_widget: Container,
+    data_status: gtk::Label,
 
     ctx: Arc<RwLock<Application>>,
 }
@@ -35,6 +46,10 @@ impl Settings {
             language_widget: Container::new(no_widget.clone()),
             timezone_widget: Container::new(no_widget.clone()),
             units_widget: Container::new(no_widget.clone()),
+            preferences_status: gtk::Label::new(None),
+            activities_widget: Container::new(no_widget.clone()),
+            data_widget: Container::new(no_widget.clone()),
+            data_status: gtk::Label::new(None),
             ctx: ctx.clone(),
         };
 
@@ -50,34 +65,35 @@ impl Settings {
         component
             .widget
             .pack_start(&component.units_widget.widget, false, false, 0);
+        component
+            .widget
+            .pack_start(&component.preferences_status, false, false, 0);
+        component
+            .widget
+            .pack_start(&component.activities_widget.widget, false, false, 0);
+        component
+            .widget
+            .pack_start(&component.data_widget.widget, false, false, 0);
 
-        let (series_path, settings) = {
+        let (series_path, series_error, settings) = {
             let ctx = ctx.read().unwrap();
             let state = ctx.get_state();
 
             (
                 state.series_path().map(|v| v.clone()),
+                state
+                    .series_error()
+                    .map(|err| state.text().series_error(err)),
                 state.settings().clone(),
             )
         };
 
-        {
-            let ctx = ctx.clone();
-            let chooser =
-                gtk::FileChooserButton::new("database file", gtk::FileChooserAction::Open);
-            if let Some(sp) = series_path {
-                chooser.set_filename(sp);
-            }
-            chooser.connect_file_set(move |chooser| {
-                if let Some(filename) = chooser.get_filename() {
-                    ctx.write().unwrap().set_series_path(filename);
-                }
-            });
-            component.database_path_widget.swap(Some(labeled_widget_c(
-                &settings.text.database_path(),
-                chooser,
-            )));
-        }
+        component.database_path_widget.swap(Some(database_path_c(
+            &settings.text,
+            series_path,
+            series_error,
+            ctx.clone(),
+        )));
 
         {
             let component = Rc::new(RefCell::new(component.clone()));
@@ -92,6 +108,7 @@ impl Settings {
                 .swap(Some(timezone_menu(
                     &settings.text,
                     &settings.timezone,
+                    settings.timezone_display,
                     component.clone(),
                 )));
             component.borrow_mut().units_widget.swap(Some(units_menu(
@@ -101,101 +118,730 @@ impl Settings {
             )));
         }
 
+        component
+            .activities_widget
+            .swap(Some(activity_form(&settings.text, ctx.clone())));
+        let (data_widget, data_status) = data_form(&settings.text, ctx.clone());
+        component.data_widget.swap(Some(data_widget));
+        component.data_status = data_status;
+
         component.widget.show_all();
 
         component
     }
 
     fn set_language(&mut self, language: &str) {
-        let (series_path, text, timezone, units) = {
-            let mut ctx = self.ctx.write().unwrap();
-            ctx.set_language(language);
+        let result = self.ctx.write().unwrap().set_language(language);
+        self.reload();
+        self.show_saved(result);
+    }
+
+    /// Show the application's current database and settings again, after they have changed
+    /// somewhere other than this page.
+    pub fn reload(&mut self) {
+        let (series_path, series_error, text, timezone, timezone_display, units) = {
+            let ctx = self.ctx.read().unwrap();
             let state = ctx.get_state();
             let settings = state.settings();
             (
+                state.series_path().cloned(),
                 state
-                    .series_path()
-                    .and_then(|s| s.to_str())
-                    .map(String::from),
+                    .series_error()
+                    .map(|err| settings.text.series_error(err)),
                 settings.text.clone(),
                 settings.timezone,
+                settings.timezone_display,
                 settings.units,
             )
         };
 
-        let ctx = self.ctx.clone();
-        self.database_path_widget.swap(Some(labeled_widget_c(
-            &text.database_path(),
-            text_entry_c(
-                &series_path.unwrap_or(String::from("")),
-                Box::new(move |s| ctx.write().unwrap().set_series_path(PathBuf::from(s))),
-            ),
+        self.database_path_widget.swap(Some(database_path_c(
+            &text,
+            series_path,
+            series_error,
+            self.ctx.clone(),
         )));
 
         {
             let component = Rc::new(RefCell::new(self.clone()));
             self.language_widget
                 .swap(Some(language_menu(&text, component.clone())));
-            self.timezone_widget
-                .swap(Some(timezone_menu(&text, &timezone, component.clone())));
+            self.timezone_widget.swap(Some(timezone_menu(
+                &text,
+                &timezone,
+                timezone_display,
+                component.clone(),
+            )));
             self.units_widget
                 .swap(Some(units_menu(&text, &units, component.clone())));
         }
+
+        self.activities_widget
+            .swap(Some(activity_form(&text, self.ctx.clone())));
+        let (data_widget, data_status) = data_form(&text, self.ctx.clone());
+        self.data_widget.swap(Some(data_widget));
+        self.data_status = data_status;
+    }
+
+    /// Show the summary of an import which ran in the background.
+    pub fn set_import_status(&self, summary: &str) {
+        self.data_status.set_text(summary);
     }
 
-    fn set_timezone(&self, timezone_str: &str) {
-        let mut ctx = self.ctx.write().unwrap();
-        ctx.set_timezone(timezone_str.parse().unwrap());
+    fn set_timezone(&self, timezone: Tz) {
+        let result = self.ctx.write().unwrap().set_timezone(timezone);
+        self.show_saved(result);
     }
 
-    fn set_units(&self, units: &str) {
-        let mut ctx = self.ctx.write().unwrap();
-        ctx.set_units(units);
+    fn set_timezone_display(&self, timezone_display: TimezoneDisplay) {
+        let result = self
+            .ctx
+            .write()
+            .unwrap()
+            .set_timezone_display(timezone_display);
+        self.show_saved(result);
+    }
+
+    fn set_units(&mut self, units: UnitSystem) {
+        let (result, text) = {
+            let mut ctx = self.ctx.write().unwrap();
+            let result = ctx.set_units(units);
+            (result, ctx.get_state().settings().text.clone())
+        };
+
+        let component = Rc::new(RefCell::new(self.clone()));
+        self.units_widget
+            .swap(Some(units_menu(&text, &units, component)));
+        self.show_saved(result);
+    }
+
+    /// Warn that a preference only lasts until the application closes, or clear the warning once
+    /// the preferences get saved again.
+    fn show_saved(&self, result: Result<()>) {
+        match result {
+            Ok(()) => self.preferences_status.set_text(""),
+            Err(err) => {
+                let text = self.ctx.read().unwrap().get_state().settings().text.clone();
+                self.preferences_status
+                    .set_text(&text.settings_not_saved(&err));
+            }
+        }
     }
 }
 
+/// The languages that there are translations for.
+pub const LANGUAGES: [(&str, &str); 2] = [("en", "English"), ("eo", "Esperanto")];
+
+pub const UNIT_PRESETS: [(&str, &str); 3] = [
+    ("SI", "SI (kg, km, m)"),
+    ("USA", "USA (lbs, mi, yd)"),
+    ("UK", "UK (st, mi, m)"),
+];
+
 impl Component for Settings {
     fn widget(&self) -> gtk::Widget {
         self.widget.clone().upcast::<gtk::Widget>()
     }
 }
 
+/// Choose an existing database, or create a new one. Problems with either get shown next to the
+/// buttons.
+fn database_path_c(
+    text: &Text,
+    series_path: Option<PathBuf>,
+    series_error: Option<String>,
+    ctx: Arc<RwLock<Application>>,
+) -> gtk::Widget {
+    let widget = gtk::Box::new(gtk::Orientation::Horizontal, 5);
+    let status = gtk::Label::new(series_error.as_deref());
+    status.set_selectable(true);
+
+    let chooser = gtk::FileChooserButton::new(&text.database_path(), gtk::FileChooserAction::Open);
+    if let Some(ref path) = series_path {
+        chooser.set_filename(path);
+    }
+    chooser.connect_file_set(enclose!(ctx, status, text => move |chooser| {
+        if let Some(filename) = chooser.get_filename() {
+            let result = ctx.write().unwrap().set_series_path(filename);
+            match result {
+                Ok(()) => status.set_text(""),
+                Err(err) => status.set_text(&text.series_error(&err)),
+            }
+        }
+    }));
+
+    let new_button = gtk::Button::new_with_label(&text.new_database());
+    new_button.connect_clicked(enclose!(chooser, status, text => move |button| {
+        if let Some(path) = choose_new_database(&text.new_database(), button) {
+            let result = ctx.write().unwrap().create_series(path.clone());
+            match result {
+                Ok(()) => {
+                    chooser.set_filename(&path);
+                    status.set_text("");
+                }
+                Err(err) => status.set_text(&text.series_error(&err)),
+            }
+        }
+    }));
+
+    widget.pack_start(&chooser, false, false, 5);
+    widget.pack_start(&new_button, false, false, 5);
+    widget.pack_start(&status, false, false, 5);
+    labeled_widget_c(&text.database_path(), widget)
+}
+
 fn language_menu(text: &Text, component: Rc<RefCell<Settings>>) -> gtk::Widget {
     labeled_widget_c(
         text.language().as_str(),
         dropmenu_c(
-            MenuOptions(vec![("en", "English"), ("eo", "Esperanto")]),
+            MenuOptions(LANGUAGES.to_vec()),
             text.language_id().get_language(),
             Box::new(move |s| component.borrow_mut().set_language(s)),
         ),
     )
 }
 
-fn timezone_menu(text: &Text, timezone: &Tz, component: Rc<RefCell<Settings>>) -> gtk::Widget {
-    labeled_widget_c(
-        &text.timezone(),
-        dropmenu_c(
-            MenuOptions(vec![
-                ("Etc/UTC", "UTC"),
-                ("America/Chicago", "United States: Chicago"),
-                ("America/New_York", "United States: New York"),
-            ]),
-            timezone.name(),
-            Box::new(enclose!(component => move |s| component.borrow_mut().set_timezone(s))),
+/// The home timezone, and whether records show up in it or in the timezones that they were
+/// recorded in.
+fn timezone_menu(
+    text: &Text,
+    timezone: &Tz,
+    timezone_display: TimezoneDisplay,
+    component: Rc<RefCell<Settings>>,
+) -> gtk::Widget {
+    let widget = gtk::Box::new(gtk::Orientation::Vertical, 5);
+    widget.pack_start(
+        &labeled_widget_c(
+            &text.timezone(),
+            timezone_selector_c(
+                timezone,
+                Box::new(enclose!(component => move |timezone| {
+                    component.borrow_mut().set_timezone(timezone)
+                })),
+            ),
         ),
-    )
+        false,
+        false,
+        5,
+    );
+
+    let options: Vec<(String, String)> = [TimezoneDisplay::Home, TimezoneDisplay::Recorded]
+        .iter()
+        .map(|display| (String::from(display), text.timezone_display_option(display)))
+        .collect();
+    widget.pack_start(
+        &labeled_widget_c(
+            &text.timezone_display(),
+            dropmenu_c(
+                MenuOptions(
+                    options
+                        .iter()
+                        .map(|(id, label)| (id.as_ref(), label.as_ref()))
+                        .collect(),
+                ),
+                &String::from(&timezone_display),
+                Box::new(move |s| {
+                    if let Ok(display) = TimezoneDisplay::try_from(s) {
+                        component.borrow_mut().set_timezone_display(display);
+                    }
+                }),
+            ),
+        ),
+        false,
+        false,
+        5,
+    );
+
+    widget.upcast::<gtk::Widget>()
 }
 
+/// A menu of the unit presets, followed by a menu for each quantity. Choosing a preset sets the
+/// units of every quantity.
 fn units_menu(text: &Text, units: &UnitSystem, component: Rc<RefCell<Settings>>) -> gtk::Widget {
-    labeled_widget_c(
-        &text.units(),
-        dropmenu_c(
-            MenuOptions(vec![
-                ("SI", "SI (kg, km, m/s)"),
-                ("USA", "USA (lbs, mi, mph)"),
-            ]),
-            &String::from(units),
-            Box::new(enclose!(component => move |s| component.borrow_mut().set_units(s))),
+    let widget = gtk::Box::new(gtk::Orientation::Vertical, 5);
+    let units = *units;
+
+    widget.pack_start(
+        &labeled_widget_c(
+            &text.units(),
+            dropmenu_c(
+                MenuOptions(UNIT_PRESETS.to_vec()),
+                &String::from(&units),
+                Box::new(enclose!(component => move |s| {
+                    if let Ok(units) = UnitSystem::try_from(s) {
+                        component.borrow_mut().set_units(units);
+                    }
+                })),
+            ),
+        ),
+        false,
+        false,
+        5,
+    );
+    widget.pack_start(
+        &labeled_widget_c(
+            &text.body_mass_units(),
+            mass_unit_menu(
+                text,
+                &[MassUnit::Kilograms, MassUnit::Pounds, MassUnit::Stones],
+                units.body_mass,
+                Box::new(enclose!(component => move |body_mass| {
+                    component.borrow_mut().set_units(UnitSystem { body_mass, ..units });
+                })),
+            ),
+        ),
+        false,
+        false,
+        5,
+    );
+    widget.pack_start(
+        &labeled_widget_c(
+            &text.distance_units(),
+            distance_unit_menu(
+                text,
+                &[DistanceUnit::Kilometers, DistanceUnit::Miles],
+                units.distance,
+                Box::new(enclose!(component => move |distance| {
+                    component.borrow_mut().set_units(UnitSystem { distance, ..units });
+                })),
+            ),
+        ),
+        false,
+        false,
+        5,
+    );
+    widget.pack_start(
+        &labeled_widget_c(
+            &text.swim_distance_units(),
+            distance_unit_menu(
+                text,
+                &[DistanceUnit::Meters, DistanceUnit::Yards],
+                units.swim_distance,
+                Box::new(enclose!(component => move |swim_distance| {
+                    component
+                        .borrow_mut()
+                        .set_units(UnitSystem { swim_distance, ..units });
+                })),
+            ),
+        ),
+        false,
+        false,
+        5,
+    );
+    widget.pack_start(
+        &labeled_widget_c(
+            &text.load_units(),
+            mass_unit_menu(
+                text,
+                &[MassUnit::Kilograms, MassUnit::Pounds],
+                units.load,
+                Box::new(move |load| {
+                    component
+                        .borrow_mut()
+                        .set_units(UnitSystem { load, ..units });
+                }),
+            ),
+        ),
+        false,
+        false,
+        5,
+    );
+
+    widget.upcast::<gtk::Widget>()
+}
+
+fn mass_unit_menu(
+    text: &Text,
+    options: &[MassUnit],
+    current: MassUnit,
+    on_changed: Box<dyn Fn(MassUnit)>,
+) -> gtk::Widget {
+    let options: Vec<(String, String)> = options
+        .iter()
+        .map(|unit| (String::from(unit), text.mass_label(*unit)))
+        .collect();
+    dropmenu_c(
+        MenuOptions(
+            options
+                .iter()
+                .map(|(id, label)| (id.as_ref(), label.as_ref()))
+                .collect(),
+        ),
+        &String::from(&current),
+        Box::new(move |s| {
+            if let Ok(unit) = MassUnit::try_from(s) {
+                on_changed(unit);
+            }
+        }),
+    )
+}
+
+fn distance_unit_menu(
+    text: &Text,
+    options: &[DistanceUnit],
+    current: DistanceUnit,
+    on_changed: Box<dyn Fn(DistanceUnit)>,
+) -> gtk::Widget {
+    let options: Vec<(String, String)> = options
+        .iter()
+        .map(|unit| (String::from(unit), text.distance_label(*unit)))
+        .collect();
+    dropmenu_c(
+        MenuOptions(
+            options
+                .iter()
+                .map(|(id, label)| (id.as_ref(), label.as_ref()))
+                .collect(),
         ),
+        &String::from(&current),
+        Box::new(move |s| {
+            if let Ok(unit) = DistanceUnit::try_from(s) {
+                on_changed(unit);
+            }
+        }),
     )
 }
+
+fn activity_form(text: &Text, ctx: Arc<RwLock<Application>>) -> gtk::Widget {
+    let widget = gtk::Box::new(gtk::Orientation::Horizontal, 5);
+    let status = gtk::Label::new(None);
+    status.set_selectable(true);
+    let kind = Rc::new(RefCell::new(ActivityKind::TimeDistance));
+
+    let name_entry = gtk::Entry::new();
+
+    let kinds: Vec<(String, String)> = [
+        ActivityKind::TimeDistance,
+        ActivityKind::SetRep,
+        ActivityKind::RepDuration,
+        ActivityKind::WeightedSet,
+    ]
+    .iter()
+    .map(|kind| (format!("{:?}", kind), text.activity_kind(kind)))
+    .collect();
+    let kinds_: Vec<(&str, &str)> = kinds
+        .iter()
+        .map(|(id, text)| (id.as_ref(), text.as_ref()))
+        .collect();
+    let kind_menu = dropmenu_c(
+        MenuOptions(kinds_),
+        &format!("{:?}", kind.borrow()),
+        Box::new(enclose!(kind => move |s| {
+            *kind.borrow_mut() = ActivityKind::try_from(s).unwrap();
+        })),
+    );
+
+    let add_button = gtk::Button::new_with_label(&text.add_activity());
+    add_button.connect_clicked(enclose!(name_entry, kind, status => move |_| {
+        if let Some(name) = name_entry.get_text() {
+            if !name.as_str().is_empty() {
+                let result = ctx.write().unwrap().add_activity(name.as_str(), *kind.borrow());
+                match result {
+                    Ok(()) => {
+                        status.set_text("");
+                        name_entry.set_text("");
+                    }
+                    Err(err) => status.set_text(&format!("{}", err)),
+                }
+            }
+        }
+    }));
+
+    widget.pack_start(
+        &labeled_widget_c(&text.name(), name_entry.clone().upcast::<gtk::Widget>()),
+        false,
+        false,
+        5,
+    );
+    widget.pack_start(&labeled_widget_c(&text.kind(), kind_menu), false, false, 5);
+    widget.pack_start(&add_button, false, false, 5);
+
+    let form = gtk::Box::new(gtk::Orientation::Vertical, 5);
+    form.pack_start(&widget, false, false, 5);
+    form.pack_start(&status, false, false, 5);
+
+    labeled_widget_c(&text.activities(), form.upcast::<gtk::Widget>())
+}
+
+pub fn choose_new_database(title: &str, parent: &gtk::Button) -> Option<PathBuf> {
+    let window = parent
+        .get_toplevel()
+        .and_then(|w| w.downcast::<gtk::Window>().ok());
+    let dialog = gtk::FileChooserDialog::with_buttons(
+        Some(title),
+        window.as_ref(),
+        gtk::FileChooserAction::Save,
+        &[
+            ("_Cancel", gtk::ResponseType::Cancel),
+            ("_OK", gtk::ResponseType::Accept),
+        ],
+    );
+    dialog.set_current_name("fitnesstrax.series");
+    let path = match dialog.run() {
+        gtk::ResponseType::Accept => dialog.get_filename(),
+        _ => None,
+    };
+    dialog.destroy();
+    path
+}
+
+fn choose_folder(title: &str, parent: &gtk::Button) -> Option<PathBuf> {
+    let window = parent
+        .get_toplevel()
+        .and_then(|w| w.downcast::<gtk::Window>().ok());
+    let dialog = gtk::FileChooserDialog::with_buttons(
+        Some(title),
+        window.as_ref(),
+        gtk::FileChooserAction::SelectFolder,
+        &[
+            ("_Cancel", gtk::ResponseType::Cancel),
+            ("_OK", gtk::ResponseType::Accept),
+        ],
+    );
+    let folder = match dialog.run() {
+        gtk::ResponseType::Accept => dialog.get_filename(),
+        _ => None,
+    };
+    dialog.destroy();
+    folder
+}
+
+fn choose_workout_files(title: &str, parent: &gtk::Button) -> Vec<PathBuf> {
+    let window = parent
+        .get_toplevel()
+        .and_then(|w| w.downcast::<gtk::Window>().ok());
+    let dialog = gtk::FileChooserDialog::with_buttons(
+        Some(title),
+        window.as_ref(),
+        gtk::FileChooserAction::Open,
+        &[
+            ("_Cancel", gtk::ResponseType::Cancel),
+            ("_OK", gtk::ResponseType::Accept),
+        ],
+    );
+    dialog.set_select_multiple(true);
+    let filter = gtk::FileFilter::new();
+    filter.set_name(Some("FIT/GPX/TCX"));
+    for pattern in &["*.fit", "*.FIT", "*.gpx", "*.GPX", "*.tcx", "*.TCX"] {
+        filter.add_pattern(pattern);
+    }
+    dialog.add_filter(&filter);
+    let files = match dialog.run() {
+        gtk::ResponseType::Accept => dialog.get_filenames(),
+        _ => Vec::new(),
+    };
+    dialog.destroy();
+    files
+}
+
+fn choose_apple_health_export(title: &str, parent: &gtk::Button) -> Option<PathBuf> {
+    let window = parent
+        .get_toplevel()
+        .and_then(|w| w.downcast::<gtk::Window>().ok());
+    let dialog = gtk::FileChooserDialog::with_buttons(
+        Some(title),
+        window.as_ref(),
+        gtk::FileChooserAction::Open,
+        &[
+            ("_Cancel", gtk::ResponseType::Cancel),
+            ("_OK", gtk::ResponseType::Accept),
+        ],
+    );
+    let filter = gtk::FileFilter::new();
+    filter.set_name(Some("export.xml"));
+    filter.add_pattern("*.xml");
+    dialog.add_filter(&filter);
+    let file = match dialog.run() {
+        gtk::ResponseType::Accept => dialog.get_filename(),
+        _ => None,
+    };
+    dialog.destroy();
+    file
+}
+
+/// Show the records that were read from the workout files and ask whether to import them.
+fn confirm_workouts(
+    files: &[(PathBuf, Parsed)],
+    text: &Text,
+    ctx: &Arc<RwLock<Application>>,
+    parent: &gtk::Button,
+) -> bool {
+    let preview = {
+        let ctx = ctx.read().unwrap();
+        match ctx.get_state() {
+            State::Unconfigured(_) => return false,
+            State::Configured(state) => workout_preview_c(
+                files,
+                state.timezone(),
+                state.activities(),
+                state.text(),
+                state.units(),
+            ),
+        }
+    };
+
+    let window = parent
+        .get_toplevel()
+        .and_then(|w| w.downcast::<gtk::Window>().ok());
+    let dialog = gtk::Dialog::new_with_buttons(
+        Some(&text.import_workouts()),
+        window.as_ref(),
+        gtk::DialogFlags::MODAL,
+        &[
+            (&text.cancel(), gtk::ResponseType::Cancel),
+            (&text.import(), gtk::ResponseType::Accept),
+        ],
+    );
+    dialog.set_default_size(600, 400);
+    let scroller = gtk::ScrolledWindow::new(None::<&gtk::Adjustment>, None::<&gtk::Adjustment>);
+    scroller.add(&preview);
+    scroller.show();
+    dialog
+        .get_content_area()
+        .pack_start(&scroller, true, true, 5);
+
+    let response = dialog.run();
+    dialog.destroy();
+    response == gtk::ResponseType::Accept
+}
+
+/// Summarize an import, listing the entries that were not imported below the summary.
+fn import_report_lines(name: &str, report: ImportReport, text: &Text) -> Vec<String> {
+    let mut lines = vec![text.import_report(
+        name,
+        report.imported,
+        report.duplicates,
+        report.conflicts.len(),
+        report.unmapped.len(),
+    )];
+    for entry in report.conflicts.into_iter().chain(report.unmapped) {
+        lines.push(format!("    {}", entry));
+    }
+    lines
+}
+
+/// Reads the records to import, named by where they came from.
+type ReadImport = Box<dyn FnOnce() -> Result<Vec<(String, Parsed)>> + Send>;
+
+/// Read and import records on a worker thread, since an export can take minutes to read. The
+/// summary comes back to the status label through the application's channel.
+fn import_in_background(
+    ctx: Arc<RwLock<Application>>,
+    text: &Text,
+    status: &gtk::Label,
+    read: ReadImport,
+) {
+    status.set_text(&text.importing());
+    let text = text.clone();
+    thread::spawn(move || {
+        let result = read().and_then(|sources| ctx.write().unwrap().import_records(sources));
+        let summary = match result {
+            Ok(reports) => reports
+                .into_iter()
+                .flat_map(|(name, report)| import_report_lines(&name, report, &text))
+                .collect::<Vec<String>>()
+                .join("\n"),
+            Err(err) => format!("{}", err),
+        };
+        ctx.read().unwrap().report_import(summary);
+    });
+}
+
+/// The form for exporting and importing records, along with the label that shows how an export
+/// or import went.
+fn data_form(text: &Text, ctx: Arc<RwLock<Application>>) -> (gtk::Widget, gtk::Label) {
+    let widget = gtk::Box::new(gtk::Orientation::Vertical, 5);
+    let buttons = gtk::Box::new(gtk::Orientation::Horizontal, 5);
+    let status = gtk::Label::new(None);
+    status.set_xalign(0.0);
+    status.set_selectable(true);
+
+    let export_button = gtk::Button::new_with_label(&text.export_csv());
+    export_button.connect_clicked(enclose!(ctx, status, text => move |button| {
+        if let Some(dir) = choose_folder(&text.export_csv(), button) {
+            match ctx.read().unwrap().export_csv(&dir) {
+                Ok(()) => status.set_text(&text.export_complete()),
+                Err(err) => status.set_text(&format!("{}", err)),
+            }
+        }
+    }));
+
+    let import_button = gtk::Button::new_with_label(&text.import_csv());
+    import_button.connect_clicked(enclose!(ctx, status, text => move |button| {
+        if let Some(dir) = choose_folder(&text.import_csv(), button) {
+            let activities = match ctx.read().unwrap().get_state() {
+                State::Configured(ref state) => state.activities().clone(),
+                State::Unconfigured(_) => return,
+            };
+            import_in_background(
+                ctx.clone(),
+                &text,
+                &status,
+                Box::new(move || {
+                    read_csv(&dir, &activities).map(|tables| {
+                        tables
+                            .into_iter()
+                            .map(|(table, parsed)| (String::from(table.file_name()), parsed))
+                            .collect()
+                    })
+                }),
+            );
+        }
+    }));
+
+    let workouts_button = gtk::Button::new_with_label(&text.import_workouts());
+    workouts_button.connect_clicked(enclose!(ctx, status, text => move |button| {
+        let paths = choose_workout_files(&text.import_workouts(), button);
+        if paths.is_empty() {
+            return;
+        }
+        let result = ctx.read().unwrap().read_workout_files(&paths);
+        let files = match result {
+            Ok(files) => files,
+            Err(err) => {
+                status.set_text(&format!("{}", err));
+                return;
+            }
+        };
+        if !confirm_workouts(&files, &text, &ctx, button) {
+            return;
+        }
+
+        import_in_background(
+            ctx.clone(),
+            &text,
+            &status,
+            Box::new(move || {
+                Ok(files
+                    .into_iter()
+                    .map(|(path, parsed)| (path.to_string_lossy().into_owned(), parsed))
+                    .collect())
+            }),
+        );
+    }));
+
+    let apple_health_button = gtk::Button::new_with_label(&text.import_apple_health());
+    apple_health_button.connect_clicked(enclose!(ctx, status, text => move |button| {
+        if let Some(path) = choose_apple_health_export(&text.import_apple_health(), button) {
+            let timezone = *ctx.read().unwrap().get_state().timezone();
+            import_in_background(
+                ctx.clone(),
+                &text,
+                &status,
+                Box::new(move || {
+                    read_apple_health(&path, timezone)
+                        .map(|parsed| vec![(path.to_string_lossy().into_owned(), parsed)])
+                }),
+            );
+        }
+    }));
+
+    buttons.pack_start(&export_button, false, false, 5);
+    buttons.pack_start(&import_button, false, false, 5);
+    buttons.pack_start(&workouts_button, false, false, 5);
+    buttons.pack_start(&apple_health_button, false, false, 5);
+    widget.pack_start(&buttons, false, false, 5);
+    widget.pack_start(&status, false, false, 5);
+
+    (widget.upcast::<gtk::Widget>(), status)
+}
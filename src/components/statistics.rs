@@ -0,0 +1,318 @@
+use dimensioned::si;
+use emseries::UniqueId;
+use fitnesstrax_lib::activity::{ActivityKind, ActivityRegistry};
+use fitnesstrax_lib::analytics::{summarize, Period, Summary};
+use fitnesstrax_lib::TraxRecord;
+use gtk::prelude::*;
+use std::sync::{Arc, RwLock};
+
+use crate::components::activity::activity_name;
+use crate::components::basics::date_c;
+use crate::components::heatmap::heatmap_c;
+use crate::components::weight_chart::weight_chart_c;
+use crate::components::Component;
+use crate::context::{Application, Calendar};
+use crate::formats::Duration;
+use crate::i18n::{Text, UnitSystem};
+use crate::range::TimezoneDisplay;
+use crate::types::DateRange;
+
+#[derive(Clone)]
+pub struct Statistics {
+    widget: gtk::ScrolledWindow,
+    statistics_box: gtk::Box,
+
+    range: Option<DateRange>,
+    records: Vec<(UniqueId, TraxRecord)>,
+    calendar: Option<Calendar>,
+    timezone_display: TimezoneDisplay,
+    activities: ActivityRegistry,
+    text: Text,
+    units: UnitSystem,
+
+    ctx: Arc<RwLock<Application>>,
+}
+
+impl Statistics {
+    /// The page stays empty until it gets records with `set_records`.
+    pub fn new(
+        activities: &ActivityRegistry,
+        text: &Text,
+        units: &UnitSystem,
+        ctx: Arc<RwLock<Application>>,
+    ) -> Statistics {
+        let no_adjustment: Option<&gtk::Adjustment> = None;
+        let widget = gtk::ScrolledWindow::new(no_adjustment, no_adjustment);
+        let statistics_box = gtk::Box::new(gtk::Orientation::Vertical, 5);
+        widget.add(&statistics_box);
+        widget.show_all();
+
+        Statistics {
+            widget,
+            statistics_box,
+
+            range: None,
+            records: Vec::new(),
+            calendar: None,
+            timezone_display: TimezoneDisplay::Home,
+            activities: activities.clone(),
+            text: text.clone(),
+            units: *units,
+
+            ctx,
+        }
+    }
+
+    /// Show the records of a range. Everything that the page shows comes in here, so that the
+    /// page never has to go back to the application while it renders.
+    pub fn set_records(
+        &mut self,
+        range: DateRange,
+        records: Vec<(UniqueId, TraxRecord)>,
+        calendar: Calendar,
+        timezone_display: TimezoneDisplay,
+    ) {
+        self.range = Some(range);
+        self.records = records;
+        self.calendar = Some(calendar);
+        self.timezone_display = timezone_display;
+        self.render();
+    }
+
+    pub fn set_activities(&mut self, activities: ActivityRegistry) {
+        self.activities = activities;
+        self.render();
+    }
+
+    pub fn set_language(&mut self, text: Text) {
+        self.text = text;
+        self.render();
+    }
+
+    pub fn set_units(&mut self, units: UnitSystem) {
+        self.units = units;
+        self.render();
+    }
+
+    fn render(&mut self) {
+        self.statistics_box.foreach(|child| child.destroy());
+        let (range, calendar) = match (&self.range, &self.calendar) {
+            (Some(range), Some(calendar)) => (range, calendar),
+            _ => return,
+        };
+
+        let records = self.records.iter().map(|(_, record)| record);
+        let total = summarize(
+            records.clone(),
+            range.start,
+            range.end,
+            Period::Range,
+            self.timezone_display,
+        );
+        let weeks = summarize(
+            records,
+            range.start,
+            range.end,
+            Period::Week,
+            self.timezone_display,
+        );
+
+        let header = gtk::Box::new(gtk::Orientation::Horizontal, 5);
+        header.pack_start(&date_c(&range.start), false, false, 5);
+        header.pack_start(&gtk::Label::new(Some("-")), false, false, 5);
+        header.pack_start(&date_c(&range.end), false, false, 5);
+        self.statistics_box.pack_start(&header, false, false, 5);
+        self.statistics_box
+            .pack_start(&self.calendar_c(range, calendar), false, false, 5);
+
+        if let Some(total) = total.first() {
+            self.statistics_box
+                .pack_start(&self.activities_c(total), false, false, 5);
+            self.statistics_box
+                .pack_start(&self.reps_c(total), false, false, 5);
+            self.statistics_box
+                .pack_start(&self.weekly_distance_c(&weeks), false, false, 5);
+            self.statistics_box
+                .pack_start(&self.steps_c(total), false, false, 5);
+            self.statistics_box
+                .pack_start(&self.weight_c(range, total), false, false, 5);
+        }
+
+        self.statistics_box.show_all();
+    }
+
+    fn calendar_c(&self, range: &DateRange, calendar: &Calendar) -> gtk::Widget {
+        let heatmap = heatmap_c(
+            self.ctx.clone(),
+            &calendar.year,
+            calendar.records.clone(),
+            self.timezone_display,
+            range,
+            &self.text,
+        );
+        frame_c(&self.text.activity_calendar(), &heatmap)
+    }
+
+    fn activities_c(&self, total: &Summary) -> gtk::Widget {
+        let grid = table_c(&[
+            self.text.activity(),
+            self.text.workouts(),
+            self.text.total_distance(),
+            self.text.total_duration(),
+            self.text.average_distance(),
+            self.text.average_duration(),
+            self.text.average_pace(),
+        ]);
+        for (row, (activity, totals)) in total.time_distance.iter().enumerate() {
+            let workouts = totals.workouts as f64;
+            let distance_units = self.units.distance_for(activity);
+            let pace = totals
+                .speed()
+                .map(|speed| self.text.pace(activity, speed, &self.units));
+            row_c(
+                &grid,
+                row as i32 + 1,
+                &[
+                    activity_name(activity, &self.activities, &self.text),
+                    format!("{}", totals.workouts),
+                    self.text.distance(totals.distance, distance_units),
+                    format!("{}", Duration::new(totals.duration)),
+                    self.text
+                        .distance(totals.distance / workouts, distance_units),
+                    format!("{}", Duration::new(totals.duration / workouts)),
+                    pace.unwrap_or_default(),
+                ],
+            );
+        }
+        frame_c(&self.text.activity_kind(&ActivityKind::TimeDistance), &grid)
+    }
+
+    fn reps_c(&self, total: &Summary) -> gtk::Widget {
+        let grid = table_c(&[
+            self.text.activity(),
+            self.text.total(),
+            self.text.daily_average(),
+        ]);
+        for (row, (activity, reps)) in total.reps.iter().enumerate() {
+            row_c(
+                &grid,
+                row as i32 + 1,
+                &[
+                    activity_name(activity, &self.activities, &self.text),
+                    format!("{}", reps),
+                    format!("{:.1}", f64::from(*reps) / total.days() as f64),
+                ],
+            );
+        }
+        frame_c(&self.text.activity_kind(&ActivityKind::SetRep), &grid)
+    }
+
+    fn weekly_distance_c(&self, weeks: &[Summary]) -> gtk::Widget {
+        let weekly = gtk::Box::new(gtk::Orientation::Vertical, 5);
+        for week in weeks {
+            let distances = week
+                .time_distance
+                .iter()
+                .filter(|(_, totals)| totals.distance > 0. * si::M)
+                .map(|(activity, totals)| {
+                    format!(
+                        "{} {}",
+                        activity_name(activity, &self.activities, &self.text),
+                        self.text
+                            .distance(totals.distance, self.units.distance_for(activity))
+                    )
+                })
+                .collect::<Vec<String>>();
+            weekly.pack_start(
+                &label_c(&format!(
+                    "{}: {}",
+                    self.text.week_of(&week.start),
+                    distances.join(", ")
+                )),
+                false,
+                false,
+                5,
+            );
+        }
+        frame_c(&self.text.weekly_distance(), &weekly)
+    }
+
+    fn steps_c(&self, total: &Summary) -> gtk::Widget {
+        let grid = table_c(&[self.text.total(), self.text.daily_average()]);
+        row_c(
+            &grid,
+            1,
+            &[
+                self.text.step_count(total.steps),
+                total
+                    .mean_steps()
+                    .map(|mean| self.text.step_count(mean.round() as u32))
+                    .unwrap_or_default(),
+            ],
+        );
+        frame_c(&self.text.steps_label(), &grid)
+    }
+
+    fn weight_c(&self, range: &DateRange, total: &Summary) -> gtk::Widget {
+        let grid = table_c(&[
+            self.text.weight_change(),
+            self.text.minimum(),
+            self.text.maximum(),
+            self.text.mean(),
+        ]);
+        let chart = weight_chart_c(
+            range,
+            &self.records,
+            &self.text,
+            &self.units,
+            self.timezone_display,
+        );
+        grid.attach(&chart, 0, 2, 4, 1);
+        if let Some(ref weight) = total.weight {
+            row_c(
+                &grid,
+                1,
+                &[
+                    self.text
+                        .mass(weight.last - weight.first, self.units.body_mass),
+                    self.text.mass(weight.min, self.units.body_mass),
+                    self.text.mass(weight.max, self.units.body_mass),
+                    self.text.mass(weight.mean, self.units.body_mass),
+                ],
+            );
+        }
+        frame_c(&self.text.weight(), &grid)
+    }
+}
+
+impl Component for Statistics {
+    fn widget(&self) -> gtk::Widget {
+        self.widget.clone().upcast::<gtk::Widget>()
+    }
+}
+
+fn label_c(text: &str) -> gtk::Label {
+    let label = gtk::Label::new(Some(text));
+    label.set_xalign(0.0);
+    label
+}
+
+fn table_c(headers: &[String]) -> gtk::Grid {
+    let grid = gtk::Grid::new();
+    grid.set_column_spacing(15);
+    grid.set_row_spacing(5);
+    row_c(&grid, 0, headers);
+    grid
+}
+
+fn row_c(grid: &gtk::Grid, row: i32, cells: &[String]) {
+    for (column, cell) in cells.iter().enumerate() {
+        grid.attach(&label_c(cell), column as i32, row, 1, 1);
+    }
+}
+
+fn frame_c<W: IsA<gtk::Widget>>(title: &str, content: &W) -> gtk::Widget {
+    let frame = gtk::Frame::new(Some(title));
+    frame.add(content);
+    frame.upcast::<gtk::Widget>()
+}
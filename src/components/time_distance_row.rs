@@ -1,33 +1,22 @@
-use chrono::Timelike;
 use emseries::*;
 use gtk::prelude::*;
-use std::convert::TryFrom;
 //use std::sync::{Arc, RwLock};
 use std::cell::RefCell;
 use std::rc::Rc;
 
+use crate::components::activity::{activity_c, activity_edit_c};
 use crate::components::basics::{
-    distance_c, distance_edit_c, dropmenu_c, duration_c, duration_edit_c, labeled_widget_c, time_c,
-    time_edit_c, MenuOptions,
+    distance_c, distance_edit_c, duration_c, duration_edit_c, time_c, time_edit_c,
 };
 use crate::i18n::{Text, UnitSystem};
-use fitnesstrax_lib::timedistance::{activity_types, ActivityType, TimeDistanceRecord};
-
-fn activity_c(activity: &ActivityType, text: &Text) -> gtk::Label {
-    let activity_str = match activity {
-        ActivityType::Cycling => text.cycling(),
-        ActivityType::Rowing => text.rowing(),
-        ActivityType::Running => text.running(),
-        ActivityType::Swimming => text.swimming(),
-        ActivityType::Walking => text.walking(),
-    };
-
-    gtk::Label::new(Some(&activity_str))
-}
+use crate::range::set_time;
+use fitnesstrax_lib::activity::{ActivityKind, ActivityRegistry};
+use fitnesstrax_lib::timedistance::TimeDistanceRecord;
 
 pub fn time_distance_c(
     record: &fitnesstrax_lib::timedistance::TimeDistanceRecord,
     timezone: &chrono_tz::Tz,
+    activities: &ActivityRegistry,
     text: &Text,
     units: &UnitSystem,
 ) -> gtk::Box {
@@ -39,11 +28,16 @@ pub fn time_distance_c(
         false,
         5,
     );
-    container.pack_start(&activity_c(&record.activity, text), false, false, 5);
+    container.pack_start(
+        &activity_c(&record.activity, activities, text),
+        false,
+        false,
+        5,
+    );
     container.pack_start(
         &record
             .distance
-            .map(|r| distance_c(&r, units))
+            .map(|r| distance_c(&r, units.distance_for(&record.activity)))
             .unwrap_or(gtk::Label::new(Some("---"))),
         false,
         false,
@@ -58,6 +52,20 @@ pub fn time_distance_c(
         false,
         5,
     );
+    if let (Some(distance), Some(duration)) = (record.distance, record.duration) {
+        if distance.value_unsafe > 0. && duration.value_unsafe > 0. {
+            container.pack_start(
+                &gtk::Label::new(Some(&text.pace(
+                    &record.activity,
+                    distance / duration,
+                    units,
+                ))),
+                false,
+                false,
+                5,
+            );
+        }
+    }
 
     return container;
 }
@@ -66,6 +74,7 @@ pub fn time_distance_record_edit_c(
     id: UniqueId,
     record: TimeDistanceRecord,
     timezone: chrono_tz::Tz,
+    activities: &ActivityRegistry,
     text: &Text,
     units: &UnitSystem,
     on_update: Box<dyn Fn(UniqueId, TimeDistanceRecord)>,
@@ -85,50 +94,24 @@ pub fn time_distance_record_edit_c(
             &time,
             Box::new(enclose!(id, record, on_update => move |val| {
                 let mut r = record.borrow_mut();
-                r.timestamp = r.timestamp.map(|ts| {
-                    ts.clone()
-                        .with_hour(val.hour())
-                        .unwrap()
-                        .with_minute(val.minute())
-                        .unwrap()
-                        .with_second(val.second())
-                        .unwrap()
-                        .with_timezone(&timezone)
-                });
+                r.timestamp = set_time(&r.timestamp, &timezone, val);
                 on_update(id.clone(), r.clone());
             })),
         )
     };
 
     let activity_selection = {
-        let menu: Vec<(String, String)> = activity_types()
-            .iter()
-            .map(|activity| {
-                (
-                    format!("{:?}", activity),
-                    text.time_distance_activity(activity),
-                )
-            })
-            .collect();
-        /* It's really annoying that I have to do this, but the iterators won't convert a vec of
-         * (String, Cow) to (&str, &str), and trying to do it all in a single statement leads to a
-         * lot of temporaries getting dropped. */
-        let menu_: Vec<(&str, &str)> = menu
-            .iter()
-            .map(|(id, text)| (id.as_ref(), text.as_ref()))
-            .collect();
-        let activity_id = format!("{:?}", record.borrow().activity);
-        labeled_widget_c(
-            &text.activity(),
-            dropmenu_c(
-                MenuOptions(menu_),
-                &activity_id,
-                Box::new(enclose!(id, record, on_update => move |val| {
-                    let mut r = record.borrow_mut();
-                    r.activity = ActivityType::try_from(val).unwrap();
-                    on_update(id.clone(), r.clone());
-                })),
-            ),
+        let activity_id = record.borrow().activity.clone();
+        activity_edit_c(
+            ActivityKind::TimeDistance,
+            &activity_id,
+            activities,
+            text,
+            Box::new(enclose!(id, record, on_update => move |val| {
+                let mut r = record.borrow_mut();
+                r.activity = val;
+                on_update(id.clone(), r.clone());
+            })),
         )
     };
 
@@ -136,7 +119,7 @@ pub fn time_distance_record_edit_c(
         let distance = record.borrow().distance.clone();
         distance_edit_c(
             &distance,
-            units,
+            units.distance_for(&record.borrow().activity),
             Box::new(enclose!(id, record, on_update => move |res| match res {
                 Some(val) => {
                     let mut r = record.borrow_mut();
@@ -7,7 +7,7 @@ use crate::errors::Error;
 use crate::i18n::{Text, UnitSystem};
 
 pub fn weight_record_c(record: &WeightRecord, text: &Text, units: &UnitSystem) -> gtk::Label {
-    gtk::Label::new(Some(&text.mass(record.weight.clone(), units)))
+    gtk::Label::new(Some(&text.mass(record.weight, units.body_mass)))
 }
 
 pub fn weight_record_edit_c(
@@ -25,18 +25,15 @@ pub fn weight_record_edit_c(
         Box::new(move |w| u1.render_mass(w.clone())),
         Box::new(move |s| {
             if s.len() == 0 {
-                Err(Error::ParseMassError)
+                Err(Error::ParseMassError(String::new()))
             } else {
-                match u2.parse_mass(s) {
-                    Ok(v) => Ok(v),
-                    Err(_) => Err(Error::ParseMassError),
-                }
+                u2.parse_mass(s)
             }
         }),
         Box::new(move |val| on_update(id.clone(), WeightRecord::new(record.timestamp(), val))),
     );
 
-    let units_label = gtk::Label::new(Some(&text.mass_label()));
+    let units_label = gtk::Label::new(Some(&text.mass_label(units.body_mass)));
 
     b.pack_start(&entry, false, false, 5);
     b.pack_start(&units_label, false, false, 5);
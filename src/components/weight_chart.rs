@@ -0,0 +1,217 @@
+use dimensioned::si;
+use emseries::{Recordable, UniqueId};
+use fitnesstrax_lib::TraxRecord;
+use gtk::prelude::*;
+use std::f64::consts::PI;
+
+use crate::i18n::{MassUnit, Text, UnitSystem};
+use crate::range::{dates_in_range, TimezoneDisplay};
+use crate::types::DateRange;
+
+/// How quickly the trend follows new weights. Daily weights swing by a kilogram or more from
+/// water alone, so the trend only moves a tenth of the way towards each new weight.
+const TREND_SMOOTHING: f64 = 0.1;
+
+const MARGIN_LEFT: f64 = 60.;
+const MARGIN_RIGHT: f64 = 15.;
+const MARGIN_TOP: f64 = 20.;
+const MARGIN_BOTTOM: f64 = 25.;
+const Y_TICKS: usize = 5;
+
+/// The mean weight of each day in the range, in the units that the chart is drawn in. Days
+/// without a weight are `None`.
+fn daily_weights(
+    range: &DateRange,
+    records: &[(UniqueId, TraxRecord)],
+    units: &UnitSystem,
+    display: TimezoneDisplay,
+) -> Vec<(chrono::Date<chrono_tz::Tz>, Option<f64>)> {
+    let home = range.start.timezone();
+    dates_in_range(range)
+        .into_iter()
+        .map(|date| {
+            let weights: Vec<f64> = records
+                .iter()
+                .filter_map(|(_, record)| match record {
+                    TraxRecord::Weight(ref rec)
+                        if display.date(&rec.timestamp(), &home) == date.naive_local() =>
+                    {
+                        Some(mass_value(rec.weight, units))
+                    }
+                    _ => None,
+                })
+                .collect();
+            if weights.is_empty() {
+                (date, None)
+            } else {
+                (
+                    date,
+                    Some(weights.iter().sum::<f64>() / weights.len() as f64),
+                )
+            }
+        })
+        .collect()
+}
+
+fn mass_value(mass: si::Kilogram<f64>, units: &UnitSystem) -> f64 {
+    match units.body_mass {
+        MassUnit::Kilograms => mass.value_unsafe,
+        MassUnit::Pounds => (mass / si::LB).value_unsafe,
+        MassUnit::Stones => (mass / si::LB).value_unsafe / 14.,
+    }
+}
+
+/// An exponentially weighted moving average of the weights. The trend starts at the first
+/// weight and holds steady across days that have no weight.
+fn trend(weights: &[Option<f64>], smoothing: f64) -> Vec<Option<f64>> {
+    let mut current: Option<f64> = None;
+    weights
+        .iter()
+        .map(|weight| {
+            current = match (current, weight) {
+                (None, Some(weight)) => Some(*weight),
+                (Some(trend), Some(weight)) => Some(trend + smoothing * (weight - trend)),
+                (trend, None) => trend,
+            };
+            current
+        })
+        .collect()
+}
+
+fn draw(
+    area: &gtk::DrawingArea,
+    cr: &cairo::Context,
+    days: &[(chrono::Date<chrono_tz::Tz>, Option<f64>)],
+    axis_label: &str,
+    empty_label: &str,
+    date_labels: &(String, String),
+) {
+    let width = f64::from(area.get_allocated_width());
+    let height = f64::from(area.get_allocated_height());
+    let weights: Vec<Option<f64>> = days.iter().map(|(_, weight)| *weight).collect();
+    let trend = trend(&weights, TREND_SMOOTHING);
+
+    cr.set_font_size(11.);
+    cr.set_source_rgb(0.4, 0.4, 0.4);
+
+    let values: Vec<f64> = weights
+        .iter()
+        .chain(trend.iter())
+        .filter_map(|v| *v)
+        .collect();
+    if values.is_empty() {
+        let extents = cr.text_extents(empty_label);
+        cr.move_to((width - extents.width) / 2., height / 2.);
+        cr.show_text(empty_label);
+        return;
+    }
+
+    let min = values.iter().cloned().fold(f64::INFINITY, f64::min).floor() - 1.;
+    let max = values
+        .iter()
+        .cloned()
+        .fold(f64::NEG_INFINITY, f64::max)
+        .ceil()
+        + 1.;
+    let plot_width = width - MARGIN_LEFT - MARGIN_RIGHT;
+    let plot_height = height - MARGIN_TOP - MARGIN_BOTTOM;
+    let x = |idx: usize| {
+        if days.len() > 1 {
+            MARGIN_LEFT + plot_width * idx as f64 / (days.len() - 1) as f64
+        } else {
+            MARGIN_LEFT + plot_width / 2.
+        }
+    };
+    let y = |value: f64| MARGIN_TOP + plot_height * (max - value) / (max - min);
+
+    /* Axes, with the unit at the top of the vertical axis and the ends of the range along the
+     * bottom. */
+    cr.set_line_width(1.);
+    cr.move_to(MARGIN_LEFT, MARGIN_TOP);
+    cr.line_to(MARGIN_LEFT, MARGIN_TOP + plot_height);
+    cr.line_to(MARGIN_LEFT + plot_width, MARGIN_TOP + plot_height);
+    cr.stroke();
+
+    cr.move_to(5., MARGIN_TOP - 8.);
+    cr.show_text(axis_label);
+    for tick in 0..=Y_TICKS {
+        let value = min + (max - min) * tick as f64 / Y_TICKS as f64;
+        let label = format!("{:.1}", value);
+        let extents = cr.text_extents(&label);
+        cr.move_to(
+            MARGIN_LEFT - extents.width - 8.,
+            y(value) + extents.height / 2.,
+        );
+        cr.show_text(&label);
+        cr.move_to(MARGIN_LEFT - 4., y(value));
+        cr.line_to(MARGIN_LEFT, y(value));
+        cr.stroke();
+    }
+
+    let (first, last) = date_labels;
+    cr.move_to(MARGIN_LEFT, height - 5.);
+    cr.show_text(first);
+    let extents = cr.text_extents(last);
+    cr.move_to(MARGIN_LEFT + plot_width - extents.width, height - 5.);
+    cr.show_text(last);
+
+    cr.set_source_rgb(0.2, 0.4, 0.8);
+    for (idx, weight) in weights.iter().enumerate() {
+        if let Some(weight) = weight {
+            cr.arc(x(idx), y(*weight), 3., 0., 2. * PI);
+            cr.fill();
+        }
+    }
+
+    cr.set_source_rgb(0.8, 0.3, 0.2);
+    cr.set_line_width(2.);
+    let mut started = false;
+    for (idx, value) in trend.iter().enumerate() {
+        if let Some(value) = value {
+            if started {
+                cr.line_to(x(idx), y(*value));
+            } else {
+                cr.move_to(x(idx), y(*value));
+                started = true;
+            }
+        }
+    }
+    cr.stroke();
+}
+
+/// Plot the weights over the range along with their trend.
+pub fn weight_chart_c(
+    range: &DateRange,
+    records: &[(UniqueId, TraxRecord)],
+    text: &Text,
+    units: &UnitSystem,
+    display: TimezoneDisplay,
+) -> gtk::DrawingArea {
+    let days = daily_weights(range, records, units, display);
+    let axis_label = text.mass_label(units.body_mass);
+    let empty_label = text.no_weights();
+    let date_labels = (text.short_date(&range.start), text.short_date(&range.end));
+
+    let area = gtk::DrawingArea::new();
+    area.set_size_request(400, 250);
+    area.connect_draw(move |area, cr| {
+        draw(area, cr, &days, &axis_label, &empty_label, &date_labels);
+        gtk::Inhibit(false)
+    });
+    area
+}
+
+#[cfg(test)]
+mod test {
+    use super::trend;
+
+    #[test]
+    fn it_smooths_weights_across_missing_days() {
+        let trend = trend(&[None, Some(80.), None, Some(90.), Some(90.)], 0.1);
+        assert_eq!(trend[0], None);
+        assert_eq!(trend[1], Some(80.));
+        assert_eq!(trend[2], Some(80.));
+        assert_eq!(trend[3], Some(81.));
+        assert!((trend[4].unwrap() - 81.9).abs() < 1e-9);
+    }
+}
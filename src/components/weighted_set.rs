@@ -0,0 +1,199 @@
+use dimensioned::si::KG;
+use emseries::{DateTimeTz, Recordable, UniqueId};
+use fitnesstrax_lib::activity::{ActivityId, ActivityKind, ActivityRegistry};
+use fitnesstrax_lib::weightedset::{ActivityType, WeightedSet, WeightedSetRecord};
+use gtk::prelude::*;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::components::activity::activity_c;
+use crate::components::basics::{labeled_widget_c, time_c, validated_text_entry_c};
+use crate::components::record_list_edit::RecordListEdit;
+use crate::components::sets_edit::{sets_list_edit_c, SetsRecord};
+use crate::errors::Error;
+use crate::i18n::{Text, UnitSystem};
+use crate::range::TimezoneDisplay;
+
+fn sets_c(sets: &[WeightedSet], text: &Text, units: &UnitSystem) -> gtk::Label {
+    let set_strs: Vec<String> = sets
+        .iter()
+        .map(|set| match set.rpe {
+            Some(rpe) => format!(
+                "{} × {} @{}",
+                set.reps,
+                text.mass(set.load, units.load),
+                rpe
+            ),
+            None => format!("{} × {}", set.reps, text.mass(set.load, units.load)),
+        })
+        .collect();
+    gtk::Label::new(Some(&set_strs.join(", ")))
+}
+
+pub fn weighted_set_c(
+    record: &WeightedSetRecord,
+    timezone: &chrono_tz::Tz,
+    activities: &ActivityRegistry,
+    text: &Text,
+    units: &UnitSystem,
+) -> gtk::Box {
+    let container = gtk::Box::new(gtk::Orientation::Horizontal, 5);
+
+    container.pack_start(
+        &time_c(&record.timestamp().0.with_timezone(timezone).time()),
+        false,
+        false,
+        5,
+    );
+    container.pack_start(
+        &activity_c(&record.activity, activities, text),
+        false,
+        false,
+        5,
+    );
+    container.pack_start(&sets_c(&record.sets, text, units), false, false, 5);
+
+    container
+}
+
+fn set_edit_c(
+    set: &WeightedSet,
+    text: &Text,
+    units: &UnitSystem,
+    on_update: Box<dyn Fn(WeightedSet)>,
+    on_remove: Box<dyn Fn()>,
+) -> gtk::Box {
+    let on_update = Rc::new(on_update);
+    let set = Rc::new(RefCell::new(set.clone()));
+    let container = gtk::Box::new(gtk::Orientation::Horizontal, 5);
+
+    let reps_entry = validated_text_entry_c(
+        set.borrow().reps,
+        Box::new(|r| format!("{}", r)),
+        Box::new(|s| match s.parse::<u32>() {
+            Ok(v) if v > 0 => Ok(v),
+            _ => Err(Error::ParseRepsError),
+        }),
+        Box::new(enclose!(set, on_update => move |val| {
+            let mut s = set.borrow_mut();
+            s.reps = val;
+            on_update(s.clone());
+        })),
+    );
+
+    let load_entry = {
+        let u1 = *units;
+        let u2 = *units;
+        validated_text_entry_c(
+            set.borrow().load,
+            Box::new(move |w| u1.render_load(*w)),
+            Box::new(move |s| u2.parse_load(s)),
+            Box::new(enclose!(set, on_update => move |val| {
+                let mut s = set.borrow_mut();
+                s.load = val;
+                on_update(s.clone());
+            })),
+        )
+    };
+
+    let rpe_entry = validated_text_entry_c(
+        set.borrow().rpe,
+        Box::new(|r| r.map(|v| format!("{}", v)).unwrap_or(String::from(""))),
+        Box::new(|s| {
+            if s.is_empty() {
+                Ok(None)
+            } else {
+                match s.parse::<f64>() {
+                    Ok(v) if (1.0..=10.0).contains(&v) => Ok(Some(v)),
+                    _ => Err(Error::ParseRpeError),
+                }
+            }
+        }),
+        Box::new(enclose!(set, on_update => move |val| {
+            let mut s = set.borrow_mut();
+            s.rpe = val;
+            on_update(s.clone());
+        })),
+    );
+
+    let remove_button = gtk::Button::new_with_label(&text.remove());
+    remove_button.connect_clicked(move |_| on_remove());
+
+    container.pack_start(&labeled_widget_c(&text.reps(), reps_entry), false, false, 5);
+    container.pack_start(&load_entry, false, false, 5);
+    container.pack_start(
+        &gtk::Label::new(Some(&text.mass_label(units.load))),
+        false,
+        false,
+        5,
+    );
+    container.pack_start(&labeled_widget_c(&text.rpe(), rpe_entry), false, false, 5);
+    container.pack_start(&remove_button, false, false, 5);
+
+    container
+}
+
+impl SetsRecord for WeightedSetRecord {
+    type Set = WeightedSet;
+
+    const KIND: ActivityKind = ActivityKind::WeightedSet;
+
+    fn add_label(text: &Text) -> String {
+        text.add_weighted_set_workout()
+    }
+
+    fn new_record(timestamp: DateTimeTz) -> WeightedSetRecord {
+        WeightedSetRecord::new(timestamp, ActivityType::Squat.into(), vec![], None)
+    }
+
+    fn next_set(last: Option<&WeightedSet>) -> WeightedSet {
+        last.cloned().unwrap_or(WeightedSet {
+            reps: 5,
+            load: 0.0 * KG,
+            rpe: None,
+        })
+    }
+
+    fn activity(&self) -> &ActivityId {
+        &self.activity
+    }
+
+    fn set_activity(&mut self, activity: ActivityId) {
+        self.activity = activity;
+    }
+
+    fn set_timestamp(&mut self, timestamp: DateTimeTz) {
+        self.timestamp = timestamp;
+    }
+
+    fn sets(&self) -> &[WeightedSet] {
+        &self.sets
+    }
+
+    fn sets_mut(&mut self) -> &mut Vec<WeightedSet> {
+        &mut self.sets
+    }
+}
+
+pub fn weighted_set_edit_c(
+    date: chrono::Date<chrono_tz::Tz>,
+    records: Vec<(&UniqueId, &WeightedSetRecord)>,
+    timezone: chrono_tz::Tz,
+    timezone_display: TimezoneDisplay,
+    activities: ActivityRegistry,
+    text: Text,
+    units: UnitSystem,
+) -> RecordListEdit<WeightedSetRecord> {
+    let text_ = text.clone();
+    sets_list_edit_c(
+        date,
+        records,
+        timezone,
+        timezone_display,
+        activities,
+        text,
+        Rc::new(move |set, on_update, on_remove| {
+            set_edit_c(set, &text_, &units, on_update, on_remove)
+        }),
+    )
+}
@@ -0,0 +1,62 @@
+use emseries::Recordable;
+use fitnesstrax_lib::activity::ActivityRegistry;
+use fitnesstrax_lib::import::Parsed;
+use fitnesstrax_lib::TraxRecord;
+use gtk::prelude::*;
+use std::path::PathBuf;
+
+use crate::components::basics::date_c;
+use crate::components::time_distance_row::time_distance_c;
+use crate::i18n::{Text, UnitSystem};
+
+/// Show the workouts read from each file, along with the entries which could not be read, so
+/// that they can be checked before anything gets added to the series.
+pub fn workout_preview_c(
+    files: &[(PathBuf, Parsed)],
+    timezone: &chrono_tz::Tz,
+    activities: &ActivityRegistry,
+    text: &Text,
+    units: &UnitSystem,
+) -> gtk::Box {
+    let container = gtk::Box::new(gtk::Orientation::Vertical, 5);
+
+    for (path, parsed) in files {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.to_string_lossy().into_owned());
+        let file_box = gtk::Box::new(gtk::Orientation::Vertical, 5);
+
+        for (_, record) in parsed.records.iter() {
+            if let TraxRecord::TimeDistance(ref rec) = record {
+                let row = gtk::Box::new(gtk::Orientation::Horizontal, 5);
+                row.pack_start(
+                    &date_c(&rec.timestamp().0.with_timezone(timezone).date()),
+                    false,
+                    false,
+                    5,
+                );
+                row.pack_start(
+                    &time_distance_c(rec, timezone, activities, text, units),
+                    false,
+                    false,
+                    5,
+                );
+                file_box.pack_start(&row, false, false, 5);
+            }
+        }
+
+        for unmapped in parsed.unmapped.iter() {
+            let label = gtk::Label::new(Some(&format!("{}", unmapped)));
+            label.set_xalign(0.0);
+            file_box.pack_start(&label, false, false, 5);
+        }
+
+        let frame = gtk::Frame::new(Some(&name));
+        frame.add(&file_box);
+        container.pack_start(&frame, false, false, 5);
+    }
+
+    container.show_all();
+    container
+}
@@ -12,13 +12,15 @@ use std::ops::Deref;
 use std::path;
 use unic_langid::LanguageIdentifier;
 
-use crate::i18n::UnitSystem;
+use crate::i18n::{DistanceUnit, MassUnit, UnitSystem};
+use crate::range::TimezoneDisplay;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Configuration {
     pub series_path: Option<path::PathBuf>,
     pub language: LanguageId,
     pub timezone: chrono_tz::Tz,
+    pub timezone_display: TimezoneDisplay,
     pub units: UnitSystem,
 }
 
@@ -32,6 +34,7 @@ impl Configuration {
                 series_path: None,
                 language: LanguageId("en".parse().expect("hard-coded en should always parse")),
                 timezone: chrono_tz::Etc::UTC,
+                timezone_display: TimezoneDisplay::Home,
                 units: UnitSystem::SI,
             },
         }
@@ -45,6 +48,20 @@ impl Configuration {
         let _ = config_file.write(s.as_bytes());
     }
 
+    /// Guess the language, timezone and units from the system locale and timezone. This is where
+    /// the first-run assistant starts from.
+    pub fn from_system() -> Configuration {
+        let messages = locale_var(&["LC_ALL", "LC_MESSAGES", "LANG"]);
+        let measurement = locale_var(&["LC_ALL", "LC_MEASUREMENT", "LANG"]);
+        Configuration {
+            series_path: None,
+            language: language_for_locale(messages.as_deref().unwrap_or("")),
+            timezone: system_timezone().unwrap_or(chrono_tz::Etc::UTC),
+            timezone_display: TimezoneDisplay::Home,
+            units: units_for_locale(measurement.as_deref().unwrap_or("")),
+        }
+    }
+
     pub fn load_from_gsettings() -> Configuration {
         let s = Settings::new("com.luminescent-dreams.fitnesstrax");
 
@@ -62,19 +79,32 @@ impl Configuration {
                     .parse()
                     .expect("language strings"),
             ),
-            timezone: s
-                .get_string("timezone")
-                .unwrap()
-                .as_str()
-                .parse::<chrono_tz::Tz>()
-                .unwrap(),
-            units: UnitSystem::try_from(s.get_string("units").unwrap().as_str()).unwrap(),
+            timezone: timezone_setting(&s),
+            timezone_display: TimezoneDisplay::try_from(
+                s.get_string("timezone-display").unwrap().as_str(),
+            )
+            .unwrap(),
+            units: units_setting(&s),
         }
     }
 
-    pub fn save_to_gsettings(&self) {
+    /// Save every setting at once. If any of them cannot be written, none of them are.
+    pub fn save_to_gsettings(&self) -> Result<(), glib::BoolError> {
         let s = Settings::new("com.luminescent-dreams.fitnesstrax");
         s.delay();
+        match self.write_gsettings(&s) {
+            Ok(()) => {
+                s.apply();
+                Ok(())
+            }
+            Err(err) => {
+                s.revert();
+                Err(err)
+            }
+        }
+    }
+
+    fn write_gsettings(&self, s: &Settings) -> Result<(), glib::BoolError> {
         s.set_string(
             "series-path",
             &self
@@ -82,14 +112,122 @@ impl Configuration {
                 .clone()
                 .map(|p| String::from(p.to_string_lossy()))
                 .unwrap_or(String::from("")),
-        );
-        s.set_string("language", self.language.get_language());
-        s.set_string("timezone", self.timezone.name());
-        s.set_string("units", &String::from(&self.units));
-        s.apply();
+        )?;
+        s.set_string("language", self.language.get_language())?;
+        s.set_string("timezone", self.timezone.name())?;
+        s.set_string("timezone-display", &String::from(&self.timezone_display))?;
+        s.set_string("body-mass-units", &String::from(&self.units.body_mass))?;
+        s.set_string("distance-units", &String::from(&self.units.distance))?;
+        s.set_string(
+            "swim-distance-units",
+            &String::from(&self.units.swim_distance),
+        )?;
+        s.set_string("load-units", &String::from(&self.units.load))
+    }
+}
+
+/// The first of the locale variables which is set, in the order that they take precedence.
+fn locale_var(names: &[&str]) -> Option<String> {
+    names
+        .iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|value| !value.is_empty())
+}
+
+/// Split a locale such as `en_US.UTF-8` into its language and region.
+fn split_locale(locale: &str) -> (&str, Option<&str>) {
+    let locale = locale.split(&['.', '@'][..]).next().unwrap_or("");
+    let mut parts = locale.splitn(2, '_');
+    (parts.next().unwrap_or(""), parts.next())
+}
+
+/// The language of a locale, when there is a translation for it, and English otherwise.
+fn language_for_locale(locale: &str) -> LanguageId {
+    let language = match split_locale(locale).0 {
+        "eo" => "eo",
+        _ => "en",
+    };
+    LanguageId(
+        language
+            .parse()
+            .expect("hard-coded languages should always parse"),
+    )
+}
+
+/// The units that people in the region of a locale usually use.
+fn units_for_locale(locale: &str) -> UnitSystem {
+    match split_locale(locale).1 {
+        Some("US") | Some("LR") | Some("MM") => UnitSystem::USA,
+        Some("GB") => UnitSystem::UK,
+        _ => UnitSystem::SI,
     }
 }
 
+/// The timezone that the user has chosen. Until they choose one, or if theirs is not in the tz
+/// database any more, the system timezone is a better guess than the schema default.
+fn timezone_setting(s: &Settings) -> chrono_tz::Tz {
+    let chosen = s
+        .get_user_value("timezone")
+        .and_then(|_| s.get_string("timezone"))
+        .and_then(|tz| tz.as_str().parse().ok());
+    chosen
+        .or_else(system_timezone)
+        .unwrap_or(chrono_tz::Etc::UTC)
+}
+
+/// The units that the user has chosen. Older versions had a single `units` key instead of one key
+/// for each kind of measurement, so until any of those get saved, the old choice decides them all.
+fn units_setting(s: &Settings) -> UnitSystem {
+    let unit_keys = [
+        "body-mass-units",
+        "distance-units",
+        "swim-distance-units",
+        "load-units",
+    ];
+    if unit_keys.iter().all(|key| s.get_user_value(key).is_none()) {
+        let legacy = s
+            .get_user_value("units")
+            .and_then(|_| s.get_string("units"))
+            .and_then(|units| UnitSystem::try_from(units.as_str()).ok());
+        if let Some(units) = legacy {
+            return units;
+        }
+    }
+    UnitSystem {
+        body_mass: MassUnit::try_from(s.get_string("body-mass-units").unwrap().as_str()).unwrap(),
+        distance: DistanceUnit::try_from(s.get_string("distance-units").unwrap().as_str()).unwrap(),
+        swim_distance: DistanceUnit::try_from(
+            s.get_string("swim-distance-units").unwrap().as_str(),
+        )
+        .unwrap(),
+        load: MassUnit::try_from(s.get_string("load-units").unwrap().as_str()).unwrap(),
+    }
+}
+
+/// The system timezone, from the TZ variable or else from the link that `/etc/localtime` makes
+/// into the zoneinfo database.
+pub fn system_timezone() -> Option<chrono_tz::Tz> {
+    if let Ok(tz) = env::var("TZ") {
+        if let Ok(tz) = tz.trim_start_matches(':').parse() {
+            return Some(tz);
+        }
+    }
+    std::fs::read_link("/etc/localtime")
+        .ok()
+        .and_then(|target| zoneinfo_timezone(&target))
+}
+
+/// Find the timezone name in a path into the zoneinfo database, such as
+/// `/usr/share/zoneinfo/America/New_York`.
+fn zoneinfo_timezone(path: &path::Path) -> Option<chrono_tz::Tz> {
+    let path = path.to_string_lossy();
+    let name = &path[path.find("zoneinfo/")? + "zoneinfo/".len()..];
+    let name = name
+        .trim_start_matches("posix/")
+        .trim_start_matches("right/");
+    name.parse().ok()
+}
+
 #[derive(Clone, Debug)]
 pub struct LanguageId(LanguageIdentifier);
 
@@ -140,3 +278,32 @@ impl<'de> Deserialize<'de> for LanguageId {
         deserializer.deserialize_str(LanguageIdVisitor)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_guesses_settings_from_the_locale() {
+        assert_eq!(language_for_locale("eo.UTF-8").get_language(), "eo");
+        assert_eq!(language_for_locale("de_DE@euro").get_language(), "en");
+        assert_eq!(language_for_locale("").get_language(), "en");
+        assert_eq!(units_for_locale("en_US.UTF-8"), UnitSystem::USA);
+        assert_eq!(units_for_locale("en_GB"), UnitSystem::UK);
+        assert_eq!(units_for_locale("fr_CA.UTF-8"), UnitSystem::SI);
+        assert_eq!(units_for_locale("C"), UnitSystem::SI);
+    }
+
+    #[test]
+    fn it_reads_the_timezone_from_a_zoneinfo_path() {
+        assert_eq!(
+            zoneinfo_timezone(path::Path::new("/usr/share/zoneinfo/America/New_York")),
+            Some(chrono_tz::America::New_York)
+        );
+        assert_eq!(
+            zoneinfo_timezone(path::Path::new("../usr/share/zoneinfo/posix/Europe/Paris")),
+            Some(chrono_tz::Europe::Paris)
+        );
+        assert_eq!(zoneinfo_timezone(path::Path::new("/etc/timezone")), None);
+    }
+}
@@ -1,30 +1,75 @@
 use chrono::Utc;
 use glib::Sender;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use crate::config::{Configuration, LanguageId};
 use crate::errors::{Error, Result};
 use crate::i18n::{Text, UnitSystem};
-use crate::range::Range;
+use crate::range::{day_start, Range, TimezoneDisplay};
 use crate::settings::Settings;
 use crate::types::DateRange;
-use emseries::{DateTimeTz, UniqueId};
+use dimensioned::si::Kilogram;
+use emseries::{DateTimeTz, Recordable, UniqueId};
+use fitnesstrax_lib::activity::{Activity, ActivityId, ActivityKind, ActivityRegistry};
+use fitnesstrax_lib::csv::Table;
+use fitnesstrax_lib::goals::{Goal, Progress};
+use fitnesstrax_lib::import::{ImportReport, Parsed};
+use fitnesstrax_lib::personal_records::PersonalRecords;
+use fitnesstrax_lib::streaks::{Condition, Streak};
+use fitnesstrax_lib::timedistance::ActivityType;
 use fitnesstrax_lib::{Trax, TraxRecord};
+use std::collections::HashMap;
 
 #[derive(Clone, Debug)]
 pub enum Message {
     ChangeSeriesFile {
         range: DateRange,
         records: Vec<(UniqueId, TraxRecord)>,
+        activities: ActivityRegistry,
     },
+    ChangeActivities(ActivityRegistry),
     ChangeRange {
         range: DateRange,
         records: Vec<(UniqueId, TraxRecord)>,
     },
     ChangeLanguage(Text),
     ChangeTimezone(chrono_tz::Tz),
+    ChangeTimezoneDisplay(TimezoneDisplay),
     ChangeUnits(UnitSystem),
+    GoalsUpdated(Vec<(Goal, Progress)>),
+    /// An import has finished, with its summary for the person to read.
+    ImportFinished(String),
+    /// Some changes to the records could not be saved, with the reason for the person to read.
+    RecordsNotSaved(String),
     RecordsUpdated(Vec<(UniqueId, TraxRecord)>),
+    StreaksUpdated(Vec<(Condition, Streak)>),
+}
+
+/// How the first-run assistant gets a series: by opening one that already exists, or by creating
+/// a new one.
+#[derive(Clone, Debug)]
+pub enum SeriesChoice {
+    Open(PathBuf),
+    Create(PathBuf),
+}
+
+/// The records for the activity calendar on the Statistics page. The calendar covers the last
+/// year, whatever range is selected, so that picking a day from it does not move the calendar.
+#[derive(Clone, Debug)]
+pub struct Calendar {
+    pub year: DateRange,
+    pub records: Vec<(UniqueId, TraxRecord)>,
+}
+
+/// Read the CSV tables in a directory. Reading does not need the application, so that big
+/// imports can be read on a worker thread without holding it.
+pub fn read_csv(dir: &Path, activities: &ActivityRegistry) -> Result<Vec<(Table, Parsed)>> {
+    fitnesstrax_lib::csv::read(dir, activities).map_err(Error::TraxError)
+}
+
+/// Read an Apple Health export. Like `read_csv`, this can run on a worker thread.
+pub fn read_apple_health(path: &Path, timezone: chrono_tz::Tz) -> Result<Parsed> {
+    fitnesstrax_lib::import::apple_health::read_file(path, timezone).map_err(Error::TraxError)
 }
 
 pub struct Application {
@@ -40,6 +85,8 @@ pub enum State {
 pub struct Unconfigured {
     settings: Settings,
     series_path: Option<PathBuf>,
+    /// Why the series in the settings could not be opened when the application started.
+    error: Option<Error>,
 }
 
 pub struct Configured {
@@ -50,6 +97,14 @@ pub struct Configured {
 }
 
 impl State {
+    /// Why the series in the settings could not be opened, if it could not.
+    pub fn series_error(&self) -> Option<&Error> {
+        match self {
+            State::Unconfigured(Unconfigured { error, .. }) => error.as_ref(),
+            State::Configured(_) => None,
+        }
+    }
+
     pub fn series_path(&self) -> Option<&PathBuf> {
         match self {
             State::Unconfigured(Unconfigured { series_path, .. }) => series_path.as_ref(),
@@ -78,6 +133,10 @@ impl State {
         }
     }
 
+    pub fn timezone_display(&self) -> TimezoneDisplay {
+        self.settings().timezone_display
+    }
+
     pub fn units(&self) -> &UnitSystem {
         match self {
             State::Unconfigured(Unconfigured { settings, .. }) => &settings.units,
@@ -105,14 +164,25 @@ impl State {
         }
     }
 
-    fn set_units(&mut self, units_str: &str) {
+    fn set_timezone_display(&mut self, timezone_display: TimezoneDisplay) {
+        match self {
+            State::Unconfigured(Unconfigured {
+                ref mut settings, ..
+            }) => settings.set_timezone_display(timezone_display),
+            State::Configured(Configured {
+                ref mut settings, ..
+            }) => settings.set_timezone_display(timezone_display),
+        }
+    }
+
+    fn set_units(&mut self, units: UnitSystem) {
         match self {
             State::Unconfigured(Unconfigured {
                 ref mut settings, ..
-            }) => settings.set_units(units_str),
+            }) => settings.set_units(units),
             State::Configured(Configured {
                 ref mut settings, ..
-            }) => settings.set_units(units_str),
+            }) => settings.set_units(units),
         }
     }
 }
@@ -122,39 +192,100 @@ impl Configured {
         self.range.clone()
     }
 
+    pub fn activities(&self) -> &ActivityRegistry {
+        self.trax.activities()
+    }
+
     pub fn get_history(&self) -> Result<Vec<(UniqueId, TraxRecord)>> {
-        let start_time = DateTimeTz(
-            self.range
-                .start
-                .and_hms(0, 0, 0)
-                .with_timezone(&self.settings.timezone),
-        );
+        self.get_records(&self.range)
+    }
+
+    pub fn calendar(&self) -> Result<Calendar> {
+        let today = Utc::now().with_timezone(&self.settings.timezone).date();
+        let year = Range::new(today - chrono::Duration::weeks(52), today);
+        let records = self.get_records(&year)?;
+        Ok(Calendar { year, records })
+    }
+
+    /// Get the records for any range of dates, without changing the selected range. Records
+    /// belong to days according to the timezone display setting, so a record from another
+    /// timezone may fall outside of the times that the range covers at home.
+    pub fn get_records(&self, range: &DateRange) -> Result<Vec<(UniqueId, TraxRecord)>> {
+        let display = self.settings.timezone_display;
+        let margin = display.search_margin();
+        let start_time =
+            DateTimeTz(day_start(&(range.start - margin)).with_timezone(&self.settings.timezone));
         let end_time = DateTimeTz(
-            (self.range.end + chrono::Duration::days(1))
-                .and_hms(0, 0, 0)
+            day_start(&(range.end + chrono::Duration::days(1) + margin))
                 .with_timezone(&self.settings.timezone),
         );
+        let (first, last) = (range.start.naive_local(), range.end.naive_local());
         self.trax
             .get_history(start_time, end_time)
             .map(|v| {
                 v.iter()
+                    .filter(|(_, record)| {
+                        let date = display.date(&record.timestamp(), &self.settings.timezone);
+                        first <= date && date <= last
+                    })
                     .map(|(ref id, ref record)| ((*id).clone(), (*record).clone()))
                     .collect()
             })
             .map_err(|err| Error::TraxError(err))
     }
 
+    /// Evaluate every goal as of today, counting days in the same way as the history.
+    pub fn goal_progress(&self) -> Result<Vec<(Goal, Progress)>> {
+        let today = Utc::now().with_timezone(&self.settings.timezone).date();
+        self.trax
+            .goal_progress(today, self.settings.timezone_display)
+            .map_err(Error::TraxError)
+    }
+
+    /// Find the streaks for every tracked condition, counting days in the same way as the history.
+    pub fn streaks(&self) -> Result<Vec<(Condition, Streak)>> {
+        let today = Utc::now().with_timezone(&self.settings.timezone).date();
+        self.trax
+            .streaks(today, self.settings.timezone_display)
+            .map_err(Error::TraxError)
+    }
+
+    /// The most recently recorded weight, which is where a new weight goal starts from.
+    pub fn latest_weight(&self) -> Result<Option<Kilogram<f64>>> {
+        let records = self.trax.all_records().map_err(Error::TraxError)?;
+        Ok(records
+            .into_iter()
+            .filter_map(|(_, record)| match record {
+                TraxRecord::Weight(ref rec) => Some((rec.timestamp(), rec.weight)),
+                _ => None,
+            })
+            .max_by_key(|(timestamp, _)| timestamp.clone())
+            .map(|(_, weight)| weight))
+    }
+
+    /// Find the personal records over the whole series, not just the selected range.
+    pub fn personal_records(&self) -> Result<PersonalRecords> {
+        self.trax.personal_records().map_err(Error::TraxError)
+    }
+
     pub fn save_records(
         &mut self,
         updated_records: Vec<(UniqueId, TraxRecord)>,
         new_records: Vec<TraxRecord>,
-    ) {
+        deleted_records: Vec<UniqueId>,
+    ) -> Result<()> {
         for (id, record) in updated_records {
-            let _ = self.trax.replace_record(id, record);
+            self.trax
+                .replace_record(id, record)
+                .map_err(Error::TraxError)?;
         }
         for record in new_records {
-            let _ = self.trax.add_record(record);
+            self.trax.add_record(record).map_err(Error::TraxError)?;
+        }
+        for id in deleted_records {
+            self.trax.remove_record(&id).map_err(Error::TraxError)?;
         }
+        Ok(())
     }
 
     pub fn set_range(&mut self, range: DateRange) {
@@ -169,6 +300,10 @@ impl Configured {
         &self.settings.timezone
     }
 
+    pub fn timezone_display(&self) -> TimezoneDisplay {
+        self.settings.timezone_display
+    }
+
     pub fn units(&self) -> &UnitSystem {
         &self.settings.units
     }
@@ -185,21 +320,25 @@ impl Application {
 
         let settings = Settings::from_config(&config);
 
-        let state = if let Some(ref path) = config.series_path {
-            State::Configured(Configured {
-                trax: fitnesstrax_lib::Trax::new(fitnesstrax_lib::Params {
-                    series_path: path.clone(),
-                })
-                .unwrap(),
+        // A series that can no longer be opened leaves the application unconfigured, so that
+        // another one can be chosen in the settings, which show why.
+        let trax = config.series_path.as_ref().map(|path| {
+            fitnesstrax_lib::Trax::open(fitnesstrax_lib::Params {
                 series_path: path.clone(),
+            })
+        });
+        let state = match (trax, config.series_path) {
+            (Some(Ok(trax)), Some(path)) => State::Configured(Configured {
+                trax,
+                series_path: path,
                 range,
                 settings,
-            })
-        } else {
-            State::Unconfigured(Unconfigured {
-                series_path: None,
+            }),
+            (trax, series_path) => State::Unconfigured(Unconfigured {
+                series_path,
                 settings,
-            })
+                error: trax.and_then(|trax| trax.err()).map(Error::TraxError),
+            }),
         };
 
         Ok(Application { channel, state })
@@ -209,22 +348,72 @@ impl Application {
         &self.state
     }
 
-    fn save_configuration(&self) {
+    fn save_configuration(&self) -> Result<()> {
         let config = Configuration {
             series_path: self.state.series_path().map(|p| p.clone()),
             language: LanguageId::from(self.state.settings().text.language_id()),
             timezone: self.state.settings().timezone.clone(),
+            timezone_display: self.state.timezone_display(),
             units: self.state.settings().units.clone(),
         };
-        config.save_to_gsettings();
+        config.save_to_gsettings().map_err(Error::SettingsNotSaved)
     }
 
-    pub fn set_series_path(&mut self, path: PathBuf) {
-        let trax = fitnesstrax_lib::Trax::new(fitnesstrax_lib::Params {
+    /// Switch to the existing series at `path`.
+    pub fn set_series_path(&mut self, path: PathBuf) -> Result<()> {
+        let trax = fitnesstrax_lib::Trax::open(fitnesstrax_lib::Params {
             series_path: path.clone(),
-        })
-        .unwrap();
+        })?;
+        self.use_series(trax, path)
+    }
+
+    /// Create a new, empty series at `path` and switch to it.
+    pub fn create_series(&mut self, path: PathBuf) -> Result<()> {
+        let trax = fitnesstrax_lib::Trax::create(fitnesstrax_lib::Params {
+            series_path: path.clone(),
+        })?;
+        self.use_series(trax, path)
+    }
+
+    /// Finish the first run: take on the settings, open or create the series, and record the
+    /// starting weight, if there is one. Nothing changes if the series cannot be opened or created.
+    pub fn configure(
+        &mut self,
+        series: SeriesChoice,
+        settings: Settings,
+        weight: Option<Kilogram<f64>>,
+    ) -> Result<()> {
+        let (mut trax, path) = match series {
+            SeriesChoice::Open(path) => (
+                fitnesstrax_lib::Trax::open(fitnesstrax_lib::Params {
+                    series_path: path.clone(),
+                })?,
+                path,
+            ),
+            SeriesChoice::Create(path) => (
+                fitnesstrax_lib::Trax::create(fitnesstrax_lib::Params {
+                    series_path: path.clone(),
+                })?,
+                path,
+            ),
+        };
+        if let Some(weight) = weight {
+            let now = DateTimeTz(Utc::now().with_timezone(&settings.timezone));
+            trax.add_record(TraxRecord::weight(now, weight))
+                .map_err(Error::TraxError)?;
+        }
+
+        match self.state {
+            State::Unconfigured(ref mut state) => state.settings = settings,
+            State::Configured(ref mut state) => state.settings = settings,
+        }
+        self.send_notifications(Message::ChangeLanguage(self.state.text().clone()));
+        self.use_series(trax, path)
+    }
 
+    /// Switch to `trax`. The series is in use even if the settings cannot be saved, but it will
+    /// not be opened again on the next start.
+    fn use_series(&mut self, trax: fitnesstrax_lib::Trax, path: PathBuf) -> Result<()> {
         let range = Range::new(
             Utc::now()
                 .with_timezone(&self.state.settings().timezone)
@@ -255,63 +444,214 @@ impl Application {
                 settings: settings.clone(),
             }),
         };
-        self.save_configuration();
+        let saved = self.save_configuration();
 
         if let State::Configured(ref cfg) = self.state {
             self.send_notifications(Message::ChangeSeriesFile {
                 range: cfg.range(),
                 records: cfg.get_history().unwrap(),
+                activities: cfg.activities().clone(),
             });
         }
+        saved
     }
 
-    pub fn set_language(&mut self, language_str: &str) {
+    /* The setters below change the settings for this run even when they cannot be saved, and
+     * return the error so that the person knows that the change will not last. */
+
+    pub fn set_language(&mut self, language_str: &str) -> Result<()> {
         self.state.set_language(language_str);
-        self.save_configuration();
+        let saved = self.save_configuration();
         if let State::Configured(ref state) = self.state {
             self.send_notifications(Message::ChangeLanguage(state.settings.text.clone()));
         }
+        saved
     }
 
-    pub fn set_timezone(&mut self, timezone: chrono_tz::Tz) {
+    pub fn set_timezone(&mut self, timezone: chrono_tz::Tz) -> Result<()> {
         self.state.set_timezone(timezone);
-        self.save_configuration();
+        let saved = self.save_configuration();
         if let State::Configured(ref state) = self.state {
             self.send_notifications(Message::ChangeTimezone(state.settings.timezone.clone()));
         }
+        saved
+    }
+
+    pub fn set_timezone_display(&mut self, timezone_display: TimezoneDisplay) -> Result<()> {
+        self.state.set_timezone_display(timezone_display);
+        let saved = self.save_configuration();
+        if let State::Configured(_) = self.state {
+            self.send_notifications(Message::ChangeTimezoneDisplay(timezone_display));
+        }
+        saved
     }
 
-    pub fn set_units(&mut self, units_str: &str) {
-        self.state.set_units(units_str);
-        self.save_configuration();
+    pub fn set_units(&mut self, units: UnitSystem) -> Result<()> {
+        self.state.set_units(units);
+        let saved = self.save_configuration();
         if let State::Configured(ref state) = self.state {
             self.send_notifications(Message::ChangeUnits(state.settings.units.clone()));
         }
+        saved
+    }
+
+    pub fn add_activity(&mut self, name: &str, kind: ActivityKind) -> Result<()> {
+        match self.state {
+            State::Unconfigured(_) => Err(Error::SeriesNotOpen),
+            State::Configured(ref mut state) => {
+                let activity = Activity::new(
+                    ActivityId::new(&UniqueId::new().to_string()),
+                    name,
+                    kind,
+                    HashMap::new(),
+                );
+                state.trax.add_activity(activity).map_err(Error::TraxError)
+            }
+        }?;
+        if let State::Configured(ref state) = self.state {
+            self.send_notifications(Message::ChangeActivities(state.activities().clone()));
+        }
+        Ok(())
+    }
+
+    pub fn add_goal(&mut self, goal: Goal) -> Result<()> {
+        match self.state {
+            State::Unconfigured(_) => Err(Error::SeriesNotOpen),
+            State::Configured(ref mut state) => state.trax.add_goal(goal).map_err(Error::TraxError),
+        }?;
+        self.send_goals()
+    }
+
+    pub fn remove_goal(&mut self, idx: usize) -> Result<()> {
+        match self.state {
+            State::Unconfigured(_) => Err(Error::SeriesNotOpen),
+            State::Configured(ref mut state) => {
+                state.trax.remove_goal(idx).map_err(Error::TraxError)
+            }
+        }?;
+        self.send_goals()
+    }
+
+    fn send_goals(&self) -> Result<()> {
+        if let State::Configured(ref state) = self.state {
+            self.send_notifications(Message::GoalsUpdated(state.goal_progress()?));
+        }
+        Ok(())
+    }
+
+    pub fn add_streak_condition(&mut self, condition: Condition) -> Result<()> {
+        match self.state {
+            State::Unconfigured(_) => Err(Error::SeriesNotOpen),
+            State::Configured(ref mut state) => state
+                .trax
+                .add_streak_condition(condition)
+                .map_err(Error::TraxError),
+        }?;
+        self.send_streaks()
+    }
+
+    pub fn remove_streak_condition(&mut self, idx: usize) -> Result<()> {
+        match self.state {
+            State::Unconfigured(_) => Err(Error::SeriesNotOpen),
+            State::Configured(ref mut state) => state
+                .trax
+                .remove_streak_condition(idx)
+                .map_err(Error::TraxError),
+        }?;
+        self.send_streaks()
+    }
+
+    fn send_streaks(&self) -> Result<()> {
+        if let State::Configured(ref state) = self.state {
+            self.send_notifications(Message::StreaksUpdated(state.streaks()?));
+        }
+        Ok(())
     }
 
     pub fn save_records(
         &mut self,
         updated_records: Vec<(UniqueId, TraxRecord)>,
         new_records: Vec<TraxRecord>,
+        deleted_records: Vec<UniqueId>,
     ) -> Result<()> {
-        match self.state {
-            State::Unconfigured(_) => Err(Error::SeriesNotOpen),
+        let result = match self.state {
+            State::Unconfigured(_) => return Err(Error::SeriesNotOpen),
             State::Configured(ref mut state) => {
-                state.save_records(updated_records, new_records);
-                Ok(())
+                state.save_records(updated_records, new_records, deleted_records)
             }
-        }?;
+        };
+        // Some of the changes may have been saved before one failed, so the window gets the
+        // records as they are now either way.
+        if let State::Configured(ref state) = self.state {
+            self.send_notifications(Message::RecordsUpdated(
+                state.get_history().unwrap().clone(),
+            ));
+        }
+        if let Err(ref err) = result {
+            self.send_notifications(Message::RecordsNotSaved(
+                self.state.text().records_not_saved(err),
+            ));
+        }
+        result
+    }
+
+    pub fn export_csv(&self, dir: &Path) -> Result<()> {
         match self.state {
             State::Unconfigured(_) => Err(Error::SeriesNotOpen),
             State::Configured(ref state) => {
-                self.send_notifications(Message::RecordsUpdated(
-                    state.get_history().unwrap().clone(),
-                ));
-                Ok(())
+                fitnesstrax_lib::csv::export(&state.trax, dir).map_err(Error::TraxError)
             }
         }
     }
 
+    /// Read FIT, GPX and TCX files for previewing. Nothing is added to the series until the
+    /// records are passed to `import_records`.
+    pub fn read_workout_files(&self, paths: &[PathBuf]) -> Result<Vec<(PathBuf, Parsed)>> {
+        match self.state {
+            State::Unconfigured(_) => Err(Error::SeriesNotOpen),
+            State::Configured(ref state) => paths
+                .iter()
+                .map(|path| {
+                    fitnesstrax_lib::import::read_workout_file(
+                        path,
+                        state.settings.timezone,
+                        &ActivityType::Running.into(),
+                    )
+                    .map(|parsed| (path.clone(), parsed))
+                    .map_err(Error::TraxError)
+                })
+                .collect(),
+        }
+    }
+
+    /// Import the records read from any number of sources, pairing each report with the name of
+    /// its source.
+    pub fn import_records<N>(
+        &mut self,
+        sources: Vec<(N, Parsed)>,
+    ) -> Result<Vec<(N, ImportReport)>> {
+        let reports = match self.state {
+            State::Unconfigured(_) => Err(Error::SeriesNotOpen),
+            State::Configured(ref mut state) => sources
+                .into_iter()
+                .map(|(name, parsed)| {
+                    fitnesstrax_lib::import::import_records(&mut state.trax, parsed)
+                        .map(|report| (name, report))
+                        .map_err(Error::TraxError)
+                })
+                .collect::<Result<Vec<(N, ImportReport)>>>(),
+        }?;
+        if let State::Configured(ref state) = self.state {
+            self.send_notifications(Message::RecordsUpdated(state.get_history()?));
+        }
+        Ok(reports)
+    }
+
+    /// Pass the summary of an import which ran on a worker thread back to the window.
+    pub fn report_import(&self, summary: String) {
+        self.send_notifications(Message::ImportFinished(summary));
+    }
+
     pub fn set_range(&mut self, range: DateRange) -> Result<()> {
         match self.state {
             State::Unconfigured(_) => Err(Error::SeriesNotOpen),
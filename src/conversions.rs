@@ -1,7 +1,10 @@
 use chrono::NaiveTime;
 use dimensioned::si;
 
+use std::convert::TryFrom;
+
 use crate::errors::Error;
+use crate::formats::Duration;
 use crate::i18n::UnitSystem;
 
 pub fn render_duration(inp: &si::Second<f64>) -> String {
@@ -21,36 +24,11 @@ pub fn render_duration(inp: &si::Second<f64>) -> String {
 }
 
 pub fn parse_duration(inp: &str) -> Result<Option<si::Second<f64>>, Error> {
-    let parts: Vec<&str> = inp.split(":").collect();
-    match parts.len() {
-        3 => {
-            let seconds = parts[2]
-                .parse::<f64>()
-                .map_err(|_| Error::ParseDurationError)?;
-            let minutes = parts[1]
-                .parse::<f64>()
-                .map_err(|_| Error::ParseDurationError)?;
-            let hours = parts[0]
-                .parse::<f64>()
-                .map_err(|_| Error::ParseDurationError)?;
-            Ok(Some((seconds + minutes * 60.0 + hours * 3600.0) * si::S))
-        }
-        2 => {
-            let seconds = parts[1]
-                .parse::<f64>()
-                .map_err(|_| Error::ParseDurationError)?;
-            let minutes = parts[0]
-                .parse::<f64>()
-                .map_err(|_| Error::ParseDurationError)?;
-            Ok(Some((seconds + minutes * 60.0) * si::S))
-        }
-        1 => parts[0]
-            .parse::<f64>()
-            .map(|f| Some(f * si::S))
-            .map_err(|_| Error::ParseDurationError),
-        0 => Ok(None),
-        _ => Err(Error::ParseDurationError),
+    if inp.len() == 0 {
+        return Ok(None);
     }
+
+    Duration::try_from(inp).map(|d| Some(d.extract()))
 }
 
 pub fn render_distance(inp: &si::Meter<f64>, units: &UnitSystem, display_units: bool) -> String {
@@ -71,10 +49,5 @@ pub fn parse_distance(inp: &str, units: &UnitSystem) -> Result<Option<si::Meter<
         return Ok(None);
     }
 
-    inp.parse::<f64>()
-        .map(|v| match units {
-            UnitSystem::SI => Some(v * 1000.0 * si::M),
-            UnitSystem::USA => Some(v * si::MI),
-        })
-        .map_err(|_| Error::ParseDistanceError)
+    units.parse_distance(inp).map(Some)
 }
@@ -5,13 +5,17 @@ use std::result;
 
 #[derive(Debug)]
 pub enum Error {
-    ParseDistanceError,
-    ParseDurationError,
-    ParseMassError,
+    ParseDateError,
+    ParseDistanceError(String),
+    ParseDurationError(String),
+    ParseMassError(String),
+    ParseRepsError,
+    ParseRpeError,
     ParseStepsError,
     ParseTimeError,
     ParseUnitsError,
     SeriesNotOpen,
+    SettingsNotSaved(glib::BoolError),
     TraxError(fitnesstrax_lib::Error),
     IOError(io::Error),
 }
@@ -31,13 +35,23 @@ impl From<io::Error> for Error {
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Error::ParseDistanceError => write!(f, "Failed to parse a distance string"),
-            Error::ParseDurationError => write!(f, "Failed to parse a duration string"),
-            Error::ParseMassError => write!(f, "Failed to parse a mass string"),
+            Error::ParseDateError => write!(f, "Failed to parse a date"),
+            Error::ParseDistanceError(token) => {
+                write!(f, "Failed to parse a distance string at \"{}\"", token)
+            }
+            Error::ParseDurationError(token) => {
+                write!(f, "Failed to parse a duration string at \"{}\"", token)
+            }
+            Error::ParseMassError(token) => {
+                write!(f, "Failed to parse a mass string at \"{}\"", token)
+            }
+            Error::ParseRepsError => write!(f, "Failed to parse a number of repetitions"),
+            Error::ParseRpeError => write!(f, "Failed to parse a rate of perceived exertion"),
             Error::ParseStepsError => write!(f, "Failed to parse a number of steps"),
             Error::ParseTimeError => write!(f, "Failed to parse a time"),
             Error::ParseUnitsError => write!(f, "Failed to parse a units string"),
             Error::SeriesNotOpen => write!(f, "There is no series open"),
+            Error::SettingsNotSaved(err) => write!(f, "Settings could not be saved: {}", err),
             Error::TraxError(err) => write!(f, "Trax encountered an error: {}", err),
             Error::IOError(err) => write!(f, "IO Error: {}", err),
         }
@@ -50,13 +64,17 @@ impl error::Error for Error {
      * */
     fn description(&self) -> &str {
         match self {
-            Error::ParseDistanceError => "Failed to parse a distance string",
-            Error::ParseDurationError => "Failed to parse a duration string",
-            Error::ParseMassError => "Failed to parse a mass string",
+            Error::ParseDateError => "Failed to parse a date",
+            Error::ParseDistanceError(_) => "Failed to parse a distance string",
+            Error::ParseDurationError(_) => "Failed to parse a duration string",
+            Error::ParseMassError(_) => "Failed to parse a mass string",
+            Error::ParseRepsError => "Failed to parse a number of repetitions",
+            Error::ParseRpeError => "Failed to parse a rate of perceived exertion",
             Error::ParseStepsError => "Failed to parse a number of steps",
             Error::ParseTimeError => "Failed to parse a time",
             Error::ParseUnitsError => "Failed to parse a units string",
             Error::SeriesNotOpen => "There is no series open",
+            Error::SettingsNotSaved(_) => "Settings could not be saved",
             Error::TraxError(err) => err.description(),
             Error::IOError(err) => err.description(),
         }
@@ -64,13 +82,17 @@ impl error::Error for Error {
 
     fn cause(&self) -> Option<&(dyn error::Error + 'static)> {
         match self {
-            Error::ParseDistanceError => None,
-            Error::ParseDurationError => None,
-            Error::ParseMassError => None,
+            Error::ParseDateError => None,
+            Error::ParseDistanceError(_) => None,
+            Error::ParseDurationError(_) => None,
+            Error::ParseMassError(_) => None,
+            Error::ParseRepsError => None,
+            Error::ParseRpeError => None,
             Error::ParseStepsError => None,
             Error::ParseTimeError => None,
             Error::ParseUnitsError => None,
             Error::SeriesNotOpen => None,
+            Error::SettingsNotSaved(ref err) => Some(err),
             Error::TraxError(ref err) => Some(err),
             Error::IOError(ref err) => Some(err),
         }
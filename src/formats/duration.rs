@@ -40,38 +40,66 @@ impl fmt::Display for Duration {
 impl TryFrom<&str> for Duration {
     type Error = Error;
 
+    /// Parse a duration written as a clock, such as "1:02:03.5", "5:15" or "15" seconds, or with
+    /// units, such as "1h20m" or "45 min".
     fn try_from(inp: &str) -> Result<Self, Self::Error> {
-        let parts: Vec<&str> = inp.split(":").collect();
-        match parts.len() {
-            3 => {
-                let seconds = parts[2]
-                    .parse::<f64>()
-                    .map_err(|_| Error::ParseDurationError)?;
-                let minutes = parts[1]
-                    .parse::<f64>()
-                    .map_err(|_| Error::ParseDurationError)?;
-                let hours = parts[0]
-                    .parse::<f64>()
-                    .map_err(|_| Error::ParseDurationError)?;
-                Ok((seconds + minutes * 60.0 + hours * 3600.0) * si::S)
-            }
-            2 => {
-                let seconds = parts[1]
-                    .parse::<f64>()
-                    .map_err(|_| Error::ParseDurationError)?;
-                let minutes = parts[0]
-                    .parse::<f64>()
-                    .map_err(|_| Error::ParseDurationError)?;
-                Ok((seconds + minutes * 60.0) * si::S)
-            }
-            1 => parts[0]
-                .parse::<f64>()
-                .map(|f| f * si::S)
-                .map_err(|_| Error::ParseDurationError),
-            _ => Err(Error::ParseDurationError),
+        let inp = inp.trim();
+        if inp.contains(char::is_alphabetic) {
+            parse_with_units(inp)
+        } else {
+            parse_clock(inp)
         }
-        .map(|d| Duration(d))
+        .map(Duration)
+    }
+}
+
+fn parse_clock(inp: &str) -> Result<si::Second<f64>, Error> {
+    let parts: Vec<&str> = inp.split(':').collect();
+    if parts.len() > 3 {
+        return Err(Error::ParseDurationError(inp.to_owned()));
     }
+    parts.iter().try_fold(0. * si::S, |total, part| {
+        let value = part
+            .trim()
+            .parse::<f64>()
+            .map_err(|_| Error::ParseDurationError((*part).to_owned()))?;
+        Ok(total * 60. + value * si::S)
+    })
+}
+
+fn parse_with_units(inp: &str) -> Result<si::Second<f64>, Error> {
+    let mut total = 0. * si::S;
+    let mut rest = inp;
+    while !rest.is_empty() {
+        let number_end = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(rest.len());
+        let (number, after) = rest.split_at(number_end);
+        let after = after.trim_start();
+        let unit_end = after
+            .find(|c: char| !c.is_alphabetic())
+            .unwrap_or(after.len());
+        let (unit, after) = after.split_at(unit_end);
+
+        let consumed = rest.len() - after.len();
+        let token = if consumed > 0 {
+            rest[..consumed].trim()
+        } else {
+            rest.split_whitespace().next().unwrap_or(rest)
+        };
+        let value = number
+            .parse::<f64>()
+            .map_err(|_| Error::ParseDurationError(token.to_owned()))?;
+        let seconds = match unit.to_lowercase().as_str() {
+            "h" | "hr" | "hrs" | "hour" | "hours" => 3600.,
+            "m" | "min" | "mins" | "minute" | "minutes" => 60.,
+            "s" | "sec" | "secs" | "second" | "seconds" => 1.,
+            _ => return Err(Error::ParseDurationError(token.to_owned())),
+        };
+        total += value * seconds * si::S;
+        rest = after.trim_start();
+    }
+    Ok(total)
 }
 
 impl FromStr for Duration {
@@ -131,4 +159,32 @@ mod test {
         let t = "1:05:15".parse::<Duration>().expect("successful parse");
         assert_eq!(t, Duration::new(3915. * si::S));
     }
+
+    #[test]
+    fn it_parses_fractional_seconds() {
+        let t = Duration::try_from("1:02:03.5").expect("successful parse");
+        assert_eq!(t, Duration::new(3723.5 * si::S));
+    }
+
+    #[test]
+    fn it_parses_durations_with_units() {
+        let t = Duration::try_from("1h20m").expect("successful parse");
+        assert_eq!(t, Duration::new(4800. * si::S));
+        let t = Duration::try_from("45 min").expect("successful parse");
+        assert_eq!(t, Duration::new(2700. * si::S));
+        let t = Duration::try_from("1 hour 5 seconds").expect("successful parse");
+        assert_eq!(t, Duration::new(3605. * si::S));
+    }
+
+    #[test]
+    fn it_names_the_bad_token() {
+        match Duration::try_from("1h 20 mn") {
+            Err(Error::ParseDurationError(token)) => assert_eq!(token, "20 mn"),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        match Duration::try_from("5:1.2.3") {
+            Err(Error::ParseDurationError(token)) => assert_eq!(token, "1.2.3"),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
 }
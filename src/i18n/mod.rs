@@ -5,4 +5,4 @@ mod text;
 mod units;
 
 pub use text::Text;
-pub use units::UnitSystem;
+pub use units::{DistanceUnit, MassUnit, PaceStyle, UnitSystem};
@@ -1,19 +1,42 @@
-use dimensioned::si::Kilogram;
+use chrono::{Datelike, NaiveDate};
+use dimensioned::si::{Kilogram, Meter, MeterPerSecond};
 use fluent::{FluentArgs, FluentBundle, FluentResource, FluentValue};
 use std::fmt;
 use std::sync::Arc;
 use unic_langid::LanguageIdentifier;
 
-use crate::i18n::UnitSystem;
-use fitnesstrax_lib::timedistance;
+use crate::errors::Error;
+use crate::i18n::{DistanceUnit, MassUnit, PaceStyle, UnitSystem};
+use crate::range::TimezoneDisplay;
+use fitnesstrax_lib::activity::{ActivityId, ActivityKind};
+use fitnesstrax_lib::goals::Interval;
+use fitnesstrax_lib::personal_records::Category;
+use fitnesstrax_lib::streaks::{Condition, Streak};
 
 const ENGLISH_STRINGS: &str = "
 about = About
+activities = Activities
 activity = Activity
+activity-calendar = Activity Calendar
+add-activity = Add Activity
+add-goal = Add Goal
+add-rep-duration-workout = Add Reps/Duration Workout
+add-set = Add Set
+add-set-rep-workout = Add Sets/Reps Workout
+add-streak = Add Streak
 add-time-distance-workout = Add Time/Distance Workout
+add-weighted-set-workout = Add Weight Training Workout
+average-distance = Average Distance
+average-duration = Average Duration
+average-pace = Average Pace
+body-mass-units = Body Mass
+by-distance = By Distance
+by-duration = By Duration
 cancel = Cancel
-cycling = Cycling
+current-weight = Current Weight
+daily-average = Daily Average
 database-path = Database Path
+delete = Delete
 dependencies = Dependencies
   chrono-tz 0.4, MIT/Apache-2.0, Djzin
   chrono 0.4, MIT/Apache-2.0, Brandon W. Maister, Kang Seonghoon
@@ -27,12 +50,52 @@ dependencies = Dependencies
   serde_yaml, MIT/Apache-2.0, David Tolnay
   tzdata 0.4.1, MIT, Maxime Lenoir
   unic-langid 0.7.1, MIT/Apache-2.0, Zibi Braniecki
+distance = {$units ->
+    *[Kilometers] {$value} km
+    [Miles] {$value} mi
+    [Meters] {$value} m
+    [Yards] {$value} yd
+}
+distance-goal = {$activity}: {$distance} {$interval ->
+    [Day] per day
+    *[Week] per week
+    [Month] per month
+}
+distance-goal-kind = Distance Goal
+distance-label = {$units ->
+    *[Kilometers] kilometers
+    [Miles] miles
+    [Meters] meters
+    [Yards] yards
+}
+distance-units = Distance
 edit = Edit
 enter-distance = Enter distance
 enter-duration = Enter duration
 enter-time = Enter time
+export-complete = Export complete
+export-csv = Export CSV
+fastest-half-marathon = Fastest Half Marathon
+fastest-marathon = Fastest Marathon
+fastest-over = Fastest {$distance}
+goal-progress = {$current} of {$target}
+goals = Goals
 health-tracker = Health Tracker
+heaviest-set = Heaviest Set
 history = History
+import = Import
+import-apple-health = Import Apple Health
+import-csv = Import CSV
+import-report = {$table}: {$imported} imported, {$duplicates} already present, {$conflicts} conflicting notes, {$rejected} rejected
+import-workouts = Import FIT/GPX/TCX Workouts
+importing = Importing…
+interval = {$interval ->
+    [Day] Daily
+    *[Week] Weekly
+    [Month] Monthly
+}
+journal = Journal
+kind = Kind
 language = Language
 license = Copyright Savanni D'Gerinel (c) 2018-2020
   All rights reserved.
@@ -63,70 +126,249 @@ license = Copyright Savanni D'Gerinel (c) 2018-2020
   THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
   (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
   OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+load-units = Lifting Load
+long-date = {$month} {$day}, {$year}
+longest-distance = Longest Distance
+longest-duration = Longest Duration
+longest-set = Longest Set
 mass = {$units ->
-    *[SI] {$value} kilograms
-    [USA] {$value} pounds
+    *[Kilograms] {$value} kilograms
+    [Pounds] {$value} pounds
+    [Stones] {$value}
 }
 mass-label = {$units ->
-    *[SI] kilograms
-    [USA] pounds
+    *[Kilograms] kilograms
+    [Pounds] pounds
+    [Stones] stones
 }
+maximum = Maximum
+mean = Mean
+minimum = Minimum
+month = {$month ->
+    [January] January
+    [February] February
+    [March] March
+    [April] April
+    [May] May
+    [June] June
+    [July] July
+    [August] August
+    [September] September
+    [October] October
+    [November] November
+    *[December] December
+}
+month-abbreviation = {$month ->
+    [January] Jan
+    [February] Feb
+    [March] Mar
+    [April] Apr
+    [May] May
+    [June] Jun
+    [July] Jul
+    [August] Aug
+    [September] Sep
+    [October] Oct
+    [November] Nov
+    *[December] Dec
+}
+most-reps = Most Reps in a Set
+name = Name
 name-with-version = FitnessTrax, version 0.1
+new-database = New Database
+no-weights = No weights recorded in this range
+not-projected = The current trend does not reach the target
+open-database = Open Database
+pace = {$style ->
+    *[PerDistance] {$units ->
+        *[Kilometers] {$value} per km
+        [Miles] {$value} per mi
+    }
+    [Speed] {$units ->
+        *[Kilometers] {$value} km/h
+        [Miles] {$value} mph
+    }
+    [Split] {$value} per 500 m
+    [Swim] {$units ->
+        *[Meters] {$value} per 100 m
+        [Yards] {$value} per 100 yd
+    }
+}
+personal-record = Personal Record
 preferences = Preferences
-pushups = Pushups
-rowing = Rowing
-running = Running
+projected = On track to reach the target on {$date}
+ready = Ready
+records-not-saved = Some changes were not saved: {$error}
+remove = Remove
+rep-duration = Reps/Duration
+reps = Reps
+rpe = RPE
 save = Save
-situps = Situps
+series-corrupt = {$path} is damaged or is not a FitnessTrax database
+series-exists = {$path} already exists
+series-missing = {$path} does not exist
+series-unreadable = {$path} cannot be read
+set-rep = Sets/Reps
+settings-not-saved = Your preferences were changed, but could not be saved for next time: {$error}
+setup = Set Up FitnessTrax
+setup-database = Choose where to keep your records. You can open an existing database or start a new one.
+setup-ready = Everything is ready. Choose Apply to start using FitnessTrax.
+setup-weight = Enter your current weight to start tracking it, or leave it blank to skip this step.
+short-date = {$month} {$day}
+starting-weight = Starting Weight
+statistics = Statistics
 steps = Steps
 step-count = {$count ->
     [one] 1 step
     *[other] {$count} steps
 }
-swimming = Swimming
+steps-goal = {$steps} {$interval ->
+    [Day] per day
+    *[Week] per week
+    [Month] per month
+}
+steps-goal-kind = Steps Goal
+streak = Current streak: {$current ->
+    [one] 1 day
+    *[other] {$current} days
+}, longest: {$longest ->
+    [one] 1 day
+    *[other] {$longest} days
+}
+streak-activity = {$activity}
+streak-any-workout = Any Workout
+streak-steps = At Least {$steps}
+streak-weight = Weight Logged
+streaks = Streaks
+swim-distance-units = Swimming Distance
+target-date = Target Date (YYYY-MM-DD)
+target-weight = Target Weight
+time-distance = Time/Distance
 timezone = Timezone
+timezone-display = Days and Times
+timezone-display-home = In my timezone
+timezone-display-recorded = Where each record was made
+total = Total
+total-distance = Total Distance
+total-duration = Total Duration
 units = Units
-walking = Walking
+week-of = Week of {$date}
+weekday-abbreviation = {$weekday ->
+    *[Mon] Mon
+    [Tue] Tue
+    [Wed] Wed
+    [Thu] Thu
+    [Fri] Fri
+    [Sat] Sat
+    [Sun] Sun
+}
+weekly-distance = Weekly Distance
 weight = Weight
+weight-change = Weight Change
+weight-goal = Reach {$weight} by {$date}
+weight-goal-kind = Weight Goal
+weighted-set = Weight Training
+welcome = Welcome
+welcome-intro = FitnessTrax keeps your workouts and health records on your own computer. This assistant sets up a database and your preferences.
+workouts = Workouts
 ";
 
 const ESPERANTO_STRINGS: &str = "
 about = Pri
+activities = Aktivecoj
+activity = Aktiveco
+add-set = Aldonu Serion
 add-time-distance-workout = Aldonu Trejnadon de Daŭro/Distanco
 cancel = Nuligi
-cycling = Biciklado
 database-path = Vojo al Datumbazo
+delete = Forviŝu
 edit = Redaktu
 enter-distance = Eniru distanco
 enter-duration = Eniru daŭro
 enter-time = Eniru tempon
+export-csv = Eksportu CSV
+goals = Celoj
 health-tracker = Sana Supuristo
 history = Historio
+import = Importu
+import-apple-health = Importu Apple Health
+import-csv = Importu CSV
+import-workouts = Importu FIT/GPX/TCX Trejnadojn
+importing = Importante…
+journal = Taglibro
 language = Lingvo
+long-date = {$day}-a de {$month} {$year}
+name = Nomo
 mass = {$units ->
-    *[SI] {$value} kilogramoj
-    [USA] {$value} funtoj
+    *[Kilograms] {$value} kilogramoj
+    [Pounds] {$value} funtoj
+    [Stones] {$value}
 }
 mass-label = {$units ->
-    *[SI] kilogramoj
-    [USA] funtoj
+    *[Kilograms] kilogramoj
+    [Pounds] funtoj
+    [Stones] ŝtonoj
+}
+month = {$month ->
+    [January] januaro
+    [February] februaro
+    [March] marto
+    [April] aprilo
+    [May] majo
+    [June] junio
+    [July] julio
+    [August] aŭgusto
+    [September] septembro
+    [October] oktobro
+    [November] novembro
+    *[December] decembro
 }
+month-abbreviation = {$month ->
+    [January] jan
+    [February] feb
+    [March] mar
+    [April] apr
+    [May] maj
+    [June] jun
+    [July] jul
+    [August] aŭg
+    [September] sep
+    [October] okt
+    [November] nov
+    *[December] dec
+}
+open-database = Malfermi Datumbazon
 preferences = Agdoroj
-pushups = Supraj Puŝoj
-rowing = Remado
-running = Kurado
+ready = Preta
+records-not-saved = Kelkaj ŝanĝoj ne estis konservitaj: {$error}
+remove = Forigu
+reps = Ripetoj
 save = Ŝpari
-situps = Sidiĝoj
+settings-not-saved = Viaj agordoj ŝanĝiĝis, sed ne povis esti konservitaj por la venonta fojo: {$error}
+setup = Agordi FitnessTrax
+short-date = {$day}-a de {$month}
+starting-weight = Komenca Pezo
+statistics = Statistikoj
 steps = Paŝoj
 step-count = {$count ->
     [one] 1 paŝo
     *[other] {$count} paŝoj
 }
-swimming = Naĝado
 timezone = Horzono
+total = Sumo
 units = Unuoj
-walking = Promenadi
+weekday-abbreviation = {$weekday ->
+    *[Mon] lu
+    [Tue] ma
+    [Wed] me
+    [Thu] ĵa
+    [Fri] ve
+    [Sat] sa
+    [Sun] di
+}
 weight = Pezo
+welcome = Bonvenon
+workouts = Trejnadoj
 ";
 
 #[derive(Clone)]
@@ -159,6 +401,15 @@ fn add_language(bundle: &mut FluentBundle<FluentResource>, langid: &LanguageIden
     }
 }
 
+/// The English name of the month, which the messages use to choose the month's name in each
+/// language.
+fn month_name<D: Datelike>(date: &D) -> String {
+    format!(
+        "{}",
+        NaiveDate::from_ymd(date.year(), date.month(), 1).format("%B")
+    )
+}
+
 impl Text {
     pub fn new(langid: LanguageIdentifier) -> Text {
         let english_id: LanguageIdentifier = "en".parse().unwrap();
@@ -181,38 +432,221 @@ impl Text {
         self.tr("about", None).unwrap()
     }
 
+    pub fn activities(&self) -> String {
+        self.tr("activities", None).unwrap()
+    }
+
     pub fn activity<'s>(&self) -> String {
         self.tr("activity", None).unwrap()
     }
 
+    pub fn activity_calendar(&self) -> String {
+        self.tr("activity-calendar", None).unwrap()
+    }
+
+    pub fn activity_kind(&self, kind: &ActivityKind) -> String {
+        match kind {
+            ActivityKind::RepDuration => self.tr("rep-duration", None),
+            ActivityKind::SetRep => self.tr("set-rep", None),
+            ActivityKind::TimeDistance => self.tr("time-distance", None),
+            ActivityKind::WeightedSet => self.tr("weighted-set", None),
+        }
+        .unwrap()
+    }
+
+    pub fn add_activity(&self) -> String {
+        self.tr("add-activity", None).unwrap()
+    }
+
+    pub fn add_goal(&self) -> String {
+        self.tr("add-goal", None).unwrap()
+    }
+
+    pub fn add_rep_duration_workout(&self) -> String {
+        self.tr("add-rep-duration-workout", None).unwrap()
+    }
+
+    pub fn add_set(&self) -> String {
+        self.tr("add-set", None).unwrap()
+    }
+
+    pub fn add_set_rep_workout(&self) -> String {
+        self.tr("add-set-rep-workout", None).unwrap()
+    }
+
+    pub fn add_streak(&self) -> String {
+        self.tr("add-streak", None).unwrap()
+    }
+
     pub fn add_time_distance_workout(&self) -> String {
         self.tr("add-time-distance-workout", None).unwrap()
     }
 
+    pub fn add_weighted_set_workout(&self) -> String {
+        self.tr("add-weighted-set-workout", None).unwrap()
+    }
+
+    pub fn average_distance(&self) -> String {
+        self.tr("average-distance", None).unwrap()
+    }
+
+    pub fn average_duration(&self) -> String {
+        self.tr("average-duration", None).unwrap()
+    }
+
+    pub fn average_pace(&self) -> String {
+        self.tr("average-pace", None).unwrap()
+    }
+
+    pub fn body_mass_units(&self) -> String {
+        self.tr("body-mass-units", None).unwrap()
+    }
+
+    pub fn by_distance(&self) -> String {
+        self.tr("by-distance", None).unwrap()
+    }
+
+    pub fn by_duration(&self) -> String {
+        self.tr("by-duration", None).unwrap()
+    }
+
     pub fn cancel(&self) -> String {
         self.tr("cancel", None).unwrap()
     }
 
-    pub fn cycling(&self) -> String {
-        self.tr("cycling", None).unwrap()
+    pub fn current_weight(&self) -> String {
+        self.tr("current-weight", None).unwrap()
+    }
+
+    pub fn daily_average(&self) -> String {
+        self.tr("daily-average", None).unwrap()
     }
 
     pub fn database_path(&self) -> String {
         self.tr("database-path", None).unwrap()
     }
 
+    pub fn delete(&self) -> String {
+        self.tr("delete", None).unwrap()
+    }
+
     pub fn dependencies(&self) -> String {
         self.tr("dependencies", None).unwrap()
     }
 
+    pub fn distance(&self, value: Meter<f64>, units: DistanceUnit) -> String {
+        let mut args = FluentArgs::new();
+        args.insert("value", FluentValue::from(units.render(value)));
+        args.insert("units", FluentValue::from(String::from(&units)));
+
+        self.tr("distance", Some(&args)).unwrap()
+    }
+
+    pub fn distance_goal(&self, activity: &str, distance: &str, interval: Interval) -> String {
+        let mut args = FluentArgs::new();
+        args.insert("activity", FluentValue::from(activity));
+        args.insert("distance", FluentValue::from(distance));
+        args.insert("interval", FluentValue::from(format!("{:?}", interval)));
+
+        self.tr("distance-goal", Some(&args)).unwrap()
+    }
+
+    pub fn distance_goal_kind(&self) -> String {
+        self.tr("distance-goal-kind", None).unwrap()
+    }
+
+    pub fn distance_label(&self, units: DistanceUnit) -> String {
+        let mut args = FluentArgs::new();
+        args.insert("units", FluentValue::from(String::from(&units)));
+
+        self.tr("distance-label", Some(&args)).unwrap()
+    }
+
+    pub fn distance_units(&self) -> String {
+        self.tr("distance-units", None).unwrap()
+    }
+
     pub fn edit(&self) -> String {
         self.tr("edit", None).unwrap()
     }
 
+    pub fn export_complete(&self) -> String {
+        self.tr("export-complete", None).unwrap()
+    }
+
+    pub fn export_csv(&self) -> String {
+        self.tr("export-csv", None).unwrap()
+    }
+
+    pub fn goal_progress(&self, current: &str, target: &str) -> String {
+        let mut args = FluentArgs::new();
+        args.insert("current", FluentValue::from(current));
+        args.insert("target", FluentValue::from(target));
+
+        self.tr("goal-progress", Some(&args)).unwrap()
+    }
+
+    pub fn goals(&self) -> String {
+        self.tr("goals", None).unwrap()
+    }
+
     pub fn history(&self) -> String {
         self.tr("history", None).unwrap()
     }
 
+    pub fn import(&self) -> String {
+        self.tr("import", None).unwrap()
+    }
+
+    pub fn import_apple_health(&self) -> String {
+        self.tr("import-apple-health", None).unwrap()
+    }
+
+    pub fn import_csv(&self) -> String {
+        self.tr("import-csv", None).unwrap()
+    }
+
+    pub fn import_report(
+        &self,
+        table: &str,
+        imported: usize,
+        duplicates: usize,
+        conflicts: usize,
+        rejected: usize,
+    ) -> String {
+        let mut args = FluentArgs::new();
+        args.insert("table", FluentValue::from(table));
+        args.insert("imported", FluentValue::from(imported));
+        args.insert("duplicates", FluentValue::from(duplicates));
+        args.insert("conflicts", FluentValue::from(conflicts));
+        args.insert("rejected", FluentValue::from(rejected));
+
+        self.tr("import-report", Some(&args)).unwrap()
+    }
+
+    pub fn import_workouts(&self) -> String {
+        self.tr("import-workouts", None).unwrap()
+    }
+
+    pub fn importing(&self) -> String {
+        self.tr("importing", None).unwrap()
+    }
+
+    pub fn interval(&self, interval: Interval) -> String {
+        let mut args = FluentArgs::new();
+        args.insert("interval", FluentValue::from(format!("{:?}", interval)));
+
+        self.tr("interval", Some(&args)).unwrap()
+    }
+
+    pub fn journal(&self) -> String {
+        self.tr("journal", None).unwrap()
+    }
+
+    pub fn kind(&self) -> String {
+        self.tr("kind", None).unwrap()
+    }
+
     pub fn language(&self) -> String {
         self.tr("language", None).unwrap()
     }
@@ -221,38 +655,234 @@ impl Text {
         self.tr("license", None).unwrap()
     }
 
-    pub fn mass(&self, value: Kilogram<f64>, units: &UnitSystem) -> String {
+    pub fn load_units(&self) -> String {
+        self.tr("load-units", None).unwrap()
+    }
+
+    pub fn long_date<D: Datelike>(&self, date: &D) -> String {
+        let mut month_args = FluentArgs::new();
+        month_args.insert("month", FluentValue::from(month_name(date)));
+
+        let mut args = FluentArgs::new();
+        args.insert(
+            "month",
+            FluentValue::from(self.tr("month", Some(&month_args)).unwrap()),
+        );
+        args.insert("day", FluentValue::from(date.day() as usize));
+        args.insert("year", FluentValue::from(date.year().to_string()));
+
+        self.tr("long-date", Some(&args)).unwrap()
+    }
+
+    pub fn mass(&self, value: Kilogram<f64>, units: MassUnit) -> String {
         let mut args = FluentArgs::new();
-        args.insert("value", FluentValue::from(units.render_mass(value)));
-        args.insert("units", FluentValue::from(String::from(units)));
+        args.insert("value", FluentValue::from(units.render(value)));
+        args.insert("units", FluentValue::from(String::from(&units)));
 
         self.tr("mass", Some(&args)).unwrap()
     }
 
-    pub fn mass_label(&self) -> String {
-        self.tr("mass-label", None).unwrap()
+    pub fn mass_label(&self, units: MassUnit) -> String {
+        let mut args = FluentArgs::new();
+        args.insert("units", FluentValue::from(String::from(&units)));
+
+        self.tr("mass-label", Some(&args)).unwrap()
+    }
+
+    pub fn maximum(&self) -> String {
+        self.tr("maximum", None).unwrap()
+    }
+
+    pub fn mean(&self) -> String {
+        self.tr("mean", None).unwrap()
+    }
+
+    pub fn minimum(&self) -> String {
+        self.tr("minimum", None).unwrap()
+    }
+
+    pub fn month_abbreviation<D: Datelike>(&self, date: &D) -> String {
+        let mut args = FluentArgs::new();
+        args.insert("month", FluentValue::from(month_name(date)));
+
+        self.tr("month-abbreviation", Some(&args)).unwrap()
+    }
+
+    pub fn name(&self) -> String {
+        self.tr("name", None).unwrap()
     }
 
     pub fn name_with_version(&self) -> String {
         self.tr("name-with-version", None).unwrap()
     }
 
+    pub fn new_database(&self) -> String {
+        self.tr("new-database", None).unwrap()
+    }
+
+    pub fn no_weights(&self) -> String {
+        self.tr("no-weights", None).unwrap()
+    }
+
+    pub fn not_projected(&self) -> String {
+        self.tr("not-projected", None).unwrap()
+    }
+
+    pub fn open_database(&self) -> String {
+        self.tr("open-database", None).unwrap()
+    }
+
+    pub fn pace(
+        &self,
+        activity: &ActivityId,
+        speed: MeterPerSecond<f64>,
+        units: &UnitSystem,
+    ) -> String {
+        let style = PaceStyle::for_activity(activity);
+        let units = match style {
+            PaceStyle::Swim => units.distance_for(activity).short(),
+            _ => units.distance_for(activity).long(),
+        };
+        let mut args = FluentArgs::new();
+        args.insert("value", FluentValue::from(units.render_pace(style, speed)));
+        args.insert("style", FluentValue::from(format!("{:?}", style)));
+        args.insert("units", FluentValue::from(String::from(&units)));
+
+        self.tr("pace", Some(&args)).unwrap()
+    }
+
+    pub fn personal_record(&self) -> String {
+        self.tr("personal-record", None).unwrap()
+    }
+
     pub fn preferences(&self) -> String {
         self.tr("preferences", None).unwrap()
     }
 
-    pub fn rowing(&self) -> String {
-        self.tr("rowing", None).unwrap()
+    pub fn projected(&self, date: &chrono::Date<chrono_tz::Tz>) -> String {
+        let mut args = FluentArgs::new();
+        args.insert("date", FluentValue::from(self.long_date(date)));
+
+        self.tr("projected", Some(&args)).unwrap()
+    }
+
+    pub fn ready(&self) -> String {
+        self.tr("ready", None).unwrap()
     }
 
-    pub fn running(&self) -> String {
-        self.tr("running", None).unwrap()
+    pub fn record_category(&self, category: &Category) -> String {
+        match category {
+            Category::FastestOver(21_097) => self.tr("fastest-half-marathon", None),
+            Category::FastestOver(42_195) => self.tr("fastest-marathon", None),
+            Category::FastestOver(meters) => {
+                let distance = if meters % 1000 == 0 {
+                    format!("{} km", meters / 1000)
+                } else {
+                    format!("{} m", meters)
+                };
+                let mut args = FluentArgs::new();
+                args.insert("distance", FluentValue::from(distance));
+                self.tr("fastest-over", Some(&args))
+            }
+            Category::LongestDistance => self.tr("longest-distance", None),
+            Category::LongestDuration => self.tr("longest-duration", None),
+            Category::MostReps => self.tr("most-reps", None),
+            Category::LongestSet => self.tr("longest-set", None),
+            Category::HeaviestSet => self.tr("heaviest-set", None),
+        }
+        .unwrap()
+    }
+
+    pub fn records_not_saved(&self, err: &Error) -> String {
+        let mut args = FluentArgs::new();
+        args.insert("error", FluentValue::from(format!("{}", err)));
+
+        self.tr("records-not-saved", Some(&args)).unwrap()
+    }
+
+    pub fn remove(&self) -> String {
+        self.tr("remove", None).unwrap()
+    }
+
+    pub fn reps(&self) -> String {
+        self.tr("reps", None).unwrap()
+    }
+
+    pub fn rpe(&self) -> String {
+        self.tr("rpe", None).unwrap()
     }
 
     pub fn save(&self) -> String {
         self.tr("save", None).unwrap()
     }
 
+    /// Describe a problem with opening or creating a series, or any other error.
+    pub fn series_error(&self, err: &Error) -> String {
+        let (key, path) = match err {
+            Error::TraxError(fitnesstrax_lib::Error::SeriesCorrupt(path, _)) => {
+                ("series-corrupt", path)
+            }
+            Error::TraxError(fitnesstrax_lib::Error::SeriesExists(path)) => ("series-exists", path),
+            Error::TraxError(fitnesstrax_lib::Error::SeriesMissing(path)) => {
+                ("series-missing", path)
+            }
+            Error::TraxError(fitnesstrax_lib::Error::SeriesUnreadable(path, _)) => {
+                ("series-unreadable", path)
+            }
+            _ => return format!("{}", err),
+        };
+        let mut args = FluentArgs::new();
+        args.insert(
+            "path",
+            FluentValue::from(path.to_string_lossy().into_owned()),
+        );
+
+        self.tr(key, Some(&args)).unwrap()
+    }
+
+    pub fn settings_not_saved(&self, err: &Error) -> String {
+        let mut args = FluentArgs::new();
+        args.insert("error", FluentValue::from(format!("{}", err)));
+
+        self.tr("settings-not-saved", Some(&args)).unwrap()
+    }
+
+    pub fn setup(&self) -> String {
+        self.tr("setup", None).unwrap()
+    }
+
+    pub fn setup_database(&self) -> String {
+        self.tr("setup-database", None).unwrap()
+    }
+
+    pub fn setup_ready(&self) -> String {
+        self.tr("setup-ready", None).unwrap()
+    }
+
+    pub fn setup_weight(&self) -> String {
+        self.tr("setup-weight", None).unwrap()
+    }
+
+    /// The month and day, for labels drawn onto charts. Cairo draws the marks that Fluent puts
+    /// around each argument to isolate its direction, so they are left out.
+    pub fn short_date<D: Datelike>(&self, date: &D) -> String {
+        let mut args = FluentArgs::new();
+        args.insert("month", FluentValue::from(self.month_abbreviation(date)));
+        args.insert("day", FluentValue::from(date.day() as usize));
+
+        self.tr("short-date", Some(&args))
+            .unwrap()
+            .replace(&['\u{2068}', '\u{2069}'][..], "")
+    }
+
+    pub fn starting_weight(&self) -> String {
+        self.tr("starting-weight", None).unwrap()
+    }
+
+    pub fn statistics(&self) -> String {
+        self.tr("statistics", None).unwrap()
+    }
+
     pub fn step_count(&self, count: u32) -> String {
         let mut _errors = vec![];
 
@@ -271,35 +901,151 @@ impl Text {
             .unwrap()
     }
 
+    pub fn steps_goal(&self, steps: &str, interval: Interval) -> String {
+        let mut args = FluentArgs::new();
+        args.insert("steps", FluentValue::from(steps));
+        args.insert("interval", FluentValue::from(format!("{:?}", interval)));
+
+        self.tr("steps-goal", Some(&args)).unwrap()
+    }
+
+    pub fn steps_goal_kind(&self) -> String {
+        self.tr("steps-goal-kind", None).unwrap()
+    }
+
     pub fn steps_label(&self) -> String {
         self.tr("steps", None).unwrap()
     }
 
-    pub fn swimming(&self) -> String {
-        self.tr("swimming", None).unwrap()
+    pub fn streak(&self, streak: &Streak) -> String {
+        let mut args = FluentArgs::new();
+        args.insert("current", FluentValue::from(streak.current));
+        args.insert("longest", FluentValue::from(streak.longest));
+
+        self.tr("streak", Some(&args)).unwrap()
+    }
+
+    pub fn streak_any_workout(&self) -> String {
+        self.tr("streak-any-workout", None).unwrap()
+    }
+
+    pub fn streak_condition(&self, condition: &Condition, activity: &str) -> String {
+        match condition {
+            Condition::AnyWorkout => self.streak_any_workout(),
+            Condition::Activity(_) => {
+                let mut args = FluentArgs::new();
+                args.insert("activity", FluentValue::from(activity));
+                self.tr("streak-activity", Some(&args)).unwrap()
+            }
+            Condition::Steps(steps) => {
+                let mut args = FluentArgs::new();
+                args.insert("steps", FluentValue::from(self.step_count(*steps)));
+                self.tr("streak-steps", Some(&args)).unwrap()
+            }
+            Condition::WeightLogged => self.streak_weight(),
+        }
+    }
+
+    pub fn streak_weight(&self) -> String {
+        self.tr("streak-weight", None).unwrap()
+    }
+
+    pub fn streaks(&self) -> String {
+        self.tr("streaks", None).unwrap()
+    }
+
+    pub fn swim_distance_units(&self) -> String {
+        self.tr("swim-distance-units", None).unwrap()
+    }
+
+    pub fn target_date(&self) -> String {
+        self.tr("target-date", None).unwrap()
+    }
+
+    pub fn target_weight(&self) -> String {
+        self.tr("target-weight", None).unwrap()
     }
 
     pub fn timezone<'s>(&'s self) -> String {
         self.tr("timezone", None).unwrap()
     }
 
-    pub fn time_distance_activity<'s>(&'s self, activity: &timedistance::ActivityType) -> String {
-        match activity {
-            timedistance::ActivityType::Cycling => self.tr("cycling", None),
-            timedistance::ActivityType::Rowing => self.tr("rowing", None),
-            timedistance::ActivityType::Running => self.tr("running", None),
-            timedistance::ActivityType::Swimming => self.tr("swimming", None),
-            timedistance::ActivityType::Walking => self.tr("walking", None),
+    pub fn timezone_display(&self) -> String {
+        self.tr("timezone-display", None).unwrap()
+    }
+
+    pub fn timezone_display_option(&self, display: &TimezoneDisplay) -> String {
+        match display {
+            TimezoneDisplay::Home => self.tr("timezone-display-home", None),
+            TimezoneDisplay::Recorded => self.tr("timezone-display-recorded", None),
         }
         .unwrap()
     }
 
+    pub fn total(&self) -> String {
+        self.tr("total", None).unwrap()
+    }
+
+    pub fn total_distance(&self) -> String {
+        self.tr("total-distance", None).unwrap()
+    }
+
+    pub fn total_duration(&self) -> String {
+        self.tr("total-duration", None).unwrap()
+    }
+
     pub fn units(&self) -> String {
         self.tr("units", None).unwrap()
     }
 
-    pub fn walking(&self) -> String {
-        self.tr("walking", None).unwrap()
+    pub fn week_of(&self, date: &chrono::Date<chrono_tz::Tz>) -> String {
+        let mut args = FluentArgs::new();
+        args.insert("date", FluentValue::from(self.long_date(date)));
+
+        self.tr("week-of", Some(&args)).unwrap()
+    }
+
+    pub fn weekday_abbreviation(&self, weekday: chrono::Weekday) -> String {
+        let mut args = FluentArgs::new();
+        args.insert("weekday", FluentValue::from(format!("{:?}", weekday)));
+
+        self.tr("weekday-abbreviation", Some(&args)).unwrap()
+    }
+
+    pub fn weekly_distance(&self) -> String {
+        self.tr("weekly-distance", None).unwrap()
+    }
+
+    pub fn weight(&self) -> String {
+        self.tr("weight", None).unwrap()
+    }
+
+    pub fn weight_change(&self) -> String {
+        self.tr("weight-change", None).unwrap()
+    }
+
+    pub fn weight_goal(&self, weight: &str, date: &chrono::NaiveDate) -> String {
+        let mut args = FluentArgs::new();
+        args.insert("weight", FluentValue::from(weight));
+        args.insert("date", FluentValue::from(self.long_date(date)));
+
+        self.tr("weight-goal", Some(&args)).unwrap()
+    }
+
+    pub fn weight_goal_kind(&self) -> String {
+        self.tr("weight-goal-kind", None).unwrap()
+    }
+
+    pub fn welcome(&self) -> String {
+        self.tr("welcome", None).unwrap()
+    }
+
+    pub fn welcome_intro(&self) -> String {
+        self.tr("welcome-intro", None).unwrap()
+    }
+
+    pub fn workouts(&self) -> String {
+        self.tr("workouts", None).unwrap()
     }
 
     pub fn tr(&self, id: &str, args: Option<&FluentArgs>) -> Option<String> {
@@ -327,4 +1073,21 @@ mod test {
         assert_eq!(eo.preferences(), "Agdoroj");
         assert_eq!(eo.history(), "Historio");
     }
+
+    #[test]
+    fn dates_get_translated() {
+        let date = chrono::NaiveDate::from_ymd(2020, 8, 3);
+
+        let en = Text::new("en-US".parse().unwrap());
+        assert_eq!(en.short_date(&date), "Aug 3");
+
+        let eo = Text::new("eo".parse().unwrap());
+        assert_eq!(eo.short_date(&date), "3-a de aŭg");
+        assert_eq!(eo.weekday_abbreviation(chrono::Weekday::Thu), "ĵa");
+        assert_eq!(
+            eo.long_date(&date)
+                .replace(&['\u{2068}', '\u{2069}'][..], ""),
+            "3-a de aŭgusto 2020"
+        );
+    }
 }
@@ -1,22 +1,217 @@
 use dimensioned::si;
+use fitnesstrax_lib::activity::ActivityId;
+use fitnesstrax_lib::timedistance::ActivityType;
 use serde::{Deserialize, Serialize};
 use std::convert::TryFrom;
 use std::fmt;
 use std::str::FromStr;
 
 use crate::errors::Error;
+use crate::formats::Duration;
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum MassUnit {
+    Kilograms,
+    Pounds,
+    /// Stones of fourteen pounds, shown as stones and pounds.
+    Stones,
+}
+
+impl From<&MassUnit> for String {
+    fn from(unit: &MassUnit) -> String {
+        format!("{:?}", unit)
+    }
+}
+
+impl TryFrom<&str> for MassUnit {
+    type Error = Error;
+
+    fn try_from(s: &str) -> Result<MassUnit, Error> {
+        match s {
+            "Kilograms" => Ok(MassUnit::Kilograms),
+            "Pounds" => Ok(MassUnit::Pounds),
+            "Stones" => Ok(MassUnit::Stones),
+            _ => Err(Error::ParseUnitsError),
+        }
+    }
+}
+
+impl MassUnit {
+    fn size(self) -> si::Kilogram<f64> {
+        match self {
+            MassUnit::Kilograms => si::KG,
+            MassUnit::Pounds => si::LB,
+            MassUnit::Stones => 14. * si::LB,
+        }
+    }
+
+    pub fn render(self, inp: si::Kilogram<f64>) -> String {
+        match self {
+            MassUnit::Kilograms => format!("{}", Kilograms::new(inp)),
+            MassUnit::Pounds => format!("{}", Pounds::new(inp)),
+            MassUnit::Stones => {
+                let pounds = ((inp / si::LB).value_unsafe * 10.).round() / 10.;
+                let stones = (pounds / 14.).floor();
+                format!("{} st {:.1} lb", stones, pounds - stones * 14.)
+            }
+        }
+    }
+
+    /// Parse a mass, such as "80", "80 kg" or "12 st 7 lb". A unit in the input overrides this
+    /// one.
+    pub fn parse(self, inp: &str) -> Result<si::Kilogram<f64>, Error> {
+        parse_amount(
+            inp,
+            self.size().value_unsafe,
+            |unit| match unit {
+                "kg" | "kgs" | "kilogram" | "kilograms" => Some(si::KG.value_unsafe),
+                "lb" | "lbs" | "pound" | "pounds" => Some(si::LB.value_unsafe),
+                "st" | "stone" | "stones" => Some(14. * si::LB.value_unsafe),
+                _ => None,
+            },
+            Error::ParseMassError,
+        )
+        .map(|v| v * si::KG)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum DistanceUnit {
+    Kilometers,
+    Miles,
+    Meters,
+    Yards,
+}
+
+impl From<&DistanceUnit> for String {
+    fn from(unit: &DistanceUnit) -> String {
+        format!("{:?}", unit)
+    }
+}
+
+impl TryFrom<&str> for DistanceUnit {
+    type Error = Error;
+
+    fn try_from(s: &str) -> Result<DistanceUnit, Error> {
+        match s {
+            "Kilometers" => Ok(DistanceUnit::Kilometers),
+            "Miles" => Ok(DistanceUnit::Miles),
+            "Meters" => Ok(DistanceUnit::Meters),
+            "Yards" => Ok(DistanceUnit::Yards),
+            _ => Err(Error::ParseUnitsError),
+        }
+    }
+}
+
+impl DistanceUnit {
+    fn size(self) -> si::Meter<f64> {
+        match self {
+            DistanceUnit::Kilometers => 1000. * si::M,
+            DistanceUnit::Miles => si::MI,
+            DistanceUnit::Meters => si::M,
+            DistanceUnit::Yards => si::YD,
+        }
+    }
+
+    /// The unit from the same system that suits longer distances, such as kilometers for meters.
+    pub fn long(self) -> DistanceUnit {
+        match self {
+            DistanceUnit::Kilometers | DistanceUnit::Meters => DistanceUnit::Kilometers,
+            DistanceUnit::Miles | DistanceUnit::Yards => DistanceUnit::Miles,
+        }
+    }
+
+    /// The unit from the same system that suits shorter distances, such as yards for miles.
+    pub fn short(self) -> DistanceUnit {
+        match self {
+            DistanceUnit::Kilometers | DistanceUnit::Meters => DistanceUnit::Meters,
+            DistanceUnit::Miles | DistanceUnit::Yards => DistanceUnit::Yards,
+        }
+    }
+
+    pub fn render(self, inp: si::Meter<f64>) -> String {
+        match self {
+            DistanceUnit::Kilometers => format!("{}", Kilometers::new(inp)),
+            DistanceUnit::Miles => format!("{}", Miles::new(inp)),
+            DistanceUnit::Meters | DistanceUnit::Yards => {
+                format!("{:.0}", (inp / self.size()).value_unsafe)
+            }
+        }
+    }
+
+    /// Parse a distance, such as "5", "5 mi" or "400m". A unit in the input overrides this one.
+    pub fn parse(self, inp: &str) -> Result<si::Meter<f64>, Error> {
+        parse_amount(
+            inp,
+            self.size().value_unsafe,
+            |unit| match unit {
+                "km" | "kilometer" | "kilometers" | "kilometre" | "kilometres" => Some(1000.),
+                "m" | "meter" | "meters" | "metre" | "metres" => Some(1.),
+                "mi" | "mile" | "miles" => Some(si::MI.value_unsafe),
+                "yd" | "yds" | "yard" | "yards" => Some(si::YD.value_unsafe),
+                _ => None,
+            },
+            Error::ParseDistanceError,
+        )
+        .map(|v| v * si::M)
+    }
+
+    /// Render a speed in the way that is usual for the pace style. Rowing splits are always per
+    /// 500 meters, since that is what rowing machines show everywhere.
+    pub fn render_pace(self, style: PaceStyle, inp: si::MeterPerSecond<f64>) -> String {
+        let distance = match style {
+            PaceStyle::Speed => {
+                return format!("{:.1}", (inp * si::HR / self.long().size()).value_unsafe)
+            }
+            PaceStyle::PerDistance => self.long().size(),
+            PaceStyle::Split => 500. * si::M,
+            PaceStyle::Swim => 100. * self.short().size(),
+        };
+        format!("{}", Duration::new(distance / inp))
+    }
+}
+
+/// The units to show and enter each kind of quantity in.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct UnitSystem {
+    pub body_mass: MassUnit,
+    pub distance: DistanceUnit,
+    pub swim_distance: DistanceUnit,
+    /// The load in weight lifting.
+    pub load: MassUnit,
+}
 
-#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
-pub enum UnitSystem {
-    SI,
-    USA,
+impl UnitSystem {
+    pub const SI: UnitSystem = UnitSystem {
+        body_mass: MassUnit::Kilograms,
+        distance: DistanceUnit::Kilometers,
+        swim_distance: DistanceUnit::Meters,
+        load: MassUnit::Kilograms,
+    };
+
+    pub const USA: UnitSystem = UnitSystem {
+        body_mass: MassUnit::Pounds,
+        distance: DistanceUnit::Miles,
+        swim_distance: DistanceUnit::Yards,
+        load: MassUnit::Pounds,
+    };
+
+    pub const UK: UnitSystem = UnitSystem {
+        body_mass: MassUnit::Stones,
+        distance: DistanceUnit::Miles,
+        swim_distance: DistanceUnit::Meters,
+        load: MassUnit::Kilograms,
+    };
 }
 
+/// The name of the preset that these units match, or "Custom".
 impl From<&UnitSystem> for String {
     fn from(units: &UnitSystem) -> String {
-        match units {
+        match *units {
             UnitSystem::SI => String::from("SI"),
             UnitSystem::USA => String::from("USA"),
+            UnitSystem::UK => String::from("UK"),
+            _ => String::from("Custom"),
         }
     }
 }
@@ -28,6 +223,7 @@ impl TryFrom<&str> for UnitSystem {
         match s {
             "SI" => Ok(UnitSystem::SI),
             "USA" => Ok(UnitSystem::USA),
+            "UK" => Ok(UnitSystem::UK),
             _ => Err(Error::ParseUnitsError),
         }
     }
@@ -35,30 +231,106 @@ impl TryFrom<&str> for UnitSystem {
 
 impl UnitSystem {
     pub fn render_mass(&self, inp: si::Kilogram<f64>) -> String {
-        match self {
-            UnitSystem::SI => format!("{}", Kilograms::new(inp)),
-            UnitSystem::USA => format!("{}", Pounds::new(inp)),
-        }
+        self.body_mass.render(inp)
     }
 
     pub fn parse_mass(&self, inp: &str) -> Result<si::Kilogram<f64>, Error> {
-        match self {
-            UnitSystem::SI => Kilograms::try_from(inp).map(|v| v.extract()),
-            UnitSystem::USA => Pounds::try_from(inp).map(|v| v.extract()),
-        }
+        self.body_mass.parse(inp)
+    }
+
+    pub fn render_load(&self, inp: si::Kilogram<f64>) -> String {
+        self.load.render(inp)
+    }
+
+    pub fn parse_load(&self, inp: &str) -> Result<si::Kilogram<f64>, Error> {
+        self.load.parse(inp)
     }
 
     pub fn render_distance(&self, inp: si::Meter<f64>) -> String {
-        match self {
-            UnitSystem::SI => format!("{}", Kilometers::new(inp)),
-            UnitSystem::USA => format!("{}", Miles::new(inp)),
-        }
+        self.distance.render(inp)
     }
 
     pub fn parse_distance(&self, inp: &str) -> Result<si::Meter<f64>, Error> {
-        match self {
-            UnitSystem::SI => Kilometers::try_from(inp).map(|v| v.extract()),
-            UnitSystem::USA => Miles::try_from(inp).map(|v| v.extract()),
+        self.distance.parse(inp)
+    }
+
+    /// The distance unit for an activity, which is the swim distance unit for swimming.
+    pub fn distance_for(&self, activity: &ActivityId) -> DistanceUnit {
+        if *activity == ActivityType::Swimming.into() {
+            self.swim_distance
+        } else {
+            self.distance
+        }
+    }
+}
+
+/// Split an input such as "12 st 7 lb" into numbers and the units that follow them.
+fn split_units(inp: &str) -> Vec<(&str, Option<&str>)> {
+    let mut parts = Vec::new();
+    let mut rest = inp.trim();
+    while !rest.is_empty() {
+        let number_end = rest.find(char::is_alphabetic).unwrap_or(rest.len());
+        let (number, after) = rest.split_at(number_end);
+        let unit_end = after
+            .find(|c: char| !c.is_alphabetic())
+            .unwrap_or(after.len());
+        let (unit, after) = after.split_at(unit_end);
+        parts.push((number.trim(), Some(unit).filter(|unit| !unit.is_empty())));
+        rest = after.trim_start();
+    }
+    parts
+}
+
+/// Parse an amount into the base SI unit. A bare number is in the default unit, while `unit`
+/// gives the size of each unit that may be written out.
+fn parse_amount(
+    inp: &str,
+    default: f64,
+    unit: fn(&str) -> Option<f64>,
+    error: fn(String) -> Error,
+) -> Result<f64, Error> {
+    let parts = split_units(inp);
+    if parts.is_empty() {
+        return Err(error(inp.to_owned()));
+    }
+    parts.iter().try_fold(0., |total, (number, name)| {
+        let size = match name {
+            None if parts.len() == 1 => default,
+            None => return Err(error((*number).to_owned())),
+            Some(name) => unit(&name.to_lowercase()).ok_or_else(|| error((*name).to_owned()))?,
+        };
+        let value = parse_number(number, error)?;
+        Ok(total + value * size)
+    })
+}
+
+fn parse_number(inp: &str, error: fn(String) -> Error) -> Result<f64, Error> {
+    inp.parse::<f64>().map_err(|_| error(inp.to_owned()))
+}
+
+/// How the speed of a time/distance activity is usually given.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PaceStyle {
+    /// The time taken to cover a kilometer or a mile, for running and walking.
+    PerDistance,
+    /// Kilometers or miles per hour, for cycling and for user-defined activities.
+    Speed,
+    /// The time taken to cover 500 meters, for rowing.
+    Split,
+    /// The time taken to cover 100 meters or 100 yards, for swimming.
+    Swim,
+}
+
+impl PaceStyle {
+    pub fn for_activity(activity: &ActivityId) -> PaceStyle {
+        if *activity == ActivityType::Running.into() || *activity == ActivityType::Walking.into() {
+            PaceStyle::PerDistance
+        } else if *activity == ActivityType::Rowing.into() {
+            PaceStyle::Split
+        } else if *activity == ActivityType::Swimming.into() {
+            PaceStyle::Swim
+        } else {
+            PaceStyle::Speed
         }
     }
 }
@@ -85,10 +357,6 @@ impl Kilograms {
         }
     }
     */
-
-    pub fn extract(self) -> si::Kilogram<f64> {
-        self.val
-    }
 }
 
 impl fmt::Display for Kilograms {
@@ -105,9 +373,7 @@ impl TryFrom<&str> for Kilograms {
     type Error = Error;
 
     fn try_from(inp: &str) -> Result<Self, Self::Error> {
-        inp.parse::<f64>()
-            .map(|v| Kilograms::new(v * si::KG))
-            .map_err(|_| Error::ParseMassError)
+        parse_number(inp, Error::ParseMassError).map(|v| Kilograms::new(v * si::KG))
     }
 }
 
@@ -141,10 +407,6 @@ impl Pounds {
         }
     }
     */
-
-    pub fn extract(self) -> si::Kilogram<f64> {
-        self.val
-    }
 }
 
 impl fmt::Display for Pounds {
@@ -161,9 +423,7 @@ impl TryFrom<&str> for Pounds {
     type Error = Error;
 
     fn try_from(inp: &str) -> Result<Self, Self::Error> {
-        inp.parse::<f64>()
-            .map(|v| Pounds::new(v * si::LB))
-            .map_err(|_| Error::ParseMassError)
+        parse_number(inp, Error::ParseMassError).map(|v| Pounds::new(v * si::LB))
     }
 }
 
@@ -197,10 +457,6 @@ impl Kilometers {
         }
     }
     */
-
-    pub fn extract(self) -> si::Meter<f64> {
-        self.val
-    }
 }
 
 impl fmt::Display for Kilometers {
@@ -217,9 +473,7 @@ impl TryFrom<&str> for Kilometers {
     type Error = Error;
 
     fn try_from(inp: &str) -> Result<Self, Self::Error> {
-        inp.parse::<f64>()
-            .map(|v| Kilometers::new(v * 1000.0 * si::M))
-            .map_err(|_| Error::ParseDistanceError)
+        parse_number(inp, Error::ParseDistanceError).map(|v| Kilometers::new(v * 1000.0 * si::M))
     }
 }
 
@@ -253,10 +507,6 @@ impl Miles {
         }
     }
     */
-
-    pub fn extract(self) -> si::Meter<f64> {
-        self.val
-    }
 }
 
 impl fmt::Display for Miles {
@@ -273,9 +523,7 @@ impl TryFrom<&str> for Miles {
     type Error = Error;
 
     fn try_from(inp: &str) -> Result<Self, Self::Error> {
-        inp.parse::<f64>()
-            .map(|v| Miles::new(v * si::MI))
-            .map_err(|_| Error::ParseDistanceError)
+        parse_number(inp, Error::ParseDistanceError).map(|v| Miles::new(v * si::MI))
     }
 }
 
@@ -292,7 +540,7 @@ mod test {
     use dimensioned::si;
     use std::convert::TryFrom;
 
-    use super::{Kilograms, Kilometers, Miles, Pounds};
+    use super::{DistanceUnit, Kilograms, Kilometers, MassUnit, Miles, PaceStyle, Pounds};
     use crate::errors::Error;
     use crate::i18n::UnitSystem;
 
@@ -375,4 +623,100 @@ mod test {
         let t: Miles = "20.00".parse().expect("successful parse");
         assert_eq!(t, Miles::new(20. * si::MI));
     }
+
+    #[test]
+    fn it_renders_pace_for_each_style() {
+        // 5 km in 25 minutes
+        let speed = 5000. * si::M / (1500. * si::S);
+        let km = DistanceUnit::Kilometers;
+        let mi = DistanceUnit::Miles;
+        assert_eq!(km.render_pace(PaceStyle::PerDistance, speed), "5:00");
+        assert_eq!(mi.render_pace(PaceStyle::PerDistance, speed), "8:02");
+        assert_eq!(km.render_pace(PaceStyle::Speed, speed), "12.0");
+        assert_eq!(mi.render_pace(PaceStyle::Speed, speed), "7.5");
+        assert_eq!(km.render_pace(PaceStyle::Split, speed), "2:30");
+        assert_eq!(mi.render_pace(PaceStyle::Split, speed), "2:30");
+        assert_eq!(
+            DistanceUnit::Meters.render_pace(PaceStyle::Swim, speed),
+            "30"
+        );
+        assert_eq!(
+            DistanceUnit::Yards.render_pace(PaceStyle::Swim, speed),
+            "27"
+        );
+    }
+
+    #[test]
+    fn it_uses_the_units_for_each_quantity() {
+        let units = UnitSystem::UK;
+        assert_eq!(units.render_mass(80. * si::KG), "12 st 8.4 lb");
+        assert_eq!(units.render_load(80. * si::KG), "80.00");
+        assert_eq!(units.render_distance(5000. * si::M), "3.11");
+        assert_eq!(
+            units.distance_for(&fitnesstrax_lib::timedistance::ActivityType::Swimming.into()),
+            DistanceUnit::Meters
+        );
+        assert_eq!(
+            units.parse_mass("12 st 7 lb").expect("successful parse"),
+            175. * si::LB
+        );
+        assert_eq!(
+            units.parse_mass("12.5").expect("successful parse"),
+            175. * si::LB
+        );
+        assert_eq!(UnitSystem::try_from("UK").unwrap(), UnitSystem::UK);
+        assert_eq!(String::from(&UnitSystem::USA), "USA");
+        assert_eq!(
+            String::from(&UnitSystem {
+                load: MassUnit::Pounds,
+                ..UnitSystem::UK
+            }),
+            "Custom"
+        );
+    }
+
+    #[test]
+    fn it_parses_explicit_units() {
+        let distance = UnitSystem::SI
+            .parse_distance("5 mi")
+            .expect("successful parse");
+        assert_eq!(distance, 5. * si::MI);
+        let distance = UnitSystem::USA
+            .parse_distance("400m")
+            .expect("successful parse");
+        assert_eq!(distance, 400. * si::M);
+        let distance = UnitSystem::USA
+            .parse_distance("10 km")
+            .expect("successful parse");
+        assert_eq!(distance, 10000. * si::M);
+        let distance = UnitSystem::SI
+            .parse_distance("100 yd")
+            .expect("successful parse");
+        assert_eq!(distance, 100. * si::YD);
+
+        let mass = UnitSystem::USA
+            .parse_mass("80 kg")
+            .expect("successful parse");
+        assert_eq!(mass, 80. * si::KG);
+        let mass = UnitSystem::SI
+            .parse_mass("150lbs")
+            .expect("successful parse");
+        assert_eq!(mass, 150. * si::LB);
+        let mass = UnitSystem::SI
+            .parse_mass("12 st")
+            .expect("successful parse");
+        assert_eq!(mass, 168. * si::LB);
+    }
+
+    #[test]
+    fn it_names_the_bad_token() {
+        match UnitSystem::SI.parse_distance("5 furlongs") {
+            Err(Error::ParseDistanceError(token)) => assert_eq!(token, "furlongs"),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        match UnitSystem::SI.parse_mass("8o kg") {
+            Err(Error::ParseMassError(token)) => assert_eq!(token, "o"),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
 }
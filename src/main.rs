@@ -1,3 +1,4 @@
+extern crate cairo;
 extern crate chrono;
 extern crate chrono_tz;
 extern crate fitnesstrax_lib;
@@ -17,6 +18,7 @@ mod formats;
 mod i18n;
 mod range;
 mod settings;
+mod timezones;
 mod types;
 
 fn main() {
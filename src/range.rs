@@ -6,7 +6,8 @@ extern crate gtk;
 
 use std::collections::HashMap;
 
-use emseries::{Recordable, UniqueId};
+use emseries::{DateTimeTz, Recordable, UniqueId};
+pub use fitnesstrax_lib::timezone::{day_start, local_time, TimezoneDisplay};
 pub use fitnesstrax_lib::{Params, Result, Trax, TraxRecord};
 
 #[derive(Clone, Debug)]
@@ -33,6 +34,17 @@ impl<A> Range<A> {
     */
 }
 
+/// Move a timestamp to another time of the same day in `timezone`, keeping the timezone that
+/// the timestamp was recorded in.
+pub fn set_time(
+    timestamp: &DateTimeTz,
+    timezone: &chrono_tz::Tz,
+    time: chrono::NaiveTime,
+) -> DateTimeTz {
+    let date = timestamp.0.with_timezone(timezone).date().naive_local();
+    DateTimeTz(local_time(timezone, &date.and_time(time)).with_timezone(&timestamp.0.timezone()))
+}
+
 pub fn dates_in_range(
     range: &Range<chrono::Date<chrono_tz::Tz>>,
 ) -> Vec<chrono::Date<chrono_tz::Tz>> {
@@ -46,18 +58,20 @@ pub fn dates_in_range(
     dates
 }
 
+/// Place records on the days of the range, in the timezone of the range or in the timezones that
+/// they were recorded in.
 pub fn group_by_date(
     range: &Range<chrono::Date<chrono_tz::Tz>>,
     records: Vec<(UniqueId, TraxRecord)>,
+    display: TimezoneDisplay,
 ) -> HashMap<chrono::Date<chrono_tz::Tz>, Vec<(UniqueId, TraxRecord)>> {
+    let home = range.start.timezone();
     let mut groups: HashMap<chrono::Date<chrono_tz::Tz>, Vec<(UniqueId, TraxRecord)>> =
         HashMap::new();
     for date in dates_in_range(range) {
-        let compare_against_date = move |target_date| target_date == date;
-
         let recs = records
             .iter()
-            .filter(|r| compare_against_date(r.1.timestamp().0.date()))
+            .filter(|r| display.date(&r.1.timestamp(), &home) == date.naive_local())
             .cloned()
             .collect();
 
@@ -69,9 +83,11 @@ pub fn group_by_date(
 
 #[cfg(test)]
 mod test {
-    use super::{dates_in_range, group_by_date, Range};
-    use chrono::TimeZone;
+    use super::{dates_in_range, group_by_date, set_time, Range, TimezoneDisplay};
+    use chrono::{NaiveDate, NaiveTime, TimeZone, Utc};
     use chrono_tz::America::New_York;
+    use chrono_tz::Asia::Tokyo;
+    use chrono_tz::Europe::London;
     use dimensioned::si::KG;
     use emseries::{DateTimeTz, UniqueId};
     use fitnesstrax_lib::TraxRecord;
@@ -115,7 +131,7 @@ mod test {
             ),
         ];
 
-        let groups = group_by_date(&range, recs);
+        let groups = group_by_date(&range, recs, TimezoneDisplay::Home);
 
         assert_eq!(
             groups.get(&New_York.ymd(2019, 5, 5)).map(|v| v.len()),
@@ -131,6 +147,61 @@ mod test {
         );
     }
 
+    #[test]
+    fn it_groups_records_by_the_timezone_they_were_recorded_in() {
+        let range = Range::new(New_York.ymd(2019, 5, 1), New_York.ymd(2019, 5, 15));
+        // 07:00 on the 5th in Tokyo is still 18:00 on the 4th in New York.
+        let recs = vec![(
+            UniqueId::new(),
+            TraxRecord::weight(DateTimeTz(Tokyo.ymd(2019, 5, 5).and_hms(7, 0, 0)), 57. * KG),
+        )];
+
+        let home = group_by_date(&range, recs.clone(), TimezoneDisplay::Home);
+        assert_eq!(
+            home.get(&New_York.ymd(2019, 5, 4)).map(|v| v.len()),
+            Some(1)
+        );
+        assert_eq!(
+            home.get(&New_York.ymd(2019, 5, 5)).map(|v| v.len()),
+            Some(0)
+        );
+
+        let recorded = group_by_date(&range, recs, TimezoneDisplay::Recorded);
+        assert_eq!(
+            recorded.get(&New_York.ymd(2019, 5, 4)).map(|v| v.len()),
+            Some(0)
+        );
+        assert_eq!(
+            recorded.get(&New_York.ymd(2019, 5, 5)).map(|v| v.len()),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn it_sets_times_that_the_clocks_skip_or_repeat() {
+        // 02:30 never happened in New York on the 10th, so it becomes 03:30.
+        let timestamp = DateTimeTz(New_York.ymd(2019, 3, 10).and_hms(8, 0, 0));
+        let moved = set_time(&timestamp, &New_York, NaiveTime::from_hms(2, 30, 0));
+        assert_eq!(
+            moved.0.naive_local(),
+            NaiveDate::from_ymd(2019, 3, 10).and_hms(3, 30, 0)
+        );
+
+        // 01:30 happened twice on the 3rd, and the first one was still daylight saving time.
+        let timestamp = DateTimeTz(New_York.ymd(2019, 11, 3).and_hms(8, 0, 0));
+        let moved = set_time(&timestamp, &New_York, NaiveTime::from_hms(1, 30, 0));
+        assert_eq!(moved.0, Utc.ymd(2019, 11, 3).and_hms(5, 30, 0));
+
+        // A record from London keeps its timezone when its time gets changed from New York.
+        let timestamp = DateTimeTz(London.ymd(2019, 5, 5).and_hms(12, 0, 0));
+        let moved = set_time(&timestamp, &New_York, NaiveTime::from_hms(9, 0, 0));
+        assert_eq!(moved.0.timezone(), London);
+        assert_eq!(
+            moved.0.naive_local(),
+            NaiveDate::from_ymd(2019, 5, 5).and_hms(14, 0, 0)
+        );
+    }
+
     /*
     #[test]
     fn it_shows_various_dates_and_times() {
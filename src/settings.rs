@@ -1,23 +1,30 @@
 use chrono_tz::Tz;
-use std::convert::TryFrom;
 use unic_langid::LanguageIdentifier;
 
 use crate::config::Configuration;
 use crate::i18n::{Text, UnitSystem};
+use crate::range::TimezoneDisplay;
 
 #[derive(Clone, Debug)]
 pub struct Settings {
     pub timezone: Tz,
+    pub timezone_display: TimezoneDisplay,
     pub units: UnitSystem,
     pub text: Text,
 }
 
 impl Settings {
-    pub fn new(langid: LanguageIdentifier, units: UnitSystem, timezone: chrono_tz::Tz) -> Settings {
+    pub fn new(
+        langid: LanguageIdentifier,
+        units: UnitSystem,
+        timezone: chrono_tz::Tz,
+        timezone_display: TimezoneDisplay,
+    ) -> Settings {
         let text = Text::new(langid);
 
         Settings {
             timezone,
+            timezone_display,
             units,
             text,
         }
@@ -28,6 +35,7 @@ impl Settings {
             (&config.language).into(),
             config.units.clone(),
             config.timezone,
+            config.timezone_display,
         )
     }
 
@@ -36,11 +44,15 @@ impl Settings {
         self.text = Text::new(langid);
     }
 
-    pub fn set_units(&mut self, units_str: &str) {
-        self.units = UnitSystem::try_from(units_str).expect("invalid unit system identifier");
+    pub fn set_units(&mut self, units: UnitSystem) {
+        self.units = units;
     }
 
     pub fn set_timezone(&mut self, timezone: chrono_tz::Tz) {
         self.timezone = timezone;
     }
+
+    pub fn set_timezone_display(&mut self, timezone_display: TimezoneDisplay) {
+        self.timezone_display = timezone_display;
+    }
 }